@@ -12,22 +12,155 @@ use bindings::windows::win32::windows_and_messaging::{
     SHOW_WINDOW_CMD, WINDOWS_EX_STYLE, WINDOWS_STYLE, WM_DESTROY, WM_QUIT, WNDCLASSA,
     WNDCLASS_STYLES, WPARAM,
 };
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_4;
 use std::ffi::OsStr;
 use std::os::windows::ffi::OsStrExt;
 
-use flower_box::cube::Vertex;
-use flower_box::GraphicsDevice;
-use flower_box::{draw, upload_mesh};
-use windows::{Abi, Interface};
+use flower_box::camera::Camera;
+use flower_box::capture::CaptureGraphicsDevice;
+use flower_box::constant::{self, ConstantBuffer, DrawConstants};
+use flower_box::cube;
+use flower_box::debug_draw::DebugDraw;
+use flower_box::instance::{self, Instance};
+use flower_box::math::{Mat4, Vec3};
+use flower_box::pipeline::{BlendMode, CullMode, FillMode, PipelineState, Topology};
+use flower_box::resource::{self, Pool};
+use flower_box::vertex::{VertexFormat, VertexLayout, VertexSemantic};
+use flower_box::{draw, render_frame, upload_mesh, upload_wireframe};
+use flower_box::{
+    ConstantBufferHandle, IndexBufferHandle, InstanceBufferHandle, PipelineStateHandle,
+    VertexBufferHandle,
+};
+use flower_box::{Error, GraphicsDevice, Result};
+use windows::{Abi, ErrorCode, Interface};
 
 const WIDTH: i32 = 1920;
 const HEIGHT: i32 = 1080;
+const CLEAR_COLOR: [f32; 4] = [0.1, 0.1, 0.15, 1.0];
+const GRID_COLOR: [f32; 4] = [0.4, 0.4, 0.45, 1.0];
+
+const DXGI_ERROR_DEVICE_REMOVED: u32 = 0x887A_0005;
+const DXGI_ERROR_DEVICE_HUNG: u32 = 0x887A_0006;
+const DXGI_ERROR_DEVICE_RESET: u32 = 0x887A_0007;
+
+fn to_error(error_code: ErrorCode, error: fn(String) -> Error) -> Error {
+    match error_code.0 {
+        DXGI_ERROR_DEVICE_REMOVED | DXGI_ERROR_DEVICE_HUNG | DXGI_ERROR_DEVICE_RESET => {
+            Error::DeviceLost
+        }
+        _ => error(error_code.message()),
+    }
+}
+
+struct VertexBuffer {
+    buffer: ID3D11Buffer,
+    layout: VertexLayout,
+    count: u32,
+}
+
+struct IndexBuffer {
+    buffer: ID3D11Buffer,
+    count: u32,
+}
+
+struct ConstantBufferResource {
+    buffer: ID3D11Buffer,
+    size: usize,
+}
+
+struct InstanceBuffer {
+    buffer: ID3D11Buffer,
+    capacity: u32,
+}
+
+struct PipelineStateObjects {
+    depth_stencil_state: ID3D11DepthStencilState,
+    rasterizer_state: ID3D11RasterizerState,
+    blend_state: ID3D11BlendState,
+    topology: D3D_PRIMITIVE_TOPOLOGY,
+}
+
+struct VertexShader {
+    shader: ID3D11VertexShader,
+    blob: ID3DBlob,
+}
+
+impl VertexShader {
+    /// Compiles `VS` or `VSInstanced`, with `VERTEX_COLOR` defined when the
+    /// vertex layout has colours to multiply in.
+    unsafe fn compile(
+        device: &ID3D11Device,
+        shader_name: &mut [u16],
+        instanced: bool,
+        colored: bool,
+    ) -> Option<VertexShader> {
+        let entry_point: &[u8] = if instanced { b"VSInstanced\0" } else { b"VS\0" };
+        let mut defines = Vec::new();
+        if colored {
+            defines.push(D3D_SHADER_MACRO {
+                name: PSTR(b"VERTEX_COLOR\0".as_ptr() as _),
+                definition: PSTR(b"1\0".as_ptr() as _),
+            });
+        }
+        defines.push(D3D_SHADER_MACRO {
+            name: PSTR(std::ptr::null_mut()),
+            definition: PSTR(std::ptr::null_mut()),
+        });
+
+        let mut blob: Option<ID3DBlob> = None;
+        let mut error_messages: Option<ID3DBlob> = None;
+        let error_code = D3DCompileFromFile(
+            PWSTR(shader_name.as_mut_ptr()),
+            defines.as_ptr(),
+            None,
+            PSTR(entry_point.as_ptr() as _),
+            PSTR(b"vs_5_0\0".as_ptr() as _),
+            D3DCOMPILE_DEBUG | D3DCOMPILE_SKIP_OPTIMIZATION,
+            0,
+            &mut blob,
+            &mut error_messages,
+        );
+        if error_code.is_err() {
+            panic!(error_code.message());
+        }
+
+        let blob = blob?;
+
+        let mut shader: Option<ID3D11VertexShader> = None;
+        let error_code = device.CreateVertexShader(
+            blob.GetBufferPointer(),
+            blob.GetBufferSize(),
+            None,
+            &mut shader,
+        );
+        if error_code.is_err() {
+            panic!(error_code.message());
+        }
+
+        Some(VertexShader {
+            shader: shader?,
+            blob,
+        })
+    }
+}
 
 struct DirectX11GraphicsDevice {
     device: ID3D11Device,
     device_context: ID3D11DeviceContext,
     swapchain: IDXGISwapChain,
     backbuffer_rtv: ID3D11RenderTargetView,
+    depth_stencil_view: ID3D11DepthStencilView,
+    // Keyed by whether the shader reads per-instance data and vertex colours.
+    vertex_shaders: HashMap<(bool, bool), VertexShader>,
+    // Keyed by vertex layout and whether per-instance data follows in slot 1.
+    input_layouts: HashMap<(VertexLayout, bool), ID3D11InputLayout>,
+    vertex_buffers: Pool<resource::VertexBuffer, VertexBuffer>,
+    index_buffers: Pool<resource::IndexBuffer, IndexBuffer>,
+    constant_buffers: Pool<resource::ConstantBuffer, ConstantBufferResource>,
+    instance_buffers: Pool<resource::InstanceBuffer, InstanceBuffer>,
+    pipeline_states: Pool<resource::PipelineState, PipelineStateObjects>,
+    in_frame: bool,
 }
 
 impl DirectX11GraphicsDevice {
@@ -102,37 +235,6 @@ impl DirectX11GraphicsDevice {
 
             device_context.RSSetViewports(1, &view_port);
 
-            let depth_stencil_desc = D3D11_DEPTH_STENCIL_DESC {
-                depth_enable: BOOL::from(true),
-                depth_write_mask: D3D11_DEPTH_WRITE_MASK::D3D11_DEPTH_WRITE_MASK_ALL,
-                depth_func: D3D11_COMPARISON_FUNC::D3D11_COMPARISON_LESS_EQUAL,
-                // Stencil Text
-                stencil_enable: BOOL::from(false),
-                stencil_read_mask: 0xFF,
-                stencil_write_mask: 0xFF,
-                front_face: D3D11_DEPTH_STENCILOP_DESC {
-                    stencil_fail_op: D3D11_STENCIL_OP::D3D11_STENCIL_OP_KEEP,
-                    stencil_depth_fail_op: D3D11_STENCIL_OP::D3D11_STENCIL_OP_INCR,
-                    stencil_pass_op: D3D11_STENCIL_OP::D3D11_STENCIL_OP_KEEP,
-                    stencil_func: D3D11_COMPARISON_FUNC::D3D11_COMPARISON_ALWAYS,
-                },
-                back_face: D3D11_DEPTH_STENCILOP_DESC {
-                    stencil_fail_op: D3D11_STENCIL_OP::D3D11_STENCIL_OP_KEEP,
-                    stencil_depth_fail_op: D3D11_STENCIL_OP::D3D11_STENCIL_OP_DECR,
-                    stencil_pass_op: D3D11_STENCIL_OP::D3D11_STENCIL_OP_KEEP,
-                    stencil_func: D3D11_COMPARISON_FUNC::D3D11_COMPARISON_ALWAYS,
-                },
-            };
-
-            let mut depth_stencil_state: Option<ID3D11DepthStencilState> = None;
-            let error_code =
-                device.CreateDepthStencilState(&depth_stencil_desc, &mut depth_stencil_state);
-            if error_code.is_err() {
-                panic!(error_code.message());
-            }
-
-            device_context.OMSetDepthStencilState(&depth_stencil_state, 1);
-
             let depth_texture_desc = D3D11_TEXTURE2D_DESC {
                 width: WIDTH as u32,
                 height: HEIGHT as u32,
@@ -183,42 +285,21 @@ impl DirectX11GraphicsDevice {
             device_context.OMSetRenderTargets(1, &mut backbuffer_rtv, &depth_stencil_view);
 
             let backbuffer_rtv = backbuffer_rtv?;
+            let depth_stencil_view = depth_stencil_view?;
 
             let mut shader_name: Vec<u16> = OsStr::new("src/shader.hlsl").encode_wide().collect();
             shader_name.push(0); // null terminate
 
-            let mut vertex_blob: Option<ID3DBlob> = None;
-            let mut error_messages: Option<ID3DBlob> = None;
-            let error_code = D3DCompileFromFile(
-                PWSTR(shader_name.as_mut_ptr()),
-                std::ptr::null(),
-                None,
-                PSTR(b"VS\0".as_ptr() as _),
-                PSTR(b"vs_5_0\0".as_ptr() as _),
-                D3DCOMPILE_DEBUG | D3DCOMPILE_SKIP_OPTIMIZATION,
-                0,
-                &mut vertex_blob,
-                &mut error_messages,
-            );
-            if error_code.is_err() {
-                panic!(error_code.message());
-            }
-
-            let vertex_blob = vertex_blob?;
-
-            let mut vertex_shader: Option<ID3D11VertexShader> = None;
-            let error_code = device.CreateVertexShader(
-                vertex_blob.GetBufferPointer(),
-                vertex_blob.GetBufferSize(),
-                None,
-                &mut vertex_shader,
-            );
-            if error_code.is_err() {
-                panic!(error_code.message());
+            let mut vertex_shaders = HashMap::new();
+            for &instanced in [false, true].iter() {
+                for &colored in [false, true].iter() {
+                    let vertex_shader =
+                        VertexShader::compile(&device, &mut shader_name, instanced, colored)?;
+                    vertex_shaders.insert((instanced, colored), vertex_shader);
+                }
             }
 
-            device_context.VSSetShader(vertex_shader, std::ptr::null_mut(), 0);
-
+            let mut error_messages: Option<ID3DBlob> = None;
             let mut pixel_blob: Option<ID3DBlob> = None;
             let error_code = D3DCompileFromFile(
                 PWSTR(shader_name.as_mut_ptr()),
@@ -250,69 +331,209 @@ impl DirectX11GraphicsDevice {
 
             device_context.PSSetShader(pixel_shader, std::ptr::null_mut(), 0);
 
-            let input_layout_desc = D3D11_INPUT_ELEMENT_DESC {
-                semantic_name: PSTR(b"POSITION\0".as_ptr() as _),
+            let mut graphics_device = DirectX11GraphicsDevice {
+                device,
+                device_context,
+                swapchain,
+                backbuffer_rtv,
+                depth_stencil_view,
+                vertex_shaders,
+                input_layouts: HashMap::new(),
+                vertex_buffers: Pool::new(),
+                index_buffers: Pool::new(),
+                constant_buffers: Pool::new(),
+                instance_buffers: Pool::new(),
+                pipeline_states: Pool::new(),
+                in_frame: false,
+            };
+
+            let default_state = graphics_device
+                .create_pipeline_state(&PipelineState::default())
+                .ok()?;
+            graphics_device.bind_pipeline_state(default_state).ok()?;
+            Some(graphics_device)
+        }
+    }
+}
+
+fn semantic_name(semantic: VertexSemantic) -> PSTR {
+    let name: &[u8] = match semantic {
+        VertexSemantic::Position => b"POSITION\0",
+        VertexSemantic::Normal => b"NORMAL\0",
+        VertexSemantic::TexCoord => b"TEXCOORD\0",
+        VertexSemantic::Color => b"COLOR\0",
+    };
+    PSTR(name.as_ptr() as _)
+}
+
+fn dxgi_format(format: VertexFormat) -> DXGI_FORMAT {
+    match format {
+        VertexFormat::Float32x2 => DXGI_FORMAT::DXGI_FORMAT_R32G32_FLOAT,
+        VertexFormat::Float32x3 => DXGI_FORMAT::DXGI_FORMAT_R32G32B32_FLOAT,
+        VertexFormat::Float32x4 => DXGI_FORMAT::DXGI_FORMAT_R32G32B32A32_FLOAT,
+    }
+}
+
+impl DirectX11GraphicsDevice {
+    fn create_input_layout(&mut self, layout: &VertexLayout, instanced: bool) -> Result<()> {
+        let key = (layout.clone(), instanced);
+        if self.input_layouts.contains_key(&key) {
+            return Ok(());
+        }
+
+        let mut input_element_descs: Vec<D3D11_INPUT_ELEMENT_DESC> = layout
+            .attributes()
+            .iter()
+            .map(|attribute| D3D11_INPUT_ELEMENT_DESC {
+                semantic_name: semantic_name(attribute.semantic),
                 semantic_index: 0,
-                format: DXGI_FORMAT::DXGI_FORMAT_R32G32B32_FLOAT,
+                format: dxgi_format(attribute.format),
                 input_slot: 0,
-                aligned_byte_offset: 0,
+                aligned_byte_offset: attribute.offset,
                 input_slot_class: D3D11_INPUT_CLASSIFICATION::D3D11_INPUT_PER_VERTEX_DATA,
                 instance_data_step_rate: 0,
-            };
-            let mut input_layout: Option<ID3D11InputLayout> = None;
-            let error_code = device.CreateInputLayout(
-                &input_layout_desc,
-                1,
+            })
+            .collect();
+
+        if instanced {
+            input_element_descs.extend(instance_element_descs());
+        }
+        let colored = layout.contains(VertexSemantic::Color);
+        let vertex_blob = &self.vertex_shaders[&(instanced, colored)].blob;
+
+        let mut input_layout: Option<ID3D11InputLayout> = None;
+        unsafe {
+            let error_code = self.device.CreateInputLayout(
+                input_element_descs.as_ptr(),
+                input_element_descs.len() as u32,
                 vertex_blob.GetBufferPointer(),
                 vertex_blob.GetBufferSize(),
                 &mut input_layout,
             );
             if error_code.is_err() {
-                panic!(error_code.message());
+                return Err(to_error(error_code, Error::InvalidVertexData));
             }
+        }
 
-            device_context.IASetInputLayout(&input_layout);
+        let input_layout = input_layout
+            .ok_or_else(|| Error::InvalidVertexData("no input layout returned".into()))?;
+        self.input_layouts.insert(key, input_layout);
+        Ok(())
+    }
 
-            device_context.IASetPrimitiveTopology(
-                D3D_PRIMITIVE_TOPOLOGY::D3D10_PRIMITIVE_TOPOLOGY_TRIANGLELIST,
-            );
+    fn draw_indexed(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+        instances: Option<(InstanceBufferHandle, u32)>,
+    ) -> Result<()> {
+        if !self.in_frame {
+            return Err(Error::NoFrameInProgress);
+        }
+        let vertex_buffer = self
+            .vertex_buffers
+            .get(vertex_buffer)
+            .ok_or(Error::InvalidHandle)?;
+        let index_buffer = self
+            .index_buffers
+            .get(index_buffer)
+            .ok_or(Error::InvalidHandle)?;
 
-            let rasterizer_desc = D3D11_RASTERIZER_DESC {
-                fill_mode: D3D11_FILL_MODE::D3D11_FILL_SOLID,
-                cull_mode: D3D11_CULL_MODE::D3D11_CULL_NONE,
-                ..Default::default()
-            };
+        if num_indices > index_buffer.count {
+            return Err(Error::InvalidIndexRange {
+                requested: num_indices,
+                available: index_buffer.count,
+            });
+        }
 
-            let mut rasterizer_state: Option<ID3D11RasterizerState> = None;
-            let error_code = device.CreateRasterizerState(&rasterizer_desc, &mut rasterizer_state);
-            if error_code.is_err() {
-                panic!(error_code.message());
+        let instance_buffer = match instances {
+            Some((handle, num_instances)) => {
+                let instance_buffer = self
+                    .instance_buffers
+                    .get(handle)
+                    .ok_or(Error::InvalidHandle)?;
+                instance::validate_range(instance_buffer.capacity, num_instances)?;
+                Some((instance_buffer, num_instances))
             }
+            None => None,
+        };
 
-            device_context.RSSetState(&rasterizer_state);
+        let key = (vertex_buffer.layout.clone(), instance_buffer.is_some());
+        let input_layout = &self.input_layouts[&key];
+        let colored = vertex_buffer.layout.contains(VertexSemantic::Color);
+        let vertex_shader = &self.vertex_shaders[&(instance_buffer.is_some(), colored)].shader;
 
-            Some(DirectX11GraphicsDevice {
-                device,
-                device_context,
-                swapchain,
-                backbuffer_rtv,
-            })
+        unsafe {
+            self.device_context
+                .VSSetShader(Some(vertex_shader.clone()), std::ptr::null_mut(), 0);
+            self.device_context.IASetInputLayout(input_layout);
+
+            let mut buffer = Some(vertex_buffer.buffer.clone());
+            let stride = vertex_buffer.layout.stride();
+            let p_offsets = 0;
+            self.device_context
+                .IASetVertexBuffers(0, 1, &mut buffer, &stride, &p_offsets);
+            self.device_context.IASetIndexBuffer(
+                Some(index_buffer.buffer.clone()),
+                DXGI_FORMAT::DXGI_FORMAT_R32_UINT,
+                0,
+            );
+
+            match instance_buffer {
+                Some((instance_buffer, num_instances)) => {
+                    let mut buffer = Some(instance_buffer.buffer.clone());
+                    let stride = Instance::SIZE;
+                    self.device_context
+                        .IASetVertexBuffers(1, 1, &mut buffer, &stride, &p_offsets);
+                    self.device_context
+                        .DrawIndexedInstanced(num_indices, num_instances, 0, 0, 0);
+                }
+                None => self.device_context.DrawIndexed(num_indices, 0, 0),
+            }
         }
+        Ok(())
     }
 }
 
+fn instance_element_descs() -> Vec<D3D11_INPUT_ELEMENT_DESC> {
+    let element = |name: &'static [u8], semantic_index: u32| D3D11_INPUT_ELEMENT_DESC {
+        semantic_name: PSTR(name.as_ptr() as _),
+        semantic_index,
+        format: DXGI_FORMAT::DXGI_FORMAT_R32G32B32A32_FLOAT,
+        input_slot: 1,
+        aligned_byte_offset: D3D11_APPEND_ALIGNED_ELEMENT,
+        input_slot_class: D3D11_INPUT_CLASSIFICATION::D3D11_INPUT_PER_INSTANCE_DATA,
+        instance_data_step_rate: 1,
+    };
+
+    vec![
+        element(b"INSTANCE_TRANSFORM\0", 0),
+        element(b"INSTANCE_TRANSFORM\0", 1),
+        element(b"INSTANCE_TRANSFORM\0", 2),
+        element(b"INSTANCE_TRANSFORM\0", 3),
+        element(b"INSTANCE_TINT\0", 0),
+    ]
+}
+
 impl GraphicsDevice for DirectX11GraphicsDevice {
-    fn set_vertex_buffer(&self, vertices: &[Vertex]) {
-        let vertex_size = 3 * std::mem::size_of::<f32>() as u32;
+    fn create_vertex_buffer(
+        &mut self,
+        data: &[u8],
+        layout: &VertexLayout,
+    ) -> Result<VertexBufferHandle> {
+        let count = layout.vertex_count(data)?;
+        self.create_input_layout(layout, false)?;
+        self.create_input_layout(layout, true)?;
+
         let buffer_desc = D3D11_BUFFER_DESC {
-            byte_width: vertex_size * vertices.len() as u32,
+            byte_width: data.len() as u32,
             usage: D3D11_USAGE::D3D11_USAGE_DEFAULT,
             bind_flags: D3D11_BIND_FLAG::D3D11_BIND_VERTEX_BUFFER.0 as u32,
-            //bind_flags: 1,
             ..Default::default()
         };
         let buffer_subresource_data = D3D11_SUBRESOURCE_DATA {
-            p_sys_mem: vertices.as_ptr() as _,
+            p_sys_mem: data.as_ptr() as _,
             sys_mem_pitch: 0,
             sys_mem_slice_pitch: 0,
         };
@@ -322,15 +543,19 @@ impl GraphicsDevice for DirectX11GraphicsDevice {
                 self.device
                     .CreateBuffer(&buffer_desc, &buffer_subresource_data, &mut buffer);
             if error_code.is_err() {
-                panic!(error_code.message())
+                return Err(to_error(error_code, Error::BufferCreation));
             }
-
-            let p_offsets = 0;
-            self.device_context
-                .IASetVertexBuffers(0, 1, &mut buffer, &vertex_size, &p_offsets);
         }
+
+        let buffer = buffer.ok_or_else(|| Error::BufferCreation("no buffer returned".into()))?;
+        Ok(self.vertex_buffers.insert(VertexBuffer {
+            buffer,
+            layout: layout.clone(),
+            count,
+        }))
     }
-    fn set_index_buffer(&self, indices: &[u32]) {
+
+    fn create_index_buffer(&mut self, indices: &[u32]) -> Result<IndexBufferHandle> {
         let index_size = std::mem::size_of::<u32>() as u32;
         let buffer_desc = D3D11_BUFFER_DESC {
             byte_width: index_size * indices.len() as u32,
@@ -350,22 +575,390 @@ impl GraphicsDevice for DirectX11GraphicsDevice {
                 self.device
                     .CreateBuffer(&buffer_desc, &buffer_subresource_data, &mut buffer);
             if error_code.is_err() {
-                panic!(error_code.message());
+                return Err(to_error(error_code, Error::BufferCreation));
+            }
+        }
+
+        let buffer = buffer.ok_or_else(|| Error::BufferCreation("no buffer returned".into()))?;
+        Ok(self.index_buffers.insert(IndexBuffer {
+            buffer,
+            count: indices.len() as u32,
+        }))
+    }
+
+    fn update_vertex_buffer(&mut self, handle: VertexBufferHandle, data: &[u8]) -> Result<()> {
+        let vertex_buffer = self
+            .vertex_buffers
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        vertex_buffer
+            .layout
+            .validate_update(vertex_buffer.count, data)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let destination = D3D11_BOX {
+            left: 0,
+            top: 0,
+            front: 0,
+            right: data.len() as u32,
+            bottom: 1,
+            back: 1,
+        };
+        unsafe {
+            self.device_context.UpdateSubresource(
+                &vertex_buffer.buffer,
+                0,
+                &destination,
+                data.as_ptr() as _,
+                0,
+                0,
+            );
+        }
+        Ok(())
+    }
+
+    fn destroy_vertex_buffer(&mut self, handle: VertexBufferHandle) -> Result<()> {
+        self.vertex_buffers
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn destroy_index_buffer(&mut self, handle: IndexBufferHandle) -> Result<()> {
+        self.index_buffers
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn create_constant_buffer(&mut self, data: &[u8]) -> Result<ConstantBufferHandle> {
+        constant::validate(data)?;
+
+        let buffer_desc = D3D11_BUFFER_DESC {
+            byte_width: data.len() as u32,
+            usage: D3D11_USAGE::D3D11_USAGE_DEFAULT,
+            bind_flags: D3D11_BIND_FLAG::D3D11_BIND_CONSTANT_BUFFER.0 as u32,
+            ..Default::default()
+        };
+        let buffer_subresource_data = D3D11_SUBRESOURCE_DATA {
+            p_sys_mem: data.as_ptr() as _,
+            sys_mem_pitch: 0,
+            sys_mem_slice_pitch: 0,
+        };
+        let mut buffer: Option<ID3D11Buffer> = None;
+        unsafe {
+            let error_code =
+                self.device
+                    .CreateBuffer(&buffer_desc, &buffer_subresource_data, &mut buffer);
+            if error_code.is_err() {
+                return Err(to_error(error_code, Error::BufferCreation));
+            }
+        }
+
+        let buffer = buffer.ok_or_else(|| Error::BufferCreation("no buffer returned".into()))?;
+        Ok(self.constant_buffers.insert(ConstantBufferResource {
+            buffer,
+            size: data.len(),
+        }))
+    }
+
+    fn update_constant_buffer(&mut self, handle: ConstantBufferHandle, data: &[u8]) -> Result<()> {
+        let constant_buffer = self
+            .constant_buffers
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        constant::validate_update(constant_buffer.size, data)?;
+
+        unsafe {
+            self.device_context.UpdateSubresource(
+                &constant_buffer.buffer,
+                0,
+                std::ptr::null(),
+                data.as_ptr() as _,
+                0,
+                0,
+            );
+        }
+        Ok(())
+    }
+
+    fn destroy_constant_buffer(&mut self, handle: ConstantBufferHandle) -> Result<()> {
+        self.constant_buffers
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn bind_constant_buffer(&mut self, slot: u32, handle: ConstantBufferHandle) -> Result<()> {
+        constant::validate_slot(slot)?;
+        let constant_buffer = self
+            .constant_buffers
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+
+        unsafe {
+            let mut buffer = Some(constant_buffer.buffer.clone());
+            self.device_context
+                .VSSetConstantBuffers(slot, 1, &mut buffer);
+            self.device_context
+                .PSSetConstantBuffers(slot, 1, &mut buffer);
+        }
+        Ok(())
+    }
+
+    fn create_instance_buffer(&mut self, instances: &[Instance]) -> Result<InstanceBufferHandle> {
+        instance::validate(instances)?;
+
+        let buffer_desc = D3D11_BUFFER_DESC {
+            byte_width: Instance::SIZE * instances.len() as u32,
+            usage: D3D11_USAGE::D3D11_USAGE_DEFAULT,
+            bind_flags: D3D11_BIND_FLAG::D3D11_BIND_VERTEX_BUFFER.0 as u32,
+            ..Default::default()
+        };
+        let buffer_subresource_data = D3D11_SUBRESOURCE_DATA {
+            p_sys_mem: instances.as_ptr() as _,
+            sys_mem_pitch: 0,
+            sys_mem_slice_pitch: 0,
+        };
+        let mut buffer: Option<ID3D11Buffer> = None;
+        unsafe {
+            let error_code =
+                self.device
+                    .CreateBuffer(&buffer_desc, &buffer_subresource_data, &mut buffer);
+            if error_code.is_err() {
+                return Err(to_error(error_code, Error::BufferCreation));
+            }
+        }
+
+        let buffer = buffer.ok_or_else(|| Error::BufferCreation("no buffer returned".into()))?;
+        Ok(self.instance_buffers.insert(InstanceBuffer {
+            buffer,
+            capacity: instances.len() as u32,
+        }))
+    }
+
+    fn update_instance_buffer(
+        &mut self,
+        handle: InstanceBufferHandle,
+        instances: &[Instance],
+    ) -> Result<()> {
+        let instance_buffer = self
+            .instance_buffers
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        instance::validate_range(instance_buffer.capacity, instances.len() as u32)?;
+        if instances.is_empty() {
+            return Ok(());
+        }
+
+        let destination = D3D11_BOX {
+            left: 0,
+            top: 0,
+            front: 0,
+            right: Instance::SIZE * instances.len() as u32,
+            bottom: 1,
+            back: 1,
+        };
+        unsafe {
+            self.device_context.UpdateSubresource(
+                &instance_buffer.buffer,
+                0,
+                &destination,
+                instances.as_ptr() as _,
+                0,
+                0,
+            );
+        }
+        Ok(())
+    }
+
+    fn destroy_instance_buffer(&mut self, handle: InstanceBufferHandle) -> Result<()> {
+        self.instance_buffers
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn create_pipeline_state(&mut self, state: &PipelineState) -> Result<PipelineStateHandle> {
+        // Direct3D only writes depth when depth is enabled, so depth writes
+        // without a test use an `ALWAYS` comparison instead.
+        let depth_stencil_desc = D3D11_DEPTH_STENCIL_DESC {
+            depth_enable: BOOL::from(state.depth_test || state.depth_write),
+            depth_write_mask: if state.depth_write {
+                D3D11_DEPTH_WRITE_MASK::D3D11_DEPTH_WRITE_MASK_ALL
+            } else {
+                D3D11_DEPTH_WRITE_MASK::D3D11_DEPTH_WRITE_MASK_ZERO
+            },
+            depth_func: if state.depth_test {
+                D3D11_COMPARISON_FUNC::D3D11_COMPARISON_LESS_EQUAL
+            } else {
+                D3D11_COMPARISON_FUNC::D3D11_COMPARISON_ALWAYS
+            },
+            stencil_enable: BOOL::from(false),
+            ..Default::default()
+        };
+
+        let rasterizer_desc = D3D11_RASTERIZER_DESC {
+            fill_mode: match state.fill_mode {
+                FillMode::Solid => D3D11_FILL_MODE::D3D11_FILL_SOLID,
+                FillMode::Wireframe => D3D11_FILL_MODE::D3D11_FILL_WIREFRAME,
+            },
+            cull_mode: match state.cull_mode {
+                CullMode::None => D3D11_CULL_MODE::D3D11_CULL_NONE,
+                CullMode::Front => D3D11_CULL_MODE::D3D11_CULL_FRONT,
+                CullMode::Back => D3D11_CULL_MODE::D3D11_CULL_BACK,
+            },
+            front_counter_clockwise: BOOL::from(false),
+            depth_clip_enable: BOOL::from(true),
+            ..Default::default()
+        };
+
+        // Colour and alpha are blended with the same factors, as in `BlendMode::blend`.
+        let (blend_enable, dest_blend) = match state.blend_mode {
+            BlendMode::Opaque => (false, D3D11_BLEND::D3D11_BLEND_ZERO),
+            BlendMode::Alpha => (true, D3D11_BLEND::D3D11_BLEND_INV_SRC_ALPHA),
+            BlendMode::Additive => (true, D3D11_BLEND::D3D11_BLEND_ONE),
+        };
+        let render_target_blend_desc = D3D11_RENDER_TARGET_BLEND_DESC {
+            blend_enable: BOOL::from(blend_enable),
+            src_blend: D3D11_BLEND::D3D11_BLEND_SRC_ALPHA,
+            dest_blend,
+            blend_op: D3D11_BLEND_OP::D3D11_BLEND_OP_ADD,
+            src_blend_alpha: D3D11_BLEND::D3D11_BLEND_SRC_ALPHA,
+            dest_blend_alpha: dest_blend,
+            blend_op_alpha: D3D11_BLEND_OP::D3D11_BLEND_OP_ADD,
+            render_target_write_mask: D3D11_COLOR_WRITE_ENABLE::D3D11_COLOR_WRITE_ENABLE_ALL.0
+                as u8,
+        };
+        let blend_desc = D3D11_BLEND_DESC {
+            alpha_to_coverage_enable: BOOL::from(false),
+            independent_blend_enable: BOOL::from(false),
+            render_target: [render_target_blend_desc; 8],
+        };
+
+        let topology = match state.topology {
+            Topology::TriangleList => D3D_PRIMITIVE_TOPOLOGY::D3D10_PRIMITIVE_TOPOLOGY_TRIANGLELIST,
+            Topology::LineList => D3D_PRIMITIVE_TOPOLOGY::D3D10_PRIMITIVE_TOPOLOGY_LINELIST,
+        };
+
+        let mut depth_stencil_state: Option<ID3D11DepthStencilState> = None;
+        let mut rasterizer_state: Option<ID3D11RasterizerState> = None;
+        let mut blend_state: Option<ID3D11BlendState> = None;
+        unsafe {
+            let error_code = self
+                .device
+                .CreateDepthStencilState(&depth_stencil_desc, &mut depth_stencil_state);
+            if error_code.is_err() {
+                return Err(to_error(error_code, Error::PipelineStateCreation));
+            }
+            let error_code = self
+                .device
+                .CreateRasterizerState(&rasterizer_desc, &mut rasterizer_state);
+            if error_code.is_err() {
+                return Err(to_error(error_code, Error::PipelineStateCreation));
             }
+            let error_code = self.device.CreateBlendState(&blend_desc, &mut blend_state);
+            if error_code.is_err() {
+                return Err(to_error(error_code, Error::PipelineStateCreation));
+            }
+        }
 
+        let missing = || Error::PipelineStateCreation("no state object returned".into());
+        Ok(self.pipeline_states.insert(PipelineStateObjects {
+            depth_stencil_state: depth_stencil_state.ok_or_else(missing)?,
+            rasterizer_state: rasterizer_state.ok_or_else(missing)?,
+            blend_state: blend_state.ok_or_else(missing)?,
+            topology,
+        }))
+    }
+
+    fn destroy_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()> {
+        self.pipeline_states
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn bind_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()> {
+        let state = self
+            .pipeline_states
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+
+        // The device context holds its own references, so the state stays
+        // bound if the handle is destroyed.
+        unsafe {
             self.device_context
-                .IASetIndexBuffer(buffer, DXGI_FORMAT::DXGI_FORMAT_R32_UINT, 0);
+                .OMSetDepthStencilState(Some(state.depth_stencil_state.clone()), 0);
+            self.device_context
+                .RSSetState(Some(state.rasterizer_state.clone()));
+            self.device_context.OMSetBlendState(
+                Some(state.blend_state.clone()),
+                std::ptr::null(),
+                0xffff_ffff,
+            );
+            self.device_context.IASetPrimitiveTopology(state.topology);
         }
+        Ok(())
     }
 
-    fn draw(&self, num_vertices: u32) {
+    fn begin_frame(&mut self, clear_color: [f32; 4], clear_depth: f32) -> Result<()> {
+        if self.in_frame {
+            return Err(Error::FrameInProgress);
+        }
+        unsafe {
+            self.device_context
+                .ClearRenderTargetView(&self.backbuffer_rtv, clear_color.as_ptr());
+            self.device_context.ClearDepthStencilView(
+                &self.depth_stencil_view,
+                D3D11_CLEAR_FLAG::D3D11_CLEAR_DEPTH.0 as u32,
+                clear_depth,
+                0,
+            );
+        }
+        self.in_frame = true;
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        if !self.in_frame {
+            return Err(Error::NoFrameInProgress);
+        }
+        self.in_frame = false;
         unsafe {
-            self.device_context.DrawIndexed(num_vertices, 0, 0);
             let error_code = self.swapchain.Present(1, 0);
             if error_code.is_err() {
-                panic!(error_code.message());
+                return Err(to_error(error_code, Error::PresentFailed));
             }
         }
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+    ) -> Result<()> {
+        self.draw_indexed(vertex_buffer, index_buffer, num_indices, None)
+    }
+
+    fn draw_instanced(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+        instance_buffer: InstanceBufferHandle,
+        num_instances: u32,
+    ) -> Result<()> {
+        self.draw_indexed(
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+            Some((instance_buffer, num_instances)),
+        )
     }
 }
 
@@ -420,10 +1013,63 @@ fn create_window() -> Option<HWND> {
 fn main() {
     let hwnd = create_window().unwrap();
 
-    let graphics_device: Box<dyn GraphicsDevice> =
-        Box::new(DirectX11GraphicsDevice::new(hwnd).unwrap());
+    // `--capture <path>` saves the first frame so it can be replayed elsewhere.
+    let capture_path = std::env::args().skip_while(|arg| arg != "--capture").nth(1);
 
-    upload_mesh(&graphics_device);
+    let mut graphics_device =
+        CaptureGraphicsDevice::new(DirectX11GraphicsDevice::new(hwnd).unwrap());
+    if capture_path.is_none() {
+        graphics_device.stop();
+    }
+
+    // `--wireframe` shows the edges of the subdivided cube instead.
+    let wireframe = std::env::args().any(|arg| arg == "--wireframe");
+
+    let uploaded = if wireframe {
+        upload_wireframe(&mut graphics_device, &cube::generate(9))
+    } else {
+        upload_mesh(&mut graphics_device, &cube::unit())
+    };
+    let gpu_mesh = match uploaded {
+        Ok(gpu_mesh) => gpu_mesh,
+        Err(error) => {
+            eprintln!("failed to upload mesh: {}", error);
+            return;
+        }
+    };
+
+    let mut camera = Camera::perspective(FRAC_PI_4, WIDTH as f32 / HEIGHT as f32, 0.1, 100.0);
+    camera.look_at(Vec3::new(1.5, 1.5, -3.0), Vec3::ZERO, Vec3::Y);
+    let constants = DrawConstants {
+        transform: camera.view_projection(),
+        ..DrawConstants::default()
+    };
+    let constant_buffer = match ConstantBuffer::new(&mut graphics_device, &constants) {
+        Ok(constant_buffer) => constant_buffer,
+        Err(error) => {
+            eprintln!("failed to create draw constants: {}", error);
+            return;
+        }
+    };
+    let mesh_state = if wireframe {
+        PipelineState::lines()
+    } else {
+        PipelineState::default()
+    };
+    let mesh_state = match graphics_device.create_pipeline_state(&mesh_state) {
+        Ok(mesh_state) => mesh_state,
+        Err(error) => {
+            eprintln!("failed to create pipeline state: {}", error);
+            return;
+        }
+    };
+    let mut debug_draw = match DebugDraw::new(&mut graphics_device) {
+        Ok(debug_draw) => debug_draw,
+        Err(error) => {
+            eprintln!("failed to create debug draw: {}", error);
+            return;
+        }
+    };
     unsafe {
         let mut msg: MSG = std::mem::zeroed();
         loop {
@@ -436,9 +1082,32 @@ fn main() {
                 }
             }
 
-            draw(&graphics_device);
-            //graphics_device.device_context.Draw(4, 0);
-            //let _ = graphics_device.swapchain.Present(1, 0);
+            let frame = render_frame(&mut graphics_device, CLEAR_COLOR, |graphics_device| {
+                // The debug draw binds its own constants and state, so rebind
+                // the mesh's every frame.
+                constant_buffer.bind(graphics_device, DrawConstants::SLOT)?;
+                graphics_device.bind_pipeline_state(mesh_state)?;
+                draw(graphics_device, &gpu_mesh)?;
+
+                debug_draw.grid(Vec3::new(0.0, -0.5, 0.0), 4.0, 8, GRID_COLOR);
+                debug_draw.axes(&Mat4::IDENTITY, 1.0);
+                debug_draw.flush(graphics_device, &camera.view_projection())
+            });
+            match frame {
+                Ok(()) => {
+                    if let (Some(path), true) = (&capture_path, graphics_device.is_recording()) {
+                        let capture = graphics_device.stop();
+                        if let Err(error) = std::fs::write(path, capture.encode()) {
+                            eprintln!("failed to write capture to {}: {}", path, error);
+                        }
+                    }
+                }
+                Err(Error::DeviceLost) => {
+                    eprintln!("graphics device lost, exiting");
+                    return;
+                }
+                Err(error) => eprintln!("failed to render frame: {}", error),
+            }
         }
     }
 }
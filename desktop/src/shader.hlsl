@@ -1,18 +1,62 @@
+// Mirrors flower_box::constant::DrawConstants.
+cbuffer DrawConstants : register(b0)
+{
+    float4x4 transform;
+    float4 color;
+};
+
 struct VSIn
 {
     float3 position : POSITION;
+#ifdef VERTEX_COLOR
+    float4 color : COLOR;
+#endif
 };
 
-float4 VS(VSIn input) : SV_Position
+// Mirrors flower_box::instance::Instance, with the transform split into columns.
+struct InstanceIn
 {
-    float4 pos = float4(input.position, 1.0f);
+    float4 transform0 : INSTANCE_TRANSFORM0;
+    float4 transform1 : INSTANCE_TRANSFORM1;
+    float4 transform2 : INSTANCE_TRANSFORM2;
+    float4 transform3 : INSTANCE_TRANSFORM3;
+    float4 tint : INSTANCE_TINT;
+};
 
-    //pos.z += 5.0f;
+struct VSOut
+{
+    float4 position : SV_Position;
+    float4 color : COLOR;
+};
+
+VSOut VS(VSIn input)
+{
+    VSOut output;
+    output.position = mul(transform, float4(input.position, 1.0f));
+    output.color = color;
+#ifdef VERTEX_COLOR
+    output.color *= input.color;
+#endif
+    return output;
+}
+
+VSOut VSInstanced(VSIn input, InstanceIn instance)
+{
+    float4 world = instance.transform0 * input.position.x
+        + instance.transform1 * input.position.y
+        + instance.transform2 * input.position.z
+        + instance.transform3;
 
-    return pos;
+    VSOut output;
+    output.position = mul(transform, world);
+    output.color = color * instance.tint;
+#ifdef VERTEX_COLOR
+    output.color *= input.color;
+#endif
+    return output;
 }
 
-float4 PS() : SV_Target
+float4 PS(VSOut input) : SV_Target
 {
-    return float4(0.8f, 0.8f, 0.3f, 1.0f);
+    return input.color;
 }
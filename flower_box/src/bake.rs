@@ -0,0 +1,220 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::mesh::{Mesh, MeshError};
+use crate::reader::{Reader, UnexpectedEof};
+use crate::vertex::{Vertex, VertexSemantic};
+
+const MAGIC: &[u8; 4] = b"FBMS";
+const FORMAT_VERSION: u32 = 1;
+
+const SEMANTICS: [VertexSemantic; 4] = [
+    VertexSemantic::Position,
+    VertexSemantic::Normal,
+    VertexSemantic::TexCoord,
+    VertexSemantic::Color,
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BakeError {
+    BadMagic,
+    UnsupportedVersion(u32),
+    UnknownVertexAttribute {
+        offset: usize,
+    },
+    UnexpectedEof,
+    TrailingBytes {
+        offset: usize,
+    },
+    /// The geometry decoded but does not form a valid mesh.
+    InvalidMesh(MeshError),
+}
+
+impl fmt::Display for BakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BakeError::BadMagic => write!(f, "not a flower_box baked mesh"),
+            BakeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported baked mesh version {}", version)
+            }
+            BakeError::UnknownVertexAttribute { offset } => {
+                write!(f, "unknown vertex attribute at byte {}", offset)
+            }
+            BakeError::UnexpectedEof => write!(f, "baked mesh ended unexpectedly"),
+            BakeError::TrailingBytes { offset } => {
+                write!(f, "unexpected data after indices at byte {}", offset)
+            }
+            BakeError::InvalidMesh(error) => write!(f, "invalid baked mesh: {}", error),
+        }
+    }
+}
+
+impl Error for BakeError {}
+
+impl UnexpectedEof for BakeError {
+    fn unexpected_eof() -> BakeError {
+        BakeError::UnexpectedEof
+    }
+}
+
+/// Serializes a mesh into the compact form loaded by `decode`.
+///
+/// The little-endian layout is the magic `FBMS`, a format version, the
+/// attribute count and one byte per attribute semantic, the vertex and index
+/// counts, the vertices interleaved as in `Mesh::vertex_data` and finally the
+/// `u32` indices.
+pub fn encode(mesh: &Mesh) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    let attributes = mesh.layout().attributes();
+    bytes.push(attributes.len() as u8);
+    for attribute in attributes {
+        let semantic = SEMANTICS.iter().position(|&s| s == attribute.semantic);
+        bytes.push(semantic.unwrap() as u8);
+    }
+    bytes.extend_from_slice(&(mesh.vertices().len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&(mesh.indices().len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&mesh.vertex_data());
+    for index in mesh.indices() {
+        bytes.extend_from_slice(&index.to_le_bytes());
+    }
+    bytes
+}
+
+/// Parses a baked mesh, checking it with `Mesh::new` and
+/// `Mesh::with_attributes` just like hand-built geometry.
+pub fn decode(bytes: &[u8]) -> Result<Mesh, BakeError> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(BakeError::BadMagic);
+    }
+    let version = reader.u32()?;
+    if version != FORMAT_VERSION {
+        return Err(BakeError::UnsupportedVersion(version));
+    }
+
+    let attribute_count = reader.u8()?;
+    let mut semantics = Vec::with_capacity(attribute_count as usize);
+    for _ in 0..attribute_count {
+        let offset = reader.offset();
+        let semantic = SEMANTICS
+            .get(reader.u8()? as usize)
+            .ok_or(BakeError::UnknownVertexAttribute { offset })?;
+        semantics.push(*semantic);
+    }
+    // Without a position the stride could be zero, and the vertex count would
+    // no longer be bounded by the length of the blob.
+    if !semantics.contains(&VertexSemantic::Position) {
+        return Err(BakeError::InvalidMesh(MeshError::MissingPosition));
+    }
+    let layout = Vertex::layout(&semantics);
+
+    let vertex_count = reader.u32()? as usize;
+    let index_count = reader.u32()? as usize;
+    let data_length = vertex_count
+        .checked_mul(layout.stride() as usize)
+        .ok_or(BakeError::UnexpectedEof)?;
+    let data = reader.take(data_length)?;
+
+    let mut vertices = Vec::with_capacity(vertex_count);
+    for index in 0..vertex_count {
+        let mut vertex = Vertex::new(0.0, 0.0, 0.0);
+        for &semantic in &semantics {
+            let value = layout.read(data, index, semantic).unwrap();
+            match semantic {
+                VertexSemantic::Position => vertex.position.copy_from_slice(&value[..3]),
+                VertexSemantic::Normal => vertex.normal.copy_from_slice(&value[..3]),
+                VertexSemantic::TexCoord => vertex.uv.copy_from_slice(&value[..2]),
+                VertexSemantic::Color => vertex.color = value,
+            }
+        }
+        vertices.push(vertex);
+    }
+
+    let mut indices = Vec::with_capacity(index_count.min(bytes.len() / 4));
+    for _ in 0..index_count {
+        indices.push(reader.u32()?);
+    }
+    if reader.offset() != bytes.len() {
+        return Err(BakeError::TrailingBytes {
+            offset: reader.offset(),
+        });
+    }
+
+    Mesh::new(vertices, indices)
+        .and_then(|mesh| mesh.with_attributes(&semantics))
+        .map_err(BakeError::InvalidMesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::{self, UvLayout};
+    use crate::normals;
+
+    #[test]
+    fn encode_then_decode() {
+        let meshes = [
+            cube::generate(2),
+            normals::flat(&cube::generate_textured(1, UvLayout::Cross)),
+            cube::unit().with_attribute(VertexSemantic::Color),
+        ];
+        for mesh in &meshes {
+            assert_eq!(&decode(&encode(mesh)).unwrap(), mesh);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_data() {
+        let bytes = encode(&cube::generate(1));
+
+        assert_eq!(decode(b"FBCP"), Err(BakeError::BadMagic));
+        let mut version = bytes.clone();
+        version[4] = 9;
+        assert_eq!(decode(&version), Err(BakeError::UnsupportedVersion(9)));
+        let mut attribute = bytes.clone();
+        attribute[9] = 7;
+        assert_eq!(
+            decode(&attribute),
+            Err(BakeError::UnknownVertexAttribute { offset: 9 })
+        );
+        assert_eq!(
+            decode(&bytes[..bytes.len() - 1]),
+            Err(BakeError::UnexpectedEof)
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            decode(&trailing),
+            Err(BakeError::TrailingBytes {
+                offset: bytes.len()
+            })
+        );
+
+        // No attributes and u32::MAX vertices, which must not be allocated.
+        let mut empty = bytes[..8].to_vec();
+        empty.push(0);
+        empty.extend_from_slice(&u32::MAX.to_le_bytes());
+        empty.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(
+            decode(&empty),
+            Err(BakeError::InvalidMesh(MeshError::MissingPosition))
+        );
+        let mut huge = bytes[..10].to_vec();
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        huge.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(decode(&huge), Err(BakeError::UnexpectedEof));
+
+        // The last index now points past the eight cube vertices.
+        let mut index = bytes.clone();
+        let last = index.len() - 4;
+        index[last..].copy_from_slice(&8u32.to_le_bytes());
+        assert!(matches!(
+            decode(&index),
+            Err(BakeError::InvalidMesh(MeshError::IndexOutOfRange {
+                index: 8,
+                ..
+            }))
+        ));
+    }
+}
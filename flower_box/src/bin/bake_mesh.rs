@@ -0,0 +1,49 @@
+//! Converts an OBJ file into the binary mesh format read by `bake::decode`.
+//!
+//! `cargo run --bin bake_mesh assets/cube.obj assets/cube.mesh`
+//!
+//! With `--optimize`, vertices are welded and the indices reordered for the
+//! vertex cache before baking; see `optimize::optimize`.
+
+use std::process;
+
+use flower_box::{bake, obj, optimize};
+
+// Well below the precision OBJ exporters usually write.
+const WELD_EPSILON: f32 = 1e-6;
+
+const USAGE: &str = "usage: bake_mesh [--optimize] <input.obj> <output.mesh>";
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (weld, input, output) = match args {
+        [input, output] => (false, input, output),
+        [flag, input, output] if flag == "--optimize" => (true, input, output),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let source = std::fs::read_to_string(input).map_err(|e| format!("{}: {}", input, e))?;
+    let mut mesh = obj::parse_mesh(&source).map_err(|e| format!("{}: {}", input, e))?;
+    if weld {
+        let (optimized, report) = optimize::optimize(&mesh, WELD_EPSILON);
+        println!("{}: {}", input, report);
+        mesh = optimized;
+    }
+    std::fs::write(output, bake::encode(&mesh)).map_err(|e| format!("{}: {}", output, e))?;
+
+    println!(
+        "{}: {} vertices, {} triangles",
+        output,
+        mesh.vertices().len(),
+        mesh.triangle_count()
+    );
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Err(error) = run(&args) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
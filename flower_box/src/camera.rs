@@ -0,0 +1,210 @@
+use crate::math::{Mat4, Vec3};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// `fov_y` is the full vertical field of view in radians.
+    Perspective { fov_y: f32, near: f32, far: f32 },
+    /// `height` is the height of the view volume in world units; its width
+    /// follows from the camera's aspect ratio.
+    Orthographic { height: f32, near: f32, far: f32 },
+}
+
+/// A viewpoint in a left-handed, y-up world, producing the matrices that map
+/// world space to Direct3D clip space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub eye: Vec3,
+    pub target: Vec3,
+    pub up: Vec3,
+    pub projection: Projection,
+    /// Viewport width divided by height.
+    pub aspect: f32,
+}
+
+impl Camera {
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Camera {
+        Camera::with_projection(Projection::Perspective { fov_y, near, far }, aspect)
+    }
+
+    pub fn orthographic(height: f32, aspect: f32, near: f32, far: f32) -> Camera {
+        Camera::with_projection(Projection::Orthographic { height, near, far }, aspect)
+    }
+
+    // Starts out at -z looking towards the origin.
+    fn with_projection(projection: Projection, aspect: f32) -> Camera {
+        Camera {
+            eye: Vec3::new(0.0, 0.0, -5.0),
+            target: Vec3::ZERO,
+            up: Vec3::Y,
+            projection,
+            aspect,
+        }
+    }
+
+    pub fn look_at(&mut self, eye: Vec3, target: Vec3, up: Vec3) {
+        self.eye = eye;
+        self.target = target;
+        self.up = up;
+    }
+
+    pub fn view(&self) -> Mat4 {
+        Mat4::look_at_lh(self.eye, self.target, self.up)
+    }
+
+    pub fn projection(&self) -> Mat4 {
+        match self.projection {
+            Projection::Perspective { fov_y, near, far } => {
+                Mat4::perspective_lh(fov_y, self.aspect, near, far)
+            }
+            Projection::Orthographic { height, near, far } => {
+                Mat4::orthographic_lh(height * self.aspect, height, near, far)
+            }
+        }
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        self.projection() * self.view()
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera::perspective(FRAC_PI_4, 1.0, 0.1, 100.0)
+    }
+}
+
+/// Orbits a camera around a target point.
+///
+/// Input is given as abstract deltas so any source (mouse drags, gamepad
+/// sticks, scripted animation) can drive it: `rotate` takes radians, `zoom`
+/// a distance multiplier exponent, and `pan` fractions of the view height.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+    /// Rotation about the world up axis. Zero looks along +z.
+    pub yaw: f32,
+    /// Elevation above the horizontal plane, kept short of the poles.
+    pub pitch: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+impl OrbitController {
+    pub fn new(target: Vec3, distance: f32) -> OrbitController {
+        OrbitController {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            min_distance: 0.01,
+            max_distance: f32::INFINITY,
+        }
+    }
+
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = (self.yaw + yaw) % std::f32::consts::TAU;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Positive deltas move the camera closer. Each unit scales the distance
+    /// by the same factor, so zooming feels uniform at any range.
+    pub fn zoom(&mut self, delta: f32) {
+        self.distance =
+            (self.distance * (-delta * 0.1).exp()).clamp(self.min_distance, self.max_distance);
+    }
+
+    /// Moves the target across the view plane, scaled by the current distance
+    /// so the scene tracks the input at any zoom level.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let forward = self.forward();
+        let right = Vec3::Y.cross(forward).normalize();
+        let up = forward.cross(right);
+        self.target += (right * -dx + up * dy) * self.distance;
+    }
+
+    pub fn eye(&self) -> Vec3 {
+        self.target - self.forward() * self.distance
+    }
+
+    fn forward(&self) -> Vec3 {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        Vec3::new(sin_yaw * cos_pitch, -sin_pitch, cos_yaw * cos_pitch)
+    }
+
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.look_at(self.eye(), self.target, Vec3::Y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn target_projects_to_centre_of_screen() {
+        for camera in &mut [
+            Camera::default(),
+            Camera::orthographic(4.0, 1.5, 0.1, 100.0),
+        ] {
+            camera.look_at(Vec3::new(2.0, 3.0, -4.0), Vec3::new(1.0, 0.0, 1.0), Vec3::Y);
+            let clip = camera
+                .view_projection()
+                .transform_point(Vec3::new(1.0, 0.0, 1.0));
+            assert!(clip.x.abs() < 1e-5 && clip.y.abs() < 1e-5);
+            assert!(clip.z > 0.0 && clip.z < 1.0);
+        }
+    }
+
+    #[test]
+    fn orthographic_volume_follows_aspect() {
+        let mut camera = Camera::orthographic(2.0, 2.0, 0.1, 10.0);
+        camera.look_at(Vec3::new(0.0, 0.0, -5.0), Vec3::ZERO, Vec3::Y);
+        let corner = camera
+            .view_projection()
+            .transform_point(Vec3::new(2.0, 1.0, 0.0));
+        assert_near(Vec3::new(corner.x, corner.y, 0.0), Vec3::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn orbit_keeps_distance_and_clamps_pitch() {
+        let target = Vec3::new(1.0, 2.0, 3.0);
+        let mut orbit = OrbitController::new(target, 5.0);
+        assert_near(orbit.eye(), Vec3::new(1.0, 2.0, -2.0));
+
+        orbit.rotate(1.0, 0.5);
+        assert!(((orbit.eye() - target).length() - 5.0).abs() < 1e-4);
+
+        orbit.rotate(0.0, 10.0);
+        assert_eq!(orbit.pitch, MAX_PITCH);
+        assert!(orbit.eye().y > target.y);
+    }
+
+    #[test]
+    fn orbit_zoom_and_pan() {
+        let mut orbit = OrbitController::new(Vec3::ZERO, 5.0);
+        orbit.min_distance = 1.0;
+        orbit.zoom(1.0);
+        assert!(orbit.distance < 5.0);
+        orbit.zoom(1000.0);
+        assert_eq!(orbit.distance, 1.0);
+
+        // Looking along +z, panning right moves the target towards -x so the
+        // scene follows the input.
+        orbit.pan(0.5, 0.0);
+        assert_near(orbit.target, Vec3::new(-0.5, 0.0, 0.0));
+
+        let mut camera = Camera::default();
+        orbit.apply(&mut camera);
+        assert_eq!(camera.target, orbit.target);
+        assert_near(camera.eye, Vec3::new(-0.5, 0.0, -1.0));
+    }
+}
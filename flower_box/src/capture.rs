@@ -0,0 +1,991 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+
+use crate::instance::Instance;
+use crate::math::Mat4;
+use crate::pipeline::{BlendMode, CullMode, FillMode, PipelineState, Topology};
+use crate::reader::{Reader, UnexpectedEof};
+use crate::recording::Command;
+use crate::resource::Handle;
+use crate::vertex::{VertexFormat, VertexLayout, VertexSemantic};
+use crate::{
+    ConstantBufferHandle, Error, GraphicsDevice, IndexBufferHandle, InstanceBufferHandle,
+    PipelineStateHandle, Result, VertexBufferHandle,
+};
+
+const MAGIC: &[u8; 4] = b"FBCP";
+const FORMAT_VERSION: u32 = 8;
+
+const CREATE_VERTEX_BUFFER: u8 = 0;
+const CREATE_INDEX_BUFFER: u8 = 1;
+const DESTROY_VERTEX_BUFFER: u8 = 2;
+const DESTROY_INDEX_BUFFER: u8 = 3;
+const DRAW: u8 = 4;
+const CREATE_CONSTANT_BUFFER: u8 = 5;
+const UPDATE_CONSTANT_BUFFER: u8 = 6;
+const DESTROY_CONSTANT_BUFFER: u8 = 7;
+const BIND_CONSTANT_BUFFER: u8 = 8;
+const CREATE_INSTANCE_BUFFER: u8 = 9;
+const UPDATE_INSTANCE_BUFFER: u8 = 10;
+const DESTROY_INSTANCE_BUFFER: u8 = 11;
+const DRAW_INSTANCED: u8 = 12;
+const BEGIN_FRAME: u8 = 13;
+const END_FRAME: u8 = 14;
+const CREATE_PIPELINE_STATE: u8 = 15;
+const DESTROY_PIPELINE_STATE: u8 = 16;
+const BIND_PIPELINE_STATE: u8 = 17;
+const UPDATE_VERTEX_BUFFER: u8 = 18;
+
+/// A sequence of `GraphicsDevice` calls, including buffer contents, that can be
+/// saved on one machine and replayed against any backend on another.
+///
+/// Captures are stored as a little-endian binary stream: the magic `FBCP`, a
+/// format version, a command count and then one tagged record per command.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Capture {
+    pub commands: Vec<Command>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CaptureError {
+    BadMagic,
+    UnsupportedVersion(u32),
+    UnknownCommand { tag: u8, offset: usize },
+    UnknownVertexAttribute { offset: usize },
+    UnknownPipelineState { offset: usize },
+    UnexpectedEof,
+    TrailingBytes { offset: usize },
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureError::BadMagic => write!(f, "not a flower_box capture"),
+            CaptureError::UnsupportedVersion(version) => {
+                write!(f, "unsupported capture version {}", version)
+            }
+            CaptureError::UnknownCommand { tag, offset } => {
+                write!(f, "unknown command tag {} at byte {}", tag, offset)
+            }
+            CaptureError::UnknownVertexAttribute { offset } => {
+                write!(f, "unknown vertex attribute at byte {}", offset)
+            }
+            CaptureError::UnknownPipelineState { offset } => {
+                write!(f, "unknown pipeline state at byte {}", offset)
+            }
+            CaptureError::UnexpectedEof => write!(f, "capture ended unexpectedly"),
+            CaptureError::TrailingBytes { offset } => {
+                write!(f, "unexpected data after last command at byte {}", offset)
+            }
+        }
+    }
+}
+
+impl error::Error for CaptureError {}
+
+impl UnexpectedEof for CaptureError {
+    fn unexpected_eof() -> CaptureError {
+        CaptureError::UnexpectedEof
+    }
+}
+
+impl Capture {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        write_u32(&mut bytes, FORMAT_VERSION);
+        write_u32(&mut bytes, self.commands.len() as u32);
+
+        for command in &self.commands {
+            match command {
+                Command::CreateVertexBuffer {
+                    handle,
+                    data,
+                    layout,
+                } => {
+                    bytes.push(CREATE_VERTEX_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                    write_layout(&mut bytes, layout);
+                    write_u32(&mut bytes, data.len() as u32);
+                    bytes.extend_from_slice(data);
+                }
+                Command::CreateIndexBuffer { handle, indices } => {
+                    bytes.push(CREATE_INDEX_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                    write_u32(&mut bytes, indices.len() as u32);
+                    for &index in indices {
+                        write_u32(&mut bytes, index);
+                    }
+                }
+                Command::UpdateVertexBuffer { handle, data } => {
+                    bytes.push(UPDATE_VERTEX_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                    write_u32(&mut bytes, data.len() as u32);
+                    bytes.extend_from_slice(data);
+                }
+                Command::DestroyVertexBuffer { handle } => {
+                    bytes.push(DESTROY_VERTEX_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                }
+                Command::DestroyIndexBuffer { handle } => {
+                    bytes.push(DESTROY_INDEX_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                }
+                Command::CreateConstantBuffer { handle, data } => {
+                    bytes.push(CREATE_CONSTANT_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                    write_u32(&mut bytes, data.len() as u32);
+                    bytes.extend_from_slice(data);
+                }
+                Command::UpdateConstantBuffer { handle, data } => {
+                    bytes.push(UPDATE_CONSTANT_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                    write_u32(&mut bytes, data.len() as u32);
+                    bytes.extend_from_slice(data);
+                }
+                Command::DestroyConstantBuffer { handle } => {
+                    bytes.push(DESTROY_CONSTANT_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                }
+                Command::BindConstantBuffer { slot, handle } => {
+                    bytes.push(BIND_CONSTANT_BUFFER);
+                    write_u32(&mut bytes, *slot);
+                    write_handle(&mut bytes, *handle);
+                }
+                Command::CreateInstanceBuffer { handle, instances } => {
+                    bytes.push(CREATE_INSTANCE_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                    write_instances(&mut bytes, instances);
+                }
+                Command::UpdateInstanceBuffer { handle, instances } => {
+                    bytes.push(UPDATE_INSTANCE_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                    write_instances(&mut bytes, instances);
+                }
+                Command::DestroyInstanceBuffer { handle } => {
+                    bytes.push(DESTROY_INSTANCE_BUFFER);
+                    write_handle(&mut bytes, *handle);
+                }
+                Command::CreatePipelineState { handle, state } => {
+                    bytes.push(CREATE_PIPELINE_STATE);
+                    write_handle(&mut bytes, *handle);
+                    write_pipeline_state(&mut bytes, state);
+                }
+                Command::DestroyPipelineState { handle } => {
+                    bytes.push(DESTROY_PIPELINE_STATE);
+                    write_handle(&mut bytes, *handle);
+                }
+                Command::BindPipelineState { handle } => {
+                    bytes.push(BIND_PIPELINE_STATE);
+                    write_handle(&mut bytes, *handle);
+                }
+                Command::BeginFrame {
+                    clear_color,
+                    clear_depth,
+                } => {
+                    bytes.push(BEGIN_FRAME);
+                    for value in clear_color.iter().chain(Some(clear_depth)) {
+                        bytes.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+                Command::EndFrame => bytes.push(END_FRAME),
+                Command::Draw {
+                    vertex_buffer,
+                    index_buffer,
+                    num_indices,
+                } => {
+                    bytes.push(DRAW);
+                    write_handle(&mut bytes, *vertex_buffer);
+                    write_handle(&mut bytes, *index_buffer);
+                    write_u32(&mut bytes, *num_indices);
+                }
+                Command::DrawInstanced {
+                    vertex_buffer,
+                    index_buffer,
+                    num_indices,
+                    instance_buffer,
+                    num_instances,
+                } => {
+                    bytes.push(DRAW_INSTANCED);
+                    write_handle(&mut bytes, *vertex_buffer);
+                    write_handle(&mut bytes, *index_buffer);
+                    write_u32(&mut bytes, *num_indices);
+                    write_handle(&mut bytes, *instance_buffer);
+                    write_u32(&mut bytes, *num_instances);
+                }
+            }
+        }
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> std::result::Result<Capture, CaptureError> {
+        let mut reader = Reader::new(bytes);
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(CaptureError::BadMagic);
+        }
+        let version = reader.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(CaptureError::UnsupportedVersion(version));
+        }
+
+        let command_count = reader.u32()?;
+        let mut commands = Vec::new();
+        for _ in 0..command_count {
+            let offset = reader.offset();
+            let command = match reader.u8()? {
+                CREATE_VERTEX_BUFFER => {
+                    let handle = reader.handle()?;
+                    let layout = reader.layout()?;
+                    let len = reader.u32()?;
+                    let data = reader.take(len as usize)?.to_vec();
+                    Command::CreateVertexBuffer {
+                        handle,
+                        data,
+                        layout,
+                    }
+                }
+                CREATE_INDEX_BUFFER => {
+                    let handle = reader.handle()?;
+                    let count = reader.u32()?;
+                    let mut indices = Vec::new();
+                    for _ in 0..count {
+                        indices.push(reader.u32()?);
+                    }
+                    Command::CreateIndexBuffer { handle, indices }
+                }
+                UPDATE_VERTEX_BUFFER => {
+                    let handle = reader.handle()?;
+                    let len = reader.u32()?;
+                    let data = reader.take(len as usize)?.to_vec();
+                    Command::UpdateVertexBuffer { handle, data }
+                }
+                DESTROY_VERTEX_BUFFER => Command::DestroyVertexBuffer {
+                    handle: reader.handle()?,
+                },
+                DESTROY_INDEX_BUFFER => Command::DestroyIndexBuffer {
+                    handle: reader.handle()?,
+                },
+                CREATE_CONSTANT_BUFFER => {
+                    let handle = reader.handle()?;
+                    let len = reader.u32()?;
+                    let data = reader.take(len as usize)?.to_vec();
+                    Command::CreateConstantBuffer { handle, data }
+                }
+                UPDATE_CONSTANT_BUFFER => {
+                    let handle = reader.handle()?;
+                    let len = reader.u32()?;
+                    let data = reader.take(len as usize)?.to_vec();
+                    Command::UpdateConstantBuffer { handle, data }
+                }
+                DESTROY_CONSTANT_BUFFER => Command::DestroyConstantBuffer {
+                    handle: reader.handle()?,
+                },
+                BIND_CONSTANT_BUFFER => Command::BindConstantBuffer {
+                    slot: reader.u32()?,
+                    handle: reader.handle()?,
+                },
+                CREATE_INSTANCE_BUFFER => Command::CreateInstanceBuffer {
+                    handle: reader.handle()?,
+                    instances: reader.instances()?,
+                },
+                UPDATE_INSTANCE_BUFFER => Command::UpdateInstanceBuffer {
+                    handle: reader.handle()?,
+                    instances: reader.instances()?,
+                },
+                DESTROY_INSTANCE_BUFFER => Command::DestroyInstanceBuffer {
+                    handle: reader.handle()?,
+                },
+                CREATE_PIPELINE_STATE => Command::CreatePipelineState {
+                    handle: reader.handle()?,
+                    state: reader.pipeline_state()?,
+                },
+                DESTROY_PIPELINE_STATE => Command::DestroyPipelineState {
+                    handle: reader.handle()?,
+                },
+                BIND_PIPELINE_STATE => Command::BindPipelineState {
+                    handle: reader.handle()?,
+                },
+                BEGIN_FRAME => Command::BeginFrame {
+                    clear_color: [reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?],
+                    clear_depth: reader.f32()?,
+                },
+                END_FRAME => Command::EndFrame,
+                DRAW => Command::Draw {
+                    vertex_buffer: reader.handle()?,
+                    index_buffer: reader.handle()?,
+                    num_indices: reader.u32()?,
+                },
+                DRAW_INSTANCED => Command::DrawInstanced {
+                    vertex_buffer: reader.handle()?,
+                    index_buffer: reader.handle()?,
+                    num_indices: reader.u32()?,
+                    instance_buffer: reader.handle()?,
+                    num_instances: reader.u32()?,
+                },
+                tag => return Err(CaptureError::UnknownCommand { tag, offset }),
+            };
+            commands.push(command);
+        }
+
+        if reader.offset() != bytes.len() {
+            return Err(CaptureError::TrailingBytes {
+                offset: reader.offset(),
+            });
+        }
+
+        Ok(Capture { commands })
+    }
+}
+
+fn write_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+// Each instance is its transform, column by column, followed by its tint.
+fn write_instances(bytes: &mut Vec<u8>, instances: &[Instance]) {
+    write_u32(bytes, instances.len() as u32);
+    for instance in instances {
+        let values = instance.transform.to_cols_array();
+        for value in values.iter().chain(instance.tint.iter()) {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+fn write_handle<K>(bytes: &mut Vec<u8>, handle: Handle<K>) {
+    write_u32(bytes, handle.index());
+    write_u32(bytes, handle.generation());
+}
+
+const SEMANTICS: [VertexSemantic; 4] = [
+    VertexSemantic::Position,
+    VertexSemantic::Normal,
+    VertexSemantic::TexCoord,
+    VertexSemantic::Color,
+];
+
+const FORMATS: [VertexFormat; 3] = [
+    VertexFormat::Float32x2,
+    VertexFormat::Float32x3,
+    VertexFormat::Float32x4,
+];
+
+// Attributes are stored in order as (semantic, format) pairs; offsets follow from
+// the order because layouts are always tightly packed.
+fn write_layout(bytes: &mut Vec<u8>, layout: &VertexLayout) {
+    bytes.push(layout.attributes().len() as u8);
+    for attribute in layout.attributes() {
+        let semantic = SEMANTICS.iter().position(|&s| s == attribute.semantic);
+        let format = FORMATS.iter().position(|&f| f == attribute.format);
+        bytes.push(semantic.unwrap() as u8);
+        bytes.push(format.unwrap() as u8);
+    }
+}
+
+const CULL_MODES: [CullMode; 3] = [CullMode::None, CullMode::Front, CullMode::Back];
+
+const FILL_MODES: [FillMode; 2] = [FillMode::Solid, FillMode::Wireframe];
+
+const BLEND_MODES: [BlendMode; 3] = [BlendMode::Opaque, BlendMode::Alpha, BlendMode::Additive];
+
+const TOPOLOGIES: [Topology; 2] = [Topology::TriangleList, Topology::LineList];
+
+// One byte per field, with enums stored as their index in the tables above.
+fn write_pipeline_state(bytes: &mut Vec<u8>, state: &PipelineState) {
+    bytes.push(state.depth_test as u8);
+    bytes.push(state.depth_write as u8);
+    bytes.push(
+        CULL_MODES
+            .iter()
+            .position(|&m| m == state.cull_mode)
+            .unwrap() as u8,
+    );
+    bytes.push(
+        FILL_MODES
+            .iter()
+            .position(|&m| m == state.fill_mode)
+            .unwrap() as u8,
+    );
+    bytes.push(
+        BLEND_MODES
+            .iter()
+            .position(|&m| m == state.blend_mode)
+            .unwrap() as u8,
+    );
+    bytes.push(
+        TOPOLOGIES
+            .iter()
+            .position(|&t| t == state.topology)
+            .unwrap() as u8,
+    );
+}
+
+fn read_pipeline_state(fields: &[u8]) -> Option<PipelineState> {
+    let flag = |value: u8| match value {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    };
+    Some(PipelineState {
+        depth_test: flag(fields[0])?,
+        depth_write: flag(fields[1])?,
+        cull_mode: *CULL_MODES.get(fields[2] as usize)?,
+        fill_mode: *FILL_MODES.get(fields[3] as usize)?,
+        blend_mode: *BLEND_MODES.get(fields[4] as usize)?,
+        topology: *TOPOLOGIES.get(fields[5] as usize)?,
+    })
+}
+
+impl Reader<'_, CaptureError> {
+    fn instances(&mut self) -> std::result::Result<Vec<Instance>, CaptureError> {
+        let count = self.u32()?;
+        let mut instances = Vec::new();
+        for _ in 0..count {
+            let mut transform = [0.0; 16];
+            for value in transform.iter_mut() {
+                *value = self.f32()?;
+            }
+            let mut tint = [0.0; 4];
+            for value in tint.iter_mut() {
+                *value = self.f32()?;
+            }
+            instances.push(Instance::new(Mat4::from_cols_array(transform), tint));
+        }
+        Ok(instances)
+    }
+
+    fn layout(&mut self) -> std::result::Result<VertexLayout, CaptureError> {
+        let mut layout = VertexLayout::new();
+        for _ in 0..self.u8()? {
+            let offset = self.offset();
+            let semantic = SEMANTICS.get(self.u8()? as usize);
+            let format = FORMATS.get(self.u8()? as usize);
+            match (semantic, format) {
+                (Some(&semantic), Some(&format)) => layout = layout.with(semantic, format),
+                _ => return Err(CaptureError::UnknownVertexAttribute { offset }),
+            }
+        }
+        Ok(layout)
+    }
+
+    fn pipeline_state(&mut self) -> std::result::Result<PipelineState, CaptureError> {
+        let offset = self.offset();
+        read_pipeline_state(self.take(6)?).ok_or(CaptureError::UnknownPipelineState { offset })
+    }
+
+    fn handle<K>(&mut self) -> std::result::Result<Handle<K>, CaptureError> {
+        Ok(Handle::from_raw(self.u32()?, self.u32()?))
+    }
+}
+
+/// Feeds every command in `capture` to `graphics_device`.
+///
+/// Handles stored in the capture are translated to the handles returned by
+/// `graphics_device`, so the target does not need to allocate them the same way
+/// the captured device did.
+pub fn replay(capture: &Capture, graphics_device: &mut dyn GraphicsDevice) -> Result<()> {
+    let mut vertex_buffers: HashMap<VertexBufferHandle, VertexBufferHandle> = HashMap::new();
+    let mut index_buffers: HashMap<IndexBufferHandle, IndexBufferHandle> = HashMap::new();
+    let mut constant_buffers: HashMap<ConstantBufferHandle, ConstantBufferHandle> = HashMap::new();
+    let mut instance_buffers: HashMap<InstanceBufferHandle, InstanceBufferHandle> = HashMap::new();
+    let mut pipeline_states: HashMap<PipelineStateHandle, PipelineStateHandle> = HashMap::new();
+
+    for command in &capture.commands {
+        match command {
+            Command::CreateVertexBuffer {
+                handle,
+                data,
+                layout,
+            } => {
+                let replayed = graphics_device.create_vertex_buffer(data, layout)?;
+                vertex_buffers.insert(*handle, replayed);
+            }
+            Command::CreateIndexBuffer { handle, indices } => {
+                let replayed = graphics_device.create_index_buffer(indices)?;
+                index_buffers.insert(*handle, replayed);
+            }
+            Command::UpdateVertexBuffer { handle, data } => {
+                let replayed = vertex_buffers.get(handle).ok_or(Error::InvalidHandle)?;
+                graphics_device.update_vertex_buffer(*replayed, data)?;
+            }
+            Command::DestroyVertexBuffer { handle } => {
+                let replayed = vertex_buffers.remove(handle).ok_or(Error::InvalidHandle)?;
+                graphics_device.destroy_vertex_buffer(replayed)?;
+            }
+            Command::DestroyIndexBuffer { handle } => {
+                let replayed = index_buffers.remove(handle).ok_or(Error::InvalidHandle)?;
+                graphics_device.destroy_index_buffer(replayed)?;
+            }
+            Command::CreateConstantBuffer { handle, data } => {
+                let replayed = graphics_device.create_constant_buffer(data)?;
+                constant_buffers.insert(*handle, replayed);
+            }
+            Command::UpdateConstantBuffer { handle, data } => {
+                let replayed = constant_buffers.get(handle).ok_or(Error::InvalidHandle)?;
+                graphics_device.update_constant_buffer(*replayed, data)?;
+            }
+            Command::DestroyConstantBuffer { handle } => {
+                let replayed = constant_buffers
+                    .remove(handle)
+                    .ok_or(Error::InvalidHandle)?;
+                graphics_device.destroy_constant_buffer(replayed)?;
+            }
+            Command::BindConstantBuffer { slot, handle } => {
+                let replayed = constant_buffers.get(handle).ok_or(Error::InvalidHandle)?;
+                graphics_device.bind_constant_buffer(*slot, *replayed)?;
+            }
+            Command::CreateInstanceBuffer { handle, instances } => {
+                let replayed = graphics_device.create_instance_buffer(instances)?;
+                instance_buffers.insert(*handle, replayed);
+            }
+            Command::UpdateInstanceBuffer { handle, instances } => {
+                let replayed = instance_buffers.get(handle).ok_or(Error::InvalidHandle)?;
+                graphics_device.update_instance_buffer(*replayed, instances)?;
+            }
+            Command::DestroyInstanceBuffer { handle } => {
+                let replayed = instance_buffers
+                    .remove(handle)
+                    .ok_or(Error::InvalidHandle)?;
+                graphics_device.destroy_instance_buffer(replayed)?;
+            }
+            Command::CreatePipelineState { handle, state } => {
+                let replayed = graphics_device.create_pipeline_state(state)?;
+                pipeline_states.insert(*handle, replayed);
+            }
+            Command::DestroyPipelineState { handle } => {
+                let replayed = pipeline_states.remove(handle).ok_or(Error::InvalidHandle)?;
+                graphics_device.destroy_pipeline_state(replayed)?;
+            }
+            Command::BindPipelineState { handle } => {
+                let replayed = pipeline_states.get(handle).ok_or(Error::InvalidHandle)?;
+                graphics_device.bind_pipeline_state(*replayed)?;
+            }
+            Command::BeginFrame {
+                clear_color,
+                clear_depth,
+            } => graphics_device.begin_frame(*clear_color, *clear_depth)?,
+            Command::EndFrame => graphics_device.end_frame()?,
+            Command::Draw {
+                vertex_buffer,
+                index_buffer,
+                num_indices,
+            } => {
+                let vertex_buffer = vertex_buffers
+                    .get(vertex_buffer)
+                    .ok_or(Error::InvalidHandle)?;
+                let index_buffer = index_buffers
+                    .get(index_buffer)
+                    .ok_or(Error::InvalidHandle)?;
+                graphics_device.draw(*vertex_buffer, *index_buffer, *num_indices)?;
+            }
+            Command::DrawInstanced {
+                vertex_buffer,
+                index_buffer,
+                num_indices,
+                instance_buffer,
+                num_instances,
+            } => {
+                let vertex_buffer = vertex_buffers
+                    .get(vertex_buffer)
+                    .ok_or(Error::InvalidHandle)?;
+                let index_buffer = index_buffers
+                    .get(index_buffer)
+                    .ok_or(Error::InvalidHandle)?;
+                let instance_buffer = instance_buffers
+                    .get(instance_buffer)
+                    .ok_or(Error::InvalidHandle)?;
+                graphics_device.draw_instanced(
+                    *vertex_buffer,
+                    *index_buffer,
+                    *num_indices,
+                    *instance_buffer,
+                    *num_instances,
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Wraps another `GraphicsDevice`, forwarding every call to it and recording
+/// the calls that succeed until `stop` is called.
+pub struct CaptureGraphicsDevice<D> {
+    inner: D,
+    commands: Vec<Command>,
+    recording: bool,
+}
+
+impl<D: GraphicsDevice> CaptureGraphicsDevice<D> {
+    pub fn new(inner: D) -> CaptureGraphicsDevice<D> {
+        CaptureGraphicsDevice {
+            inner,
+            commands: Vec::new(),
+            recording: true,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Stops recording and returns everything captured so far.
+    pub fn stop(&mut self) -> Capture {
+        self.recording = false;
+        Capture {
+            commands: std::mem::take(&mut self.commands),
+        }
+    }
+
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut D {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    fn record<T>(&mut self, result: Result<T>, command: impl FnOnce(&T) -> Command) -> Result<T> {
+        if let Ok(value) = &result {
+            if self.recording {
+                self.commands.push(command(value));
+            }
+        }
+        result
+    }
+}
+
+impl<D: GraphicsDevice> GraphicsDevice for CaptureGraphicsDevice<D> {
+    fn create_vertex_buffer(
+        &mut self,
+        data: &[u8],
+        layout: &VertexLayout,
+    ) -> Result<VertexBufferHandle> {
+        let result = self.inner.create_vertex_buffer(data, layout);
+        self.record(result, |&handle| Command::CreateVertexBuffer {
+            handle,
+            data: data.to_vec(),
+            layout: layout.clone(),
+        })
+    }
+
+    fn create_index_buffer(&mut self, indices: &[u32]) -> Result<IndexBufferHandle> {
+        let result = self.inner.create_index_buffer(indices);
+        self.record(result, |&handle| Command::CreateIndexBuffer {
+            handle,
+            indices: indices.to_vec(),
+        })
+    }
+
+    fn update_vertex_buffer(&mut self, handle: VertexBufferHandle, data: &[u8]) -> Result<()> {
+        let result = self.inner.update_vertex_buffer(handle, data);
+        self.record(result, |_| Command::UpdateVertexBuffer {
+            handle,
+            data: data.to_vec(),
+        })
+    }
+
+    fn destroy_vertex_buffer(&mut self, handle: VertexBufferHandle) -> Result<()> {
+        let result = self.inner.destroy_vertex_buffer(handle);
+        self.record(result, |_| Command::DestroyVertexBuffer { handle })
+    }
+
+    fn destroy_index_buffer(&mut self, handle: IndexBufferHandle) -> Result<()> {
+        let result = self.inner.destroy_index_buffer(handle);
+        self.record(result, |_| Command::DestroyIndexBuffer { handle })
+    }
+
+    fn create_constant_buffer(&mut self, data: &[u8]) -> Result<ConstantBufferHandle> {
+        let result = self.inner.create_constant_buffer(data);
+        self.record(result, |&handle| Command::CreateConstantBuffer {
+            handle,
+            data: data.to_vec(),
+        })
+    }
+
+    fn update_constant_buffer(&mut self, handle: ConstantBufferHandle, data: &[u8]) -> Result<()> {
+        let result = self.inner.update_constant_buffer(handle, data);
+        self.record(result, |_| Command::UpdateConstantBuffer {
+            handle,
+            data: data.to_vec(),
+        })
+    }
+
+    fn destroy_constant_buffer(&mut self, handle: ConstantBufferHandle) -> Result<()> {
+        let result = self.inner.destroy_constant_buffer(handle);
+        self.record(result, |_| Command::DestroyConstantBuffer { handle })
+    }
+
+    fn bind_constant_buffer(&mut self, slot: u32, handle: ConstantBufferHandle) -> Result<()> {
+        let result = self.inner.bind_constant_buffer(slot, handle);
+        self.record(result, |_| Command::BindConstantBuffer { slot, handle })
+    }
+
+    fn create_instance_buffer(&mut self, instances: &[Instance]) -> Result<InstanceBufferHandle> {
+        let result = self.inner.create_instance_buffer(instances);
+        self.record(result, |&handle| Command::CreateInstanceBuffer {
+            handle,
+            instances: instances.to_vec(),
+        })
+    }
+
+    fn update_instance_buffer(
+        &mut self,
+        handle: InstanceBufferHandle,
+        instances: &[Instance],
+    ) -> Result<()> {
+        let result = self.inner.update_instance_buffer(handle, instances);
+        self.record(result, |_| Command::UpdateInstanceBuffer {
+            handle,
+            instances: instances.to_vec(),
+        })
+    }
+
+    fn destroy_instance_buffer(&mut self, handle: InstanceBufferHandle) -> Result<()> {
+        let result = self.inner.destroy_instance_buffer(handle);
+        self.record(result, |_| Command::DestroyInstanceBuffer { handle })
+    }
+
+    fn create_pipeline_state(&mut self, state: &PipelineState) -> Result<PipelineStateHandle> {
+        let result = self.inner.create_pipeline_state(state);
+        self.record(result, |&handle| Command::CreatePipelineState {
+            handle,
+            state: *state,
+        })
+    }
+
+    fn destroy_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()> {
+        let result = self.inner.destroy_pipeline_state(handle);
+        self.record(result, |_| Command::DestroyPipelineState { handle })
+    }
+
+    fn bind_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()> {
+        let result = self.inner.bind_pipeline_state(handle);
+        self.record(result, |_| Command::BindPipelineState { handle })
+    }
+
+    fn begin_frame(&mut self, clear_color: [f32; 4], clear_depth: f32) -> Result<()> {
+        let result = self.inner.begin_frame(clear_color, clear_depth);
+        self.record(result, |_| Command::BeginFrame {
+            clear_color,
+            clear_depth,
+        })
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        let result = self.inner.end_frame();
+        self.record(result, |_| Command::EndFrame)
+    }
+
+    fn draw(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+    ) -> Result<()> {
+        let result = self.inner.draw(vertex_buffer, index_buffer, num_indices);
+        self.record(result, |_| Command::Draw {
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+        })
+    }
+
+    fn draw_instanced(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+        instance_buffer: InstanceBufferHandle,
+        num_instances: u32,
+    ) -> Result<()> {
+        let result = self.inner.draw_instanced(
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+            instance_buffer,
+            num_instances,
+        );
+        self.record(result, |_| Command::DrawInstanced {
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+            instance_buffer,
+            num_instances,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::constant::{ConstantBuffer, DrawConstants};
+    use crate::cpu::CpuGraphicsDevice;
+    use crate::cube;
+    use crate::math::Vec3;
+    use crate::recording::RecordingGraphicsDevice;
+    use crate::{draw, release_mesh, render_frame, upload_mesh, GpuMesh};
+
+    fn draw_cube(device: &mut dyn GraphicsDevice) -> GpuMesh {
+        let mesh = cube::unit();
+        let gpu_mesh = upload_mesh(device, &mesh).unwrap();
+
+        let mut constants = DrawConstants::default();
+        let buffer = ConstantBuffer::new(device, &constants).unwrap();
+        buffer.bind(device, DrawConstants::SLOT).unwrap();
+        let mut camera = Camera::default();
+        camera.look_at(Vec3::new(1.0, 2.0, -3.0), Vec3::ZERO, Vec3::Y);
+        constants.transform = camera.view_projection();
+        buffer.update(device, &constants).unwrap();
+
+        render_frame(device, [0.0, 0.0, 0.0, 1.0], |device| {
+            draw(device, &gpu_mesh)
+        })
+        .unwrap();
+        gpu_mesh
+    }
+
+    fn capture_cube_frame() -> Capture {
+        let mut device = CaptureGraphicsDevice::new(RecordingGraphicsDevice::new());
+        let gpu_mesh = draw_cube(&mut device);
+        release_mesh(&mut device, gpu_mesh).unwrap();
+        device.stop()
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let capture = capture_cube_frame();
+        assert_eq!(capture.commands.len(), 10);
+        assert_eq!(Capture::decode(&capture.encode()), Ok(capture));
+    }
+
+    #[test]
+    fn instanced_commands_round_trip() {
+        let mut device = CaptureGraphicsDevice::new(RecordingGraphicsDevice::new());
+        let mesh = upload_mesh(&mut device, &cube::generate(1)).unwrap();
+        let instances = [
+            Instance::default(),
+            Instance::new(Mat4::translation(Vec3::new(1.0, 2.0, 3.0)), [0.5; 4]),
+        ];
+        let instance_buffer = device.create_instance_buffer(&instances).unwrap();
+        device
+            .update_instance_buffer(instance_buffer, &instances[1..])
+            .unwrap();
+        render_frame(&mut device, [0.2, 0.4, 0.6, 1.0], |device| {
+            device.draw_instanced(
+                mesh.vertex_buffer,
+                mesh.index_buffer,
+                mesh.index_count,
+                instance_buffer,
+                2,
+            )
+        })
+        .unwrap();
+        device.destroy_instance_buffer(instance_buffer).unwrap();
+
+        let capture = device.stop();
+        assert_eq!(Capture::decode(&capture.encode()), Ok(capture.clone()));
+
+        let mut replayed = RecordingGraphicsDevice::new();
+        replay(&capture, &mut replayed).unwrap();
+        assert_eq!(replayed.commands(), capture.commands.as_slice());
+    }
+
+    #[test]
+    fn pipeline_commands_round_trip() {
+        let mut device = CaptureGraphicsDevice::new(RecordingGraphicsDevice::new());
+        let state = PipelineState {
+            cull_mode: CullMode::Back,
+            ..PipelineState::transparent()
+        };
+        let handle = device.create_pipeline_state(&state).unwrap();
+        device.bind_pipeline_state(handle).unwrap();
+        device.destroy_pipeline_state(handle).unwrap();
+
+        let capture = device.stop();
+        let mut bytes = capture.encode();
+        assert_eq!(Capture::decode(&bytes), Ok(capture.clone()));
+
+        let mut replayed = RecordingGraphicsDevice::new();
+        replay(&capture, &mut replayed).unwrap();
+        assert_eq!(replayed.commands(), capture.commands.as_slice());
+
+        // The blend mode of the created state.
+        let offset = 12 + 1 + 8;
+        bytes[offset + 4] = 7;
+        assert_eq!(
+            Capture::decode(&bytes),
+            Err(CaptureError::UnknownPipelineState { offset })
+        );
+    }
+
+    #[test]
+    fn decode_rejects_malformed_input() {
+        let bytes = capture_cube_frame().encode();
+
+        assert_eq!(Capture::decode(b"nope"), Err(CaptureError::BadMagic));
+        assert_eq!(
+            Capture::decode(&bytes[..bytes.len() - 1]),
+            Err(CaptureError::UnexpectedEof)
+        );
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert_eq!(
+            Capture::decode(&extra),
+            Err(CaptureError::TrailingBytes {
+                offset: bytes.len()
+            })
+        );
+
+        let mut unknown = bytes;
+        unknown[12] = 0xff;
+        assert_eq!(
+            Capture::decode(&unknown),
+            Err(CaptureError::UnknownCommand {
+                tag: 0xff,
+                offset: 12
+            })
+        );
+    }
+
+    #[test]
+    fn replay_matches_direct_rendering() {
+        let mut direct = CpuGraphicsDevice::new(32, 32);
+        draw_cube(&mut direct);
+
+        let capture = Capture::decode(&capture_cube_frame().encode()).unwrap();
+        let mut replayed = CpuGraphicsDevice::new(32, 32);
+        replay(&capture, &mut replayed).unwrap();
+
+        assert_eq!(direct.framebuffer(), replayed.framebuffer());
+    }
+
+    #[test]
+    fn replay_reissues_same_commands() {
+        let capture = capture_cube_frame();
+        let mut device = RecordingGraphicsDevice::new();
+        replay(&capture, &mut device).unwrap();
+        assert_eq!(device.commands(), capture.commands.as_slice());
+    }
+
+    #[test]
+    fn stopped_device_no_longer_records() {
+        let mut device = CaptureGraphicsDevice::new(RecordingGraphicsDevice::new());
+        device.create_index_buffer(&[0, 1, 2]).unwrap();
+        assert_eq!(device.stop().commands.len(), 1);
+
+        device.create_index_buffer(&[0, 1, 2]).unwrap();
+        assert!(!device.is_recording());
+        assert!(device.stop().commands.is_empty());
+        assert_eq!(device.inner().commands().len(), 2);
+    }
+}
@@ -0,0 +1,307 @@
+use std::marker::PhantomData;
+
+use crate::math::{Mat4, Vec2, Vec3, Vec4};
+use crate::{ConstantBufferHandle, Error, GraphicsDevice, Result};
+
+/// Constant buffers are bound in 16-byte registers.
+pub const REGISTER_SIZE: usize = 16;
+/// Direct3D 11 allows at most 4096 registers per constant buffer.
+pub const MAX_CONSTANT_BUFFER_SIZE: usize = 4096 * REGISTER_SIZE;
+pub const MAX_CONSTANT_BUFFER_SLOTS: u32 = 14;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ConstantType {
+    Float,
+    Float2,
+    Float3,
+    Float4,
+    Float4x4,
+}
+
+impl ConstantType {
+    pub fn size(self) -> usize {
+        match self {
+            ConstantType::Float => 4,
+            ConstantType::Float2 => 8,
+            ConstantType::Float3 => 12,
+            ConstantType::Float4 => 16,
+            ConstantType::Float4x4 => 64,
+        }
+    }
+
+    /// Where HLSL places a member of this type in a `cbuffer` when the previous
+    /// member ends at `offset`: right after it, unless it would straddle a
+    /// 16-byte register, in which case at the start of the next one. Matrices
+    /// always start a register.
+    pub fn packed_offset(self, offset: usize) -> usize {
+        let straddles = offset % REGISTER_SIZE + self.size() > REGISTER_SIZE;
+        if self == ConstantType::Float4x4 || straddles {
+            align(offset, REGISTER_SIZE)
+        } else {
+            offset
+        }
+    }
+}
+
+/// Checks that `data` can back a constant buffer: non-empty, a whole number of
+/// registers and within the size limit.
+pub fn validate(data: &[u8]) -> Result<()> {
+    if data.is_empty() || !data.len().is_multiple_of(REGISTER_SIZE) {
+        return Err(Error::InvalidConstantData(format!(
+            "{} bytes is not a whole number of {} byte registers",
+            data.len(),
+            REGISTER_SIZE
+        )));
+    }
+    if data.len() > MAX_CONSTANT_BUFFER_SIZE {
+        return Err(Error::InvalidConstantData(format!(
+            "{} bytes exceeds the {} byte limit",
+            data.len(),
+            MAX_CONSTANT_BUFFER_SIZE
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that an update to a constant buffer of `size` bytes replaces its
+/// whole contents.
+pub fn validate_update(size: usize, data: &[u8]) -> Result<()> {
+    if data.len() != size {
+        return Err(Error::InvalidConstantData(format!(
+            "update of {} bytes does not match the {} byte buffer",
+            data.len(),
+            size
+        )));
+    }
+    Ok(())
+}
+
+pub fn validate_slot(slot: u32) -> Result<()> {
+    if slot >= MAX_CONSTANT_BUFFER_SLOTS {
+        return Err(Error::InvalidConstantData(format!(
+            "slot {} is out of range, devices have {} slots",
+            slot, MAX_CONSTANT_BUFFER_SLOTS
+        )));
+    }
+    Ok(())
+}
+
+/// Packs members into a byte buffer following HLSL's `cbuffer` packing rules,
+/// padding the end out to a whole register.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConstantWriter {
+    data: Vec<u8>,
+    members: Vec<(ConstantType, usize)>,
+}
+
+impl ConstantWriter {
+    pub fn new() -> ConstantWriter {
+        ConstantWriter::default()
+    }
+
+    /// Each member with the offset it was written at.
+    pub fn members(&self) -> &[(ConstantType, usize)] {
+        &self.members
+    }
+
+    fn push(&mut self, constant_type: ConstantType, values: &[f32]) -> &mut ConstantWriter {
+        let offset = constant_type.packed_offset(self.data.len());
+        self.data.resize(offset, 0);
+        for value in values {
+            self.data.extend_from_slice(&value.to_le_bytes());
+        }
+        self.members.push((constant_type, offset));
+        self
+    }
+
+    pub fn float(&mut self, value: f32) -> &mut ConstantWriter {
+        self.push(ConstantType::Float, &[value])
+    }
+
+    pub fn float2(&mut self, value: Vec2) -> &mut ConstantWriter {
+        self.push(ConstantType::Float2, &<[f32; 2]>::from(value))
+    }
+
+    pub fn float3(&mut self, value: Vec3) -> &mut ConstantWriter {
+        self.push(ConstantType::Float3, &<[f32; 3]>::from(value))
+    }
+
+    pub fn float4(&mut self, value: Vec4) -> &mut ConstantWriter {
+        self.push(ConstantType::Float4, &<[f32; 4]>::from(value))
+    }
+
+    /// Written column by column, matching HLSL's default `column_major` packing.
+    pub fn float4x4(&mut self, value: &Mat4) -> &mut ConstantWriter {
+        self.push(ConstantType::Float4x4, &value.to_cols_array())
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let size = align(self.data.len(), REGISTER_SIZE).max(REGISTER_SIZE);
+        self.data.resize(size, 0);
+        self.data
+    }
+}
+
+fn align(offset: usize, alignment: usize) -> usize {
+    offset.div_ceil(alignment) * alignment
+}
+
+/// A Rust type that mirrors a shader `cbuffer`.
+pub trait Constants {
+    /// Writes the members in the order the shader declares them.
+    fn write(&self, writer: &mut ConstantWriter);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ConstantWriter::new();
+        self.write(&mut writer);
+        writer.finish()
+    }
+}
+
+/// The per-draw constants read by `desktop/src/shader.hlsl` from slot 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawConstants {
+    /// Object space to clip space.
+    pub transform: Mat4,
+    pub color: [f32; 4],
+}
+
+impl DrawConstants {
+    pub const SLOT: u32 = 0;
+    /// Used by `default`, and so by backends with nothing bound at `SLOT`.
+    pub const DEFAULT_COLOR: [f32; 4] = [0.8, 0.8, 0.3, 1.0];
+
+    /// Reads the constants back out of a buffer written by `to_bytes`.
+    pub fn from_bytes(data: &[u8]) -> Result<DrawConstants> {
+        if data.len() < 80 {
+            return Err(Error::InvalidConstantData(format!(
+                "{} bytes is too small for draw constants",
+                data.len()
+            )));
+        }
+
+        let mut values = [0.0; 20];
+        for (value, chunk) in values.iter_mut().zip(data.chunks_exact(4)) {
+            let mut word = [0; 4];
+            word.copy_from_slice(chunk);
+            *value = f32::from_le_bytes(word);
+        }
+
+        let mut transform = [0.0; 16];
+        transform.copy_from_slice(&values[..16]);
+        let mut color = [0.0; 4];
+        color.copy_from_slice(&values[16..]);
+        Ok(DrawConstants {
+            transform: Mat4::from_cols_array(transform),
+            color,
+        })
+    }
+}
+
+impl Default for DrawConstants {
+    fn default() -> DrawConstants {
+        DrawConstants {
+            transform: Mat4::IDENTITY,
+            color: DrawConstants::DEFAULT_COLOR,
+        }
+    }
+}
+
+impl Constants for DrawConstants {
+    fn write(&self, writer: &mut ConstantWriter) {
+        writer
+            .float4x4(&self.transform)
+            .float4(Vec4::from(self.color));
+    }
+}
+
+/// A constant buffer on a `GraphicsDevice` that only accepts contents of type
+/// `T`, so updates always match the size it was created with.
+pub struct ConstantBuffer<T> {
+    handle: ConstantBufferHandle,
+    kind: PhantomData<fn(&T)>,
+}
+
+impl<T> Clone for ConstantBuffer<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ConstantBuffer<T> {}
+
+impl<T: Constants> ConstantBuffer<T> {
+    pub fn new(graphics_device: &mut dyn GraphicsDevice, value: &T) -> Result<ConstantBuffer<T>> {
+        Ok(ConstantBuffer {
+            handle: graphics_device.create_constant_buffer(&value.to_bytes())?,
+            kind: PhantomData,
+        })
+    }
+
+    pub fn handle(&self) -> ConstantBufferHandle {
+        self.handle
+    }
+
+    pub fn update(&self, graphics_device: &mut dyn GraphicsDevice, value: &T) -> Result<()> {
+        graphics_device.update_constant_buffer(self.handle, &value.to_bytes())
+    }
+
+    pub fn bind(&self, graphics_device: &mut dyn GraphicsDevice, slot: u32) -> Result<()> {
+        graphics_device.bind_constant_buffer(slot, self.handle)
+    }
+
+    pub fn destroy(self, graphics_device: &mut dyn GraphicsDevice) -> Result<()> {
+        graphics_device.destroy_constant_buffer(self.handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hlsl_packing_offsets() {
+        // The offsets given by the HLSL packing rules for
+        //   cbuffer C { float a; float3 b; float c; float2 d; float3 e;
+        //               float4x4 f; float g; float2 h; };
+        // where only `e` and `f` are moved to a new register.
+        let mut writer = ConstantWriter::new();
+        writer
+            .float(1.0)
+            .float3(Vec3::ONE)
+            .float(2.0)
+            .float2(Vec2::new(3.0, 4.0))
+            .float3(Vec3::ONE)
+            .float4x4(&Mat4::IDENTITY)
+            .float(5.0)
+            .float2(Vec2::new(6.0, 7.0));
+
+        let offsets: Vec<usize> = writer.members().iter().map(|&(_, offset)| offset).collect();
+        assert_eq!(offsets, vec![0, 4, 16, 20, 32, 48, 112, 116]);
+        assert_eq!(writer.finish().len(), 128);
+    }
+
+    #[test]
+    fn draw_constants_match_shader_layout() {
+        let constants = DrawConstants {
+            transform: Mat4::translation(Vec3::new(1.0, 2.0, 3.0)),
+            color: [0.1, 0.2, 0.3, 0.4],
+        };
+        let bytes = constants.to_bytes();
+        assert_eq!(bytes.len(), 80);
+        assert_eq!(bytes[48..52], 1.0f32.to_le_bytes());
+        assert_eq!(bytes[64..68], 0.1f32.to_le_bytes());
+        validate(&bytes).unwrap();
+        assert_eq!(DrawConstants::from_bytes(&bytes), Ok(constants));
+    }
+
+    #[test]
+    fn rejects_unaligned_data_and_slots() {
+        assert!(validate(&[]).is_err());
+        assert!(validate(&[0; 20]).is_err());
+        assert!(validate(&vec![0; MAX_CONSTANT_BUFFER_SIZE + 16]).is_err());
+        assert!(validate_update(32, &[0; 16]).is_err());
+        assert!(validate_slot(MAX_CONSTANT_BUFFER_SLOTS).is_err());
+        assert!(validate_slot(0).is_ok());
+    }
+}
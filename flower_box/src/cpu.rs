@@ -0,0 +1,1053 @@
+use crate::constant::{self, DrawConstants, MAX_CONSTANT_BUFFER_SLOTS};
+use crate::instance::{self, Instance};
+use crate::math::{Mat4, Vec4};
+use crate::pipeline::{CullMode, FillMode, PipelineState, Topology};
+use crate::resource::{self, Pool};
+use crate::vertex::{VertexLayout, VertexSemantic};
+use crate::{
+    ConstantBufferHandle, Error, GraphicsDevice, IndexBufferHandle, InstanceBufferHandle,
+    PipelineStateHandle, Result, VertexBufferHandle,
+};
+
+const CLEAR_COLOR: [u8; 4] = [0, 0, 0, 255];
+const CLEAR_DEPTH: f32 = 1.0;
+
+/// An RGBA8 colour target with a matching 32-bit float depth buffer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    color: Vec<u8>,
+    depth: Vec<f32>,
+}
+
+impl Framebuffer {
+    /// Panics if the colour buffer's size in bytes overflows `usize`.
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        let pixel_count = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|count| count.checked_mul(4).is_some())
+            .expect("framebuffer dimensions overflow");
+        let mut framebuffer = Framebuffer {
+            width,
+            height,
+            color: vec![0; pixel_count * 4],
+            depth: vec![0.0; pixel_count],
+        };
+        framebuffer.clear(CLEAR_COLOR, CLEAR_DEPTH);
+        framebuffer
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Colour data in row-major order, four bytes per pixel.
+    pub fn color(&self) -> &[u8] {
+        &self.color
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let offset = self.offset(x, y) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.color[offset..offset + 4]);
+        pixel
+    }
+
+    pub fn depth(&self, x: u32, y: u32) -> f32 {
+        self.depth[self.offset(x, y)]
+    }
+
+    pub fn clear(&mut self, color: [u8; 4], depth: f32) {
+        for pixel in self.color.chunks_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+        for value in self.depth.iter_mut() {
+            *value = depth;
+        }
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        assert!(x < self.width && y < self.height);
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+struct VertexBuffer {
+    data: Vec<u8>,
+    layout: VertexLayout,
+    count: u32,
+}
+
+/// A software rasterizer that mirrors the Direct3D 11 backend: the bound
+/// `PipelineState`, `LESS_EQUAL` depth testing and the shaders in
+/// `desktop/src/shader.hlsl`, which read `DrawConstants` from slot 0.
+///
+/// With nothing bound at slot 0 positions pass through untransformed and are
+/// drawn in `DrawConstants::DEFAULT_COLOR`.
+pub struct CpuGraphicsDevice {
+    framebuffer: Framebuffer,
+    vertex_buffers: Pool<resource::VertexBuffer, VertexBuffer>,
+    index_buffers: Pool<resource::IndexBuffer, Vec<u32>>,
+    constant_buffers: Pool<resource::ConstantBuffer, Vec<u8>>,
+    instance_buffers: Pool<resource::InstanceBuffer, Vec<Instance>>,
+    constant_bindings: [Option<ConstantBufferHandle>; MAX_CONSTANT_BUFFER_SLOTS as usize],
+    pipeline_states: Pool<resource::PipelineState, PipelineState>,
+    pipeline_state: PipelineState,
+    in_frame: bool,
+}
+
+impl CpuGraphicsDevice {
+    pub fn new(width: u32, height: u32) -> CpuGraphicsDevice {
+        CpuGraphicsDevice {
+            framebuffer: Framebuffer::new(width, height),
+            vertex_buffers: Pool::new(),
+            index_buffers: Pool::new(),
+            constant_buffers: Pool::new(),
+            instance_buffers: Pool::new(),
+            constant_bindings: Default::default(),
+            pipeline_states: Pool::new(),
+            pipeline_state: PipelineState::default(),
+            in_frame: false,
+        }
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    pub fn framebuffer_mut(&mut self) -> &mut Framebuffer {
+        &mut self.framebuffer
+    }
+
+    fn draw_constants(&self) -> Result<DrawConstants> {
+        match self.constant_bindings[DrawConstants::SLOT as usize] {
+            Some(handle) => {
+                let data = self
+                    .constant_buffers
+                    .get(handle)
+                    .ok_or(Error::InvalidHandle)?;
+                DrawConstants::from_bytes(data)
+            }
+            None => Ok(DrawConstants::default()),
+        }
+    }
+
+    fn draw_instances(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+        instances: &[Instance],
+    ) -> Result<()> {
+        if !self.in_frame {
+            return Err(Error::NoFrameInProgress);
+        }
+        let vertex_buffer = self
+            .vertex_buffers
+            .get(vertex_buffer)
+            .ok_or(Error::InvalidHandle)?;
+        let indices = self
+            .index_buffers
+            .get(index_buffer)
+            .ok_or(Error::InvalidHandle)?;
+
+        if num_indices as usize > indices.len() {
+            return Err(Error::InvalidIndexRange {
+                requested: num_indices,
+                available: indices.len() as u32,
+            });
+        }
+
+        if let Some(&index) = indices[..num_indices as usize]
+            .iter()
+            .find(|&&index| index >= vertex_buffer.count)
+        {
+            return Err(Error::InvalidIndexRange {
+                requested: index + 1,
+                available: vertex_buffer.count,
+            });
+        }
+
+        let constants = self.draw_constants()?;
+        for instance in instances {
+            let transform = constants.transform * instance.transform;
+            let color = Vec4::from(constants.color) * Vec4::from(instance.tint);
+
+            let indices = &indices[..num_indices as usize];
+            let state = &self.pipeline_state;
+            let shade =
+                |index: u32| vertex_shader(&transform, color, vertex_buffer, index as usize);
+            match state.topology {
+                Topology::TriangleList => {
+                    for triangle in indices.chunks_exact(3) {
+                        let clip = [shade(triangle[0]), shade(triangle[1]), shade(triangle[2])];
+                        rasterize_polygon(&mut self.framebuffer, state, &clip_triangle(clip));
+                    }
+                }
+                Topology::LineList => {
+                    for line in indices.chunks_exact(2) {
+                        if let Some([a, b]) = clip_line([shade(line[0]), shade(line[1])]) {
+                            let screen = [
+                                to_screen(&self.framebuffer, a),
+                                to_screen(&self.framebuffer, b),
+                            ];
+                            if let [Some(a), Some(b)] = screen {
+                                rasterize_line(&mut self.framebuffer, state, [a, b]);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl GraphicsDevice for CpuGraphicsDevice {
+    fn create_vertex_buffer(
+        &mut self,
+        data: &[u8],
+        layout: &VertexLayout,
+    ) -> Result<VertexBufferHandle> {
+        let count = layout.vertex_count(data)?;
+        Ok(self.vertex_buffers.insert(VertexBuffer {
+            data: data.to_vec(),
+            layout: layout.clone(),
+            count,
+        }))
+    }
+
+    fn create_index_buffer(&mut self, indices: &[u32]) -> Result<IndexBufferHandle> {
+        Ok(self.index_buffers.insert(indices.to_vec()))
+    }
+
+    fn update_vertex_buffer(&mut self, handle: VertexBufferHandle, data: &[u8]) -> Result<()> {
+        let buffer = self
+            .vertex_buffers
+            .get_mut(handle)
+            .ok_or(Error::InvalidHandle)?;
+        buffer.layout.validate_update(buffer.count, data)?;
+        buffer.data[..data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn destroy_vertex_buffer(&mut self, handle: VertexBufferHandle) -> Result<()> {
+        self.vertex_buffers
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn destroy_index_buffer(&mut self, handle: IndexBufferHandle) -> Result<()> {
+        self.index_buffers
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn create_constant_buffer(&mut self, data: &[u8]) -> Result<ConstantBufferHandle> {
+        constant::validate(data)?;
+        Ok(self.constant_buffers.insert(data.to_vec()))
+    }
+
+    fn update_constant_buffer(&mut self, handle: ConstantBufferHandle, data: &[u8]) -> Result<()> {
+        let buffer = self
+            .constant_buffers
+            .get_mut(handle)
+            .ok_or(Error::InvalidHandle)?;
+        constant::validate_update(buffer.len(), data)?;
+        buffer.copy_from_slice(data);
+        Ok(())
+    }
+
+    fn destroy_constant_buffer(&mut self, handle: ConstantBufferHandle) -> Result<()> {
+        self.constant_buffers
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn bind_constant_buffer(&mut self, slot: u32, handle: ConstantBufferHandle) -> Result<()> {
+        constant::validate_slot(slot)?;
+        self.constant_buffers
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        self.constant_bindings[slot as usize] = Some(handle);
+        Ok(())
+    }
+
+    fn create_instance_buffer(&mut self, instances: &[Instance]) -> Result<InstanceBufferHandle> {
+        instance::validate(instances)?;
+        Ok(self.instance_buffers.insert(instances.to_vec()))
+    }
+
+    fn update_instance_buffer(
+        &mut self,
+        handle: InstanceBufferHandle,
+        instances: &[Instance],
+    ) -> Result<()> {
+        let buffer = self
+            .instance_buffers
+            .get_mut(handle)
+            .ok_or(Error::InvalidHandle)?;
+        instance::validate_range(buffer.len() as u32, instances.len() as u32)?;
+        buffer[..instances.len()].copy_from_slice(instances);
+        Ok(())
+    }
+
+    fn destroy_instance_buffer(&mut self, handle: InstanceBufferHandle) -> Result<()> {
+        self.instance_buffers
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn create_pipeline_state(&mut self, state: &PipelineState) -> Result<PipelineStateHandle> {
+        Ok(self.pipeline_states.insert(*state))
+    }
+
+    fn destroy_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()> {
+        self.pipeline_states
+            .remove(handle)
+            .map(|_| ())
+            .ok_or(Error::InvalidHandle)
+    }
+
+    fn bind_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()> {
+        self.pipeline_state = *self
+            .pipeline_states
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        Ok(())
+    }
+
+    fn begin_frame(&mut self, clear_color: [f32; 4], clear_depth: f32) -> Result<()> {
+        if self.in_frame {
+            return Err(Error::FrameInProgress);
+        }
+        self.in_frame = true;
+        self.framebuffer.clear(to_rgba8(clear_color), clear_depth);
+        Ok(())
+    }
+
+    /// There is no swap chain, so the finished frame stays readable through
+    /// `framebuffer` until the next `begin_frame` clears it.
+    fn end_frame(&mut self) -> Result<()> {
+        if !self.in_frame {
+            return Err(Error::NoFrameInProgress);
+        }
+        self.in_frame = false;
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+    ) -> Result<()> {
+        self.draw_instances(
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+            &[Instance::default()],
+        )
+    }
+
+    fn draw_instanced(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+        instance_buffer: InstanceBufferHandle,
+        num_instances: u32,
+    ) -> Result<()> {
+        let instances = self
+            .instance_buffers
+            .get(instance_buffer)
+            .ok_or(Error::InvalidHandle)?;
+        instance::validate_range(instances.len() as u32, num_instances)?;
+
+        let instances = instances[..num_instances as usize].to_vec();
+        self.draw_instances(vertex_buffer, index_buffer, num_indices, &instances)
+    }
+}
+
+// A clip-space position followed by the shaded vertex colour.
+type ClipVertex = [f32; 8];
+
+// Pixel coordinates, depth and `1 / w`, followed by the vertex colour.
+type ScreenVertex = [f32; 8];
+
+/// Transforms a vertex and multiplies `color` by its `Color` attribute, if
+/// the layout has one.
+fn vertex_shader(
+    transform: &Mat4,
+    color: Vec4,
+    vertex_buffer: &VertexBuffer,
+    index: usize,
+) -> ClipVertex {
+    let layout = &vertex_buffer.layout;
+    let position = layout
+        .read(&vertex_buffer.data, index, VertexSemantic::Position)
+        .expect("vertex buffer layouts are validated on creation");
+    let position = *transform * Vec4::new(position[0], position[1], position[2], 1.0);
+    let color = match layout.read(&vertex_buffer.data, index, VertexSemantic::Color) {
+        Some(vertex_color) => color * Vec4::from(vertex_color),
+        None => color,
+    };
+
+    let mut vertex = [0.0; 8];
+    vertex[..4].copy_from_slice(&<[f32; 4]>::from(position));
+    vertex[4..].copy_from_slice(&<[f32; 4]>::from(color));
+    vertex
+}
+
+fn to_rgba8(color: [f32; 4]) -> [u8; 4] {
+    let mut rgba = [0; 4];
+    for (channel, value) in rgba.iter_mut().zip(color.iter()) {
+        *channel = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    }
+    rgba
+}
+
+/// Clips a triangle against the Direct3D depth range `0 <= z <= w`, returning
+/// the remaining convex polygon (possibly empty).
+fn clip_triangle(triangle: [ClipVertex; 3]) -> Vec<ClipVertex> {
+    let near = |v: &ClipVertex| v[2];
+    let far = |v: &ClipVertex| v[3] - v[2];
+
+    let polygon = clip_polygon(triangle.to_vec(), near);
+    clip_polygon(polygon, far)
+}
+
+fn clip_polygon(
+    polygon: Vec<ClipVertex>,
+    distance: impl Fn(&ClipVertex) -> f32,
+) -> Vec<ClipVertex> {
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (i, current) in polygon.iter().enumerate() {
+        let next = &polygon[(i + 1) % polygon.len()];
+        let (d_current, d_next) = (distance(current), distance(next));
+
+        if d_current >= 0.0 {
+            clipped.push(*current);
+        }
+        if (d_current >= 0.0) != (d_next >= 0.0) {
+            clipped.push(lerp(current, next, d_current / (d_current - d_next)));
+        }
+    }
+    clipped
+}
+
+/// Clips a line against the same depth range as `clip_triangle`.
+fn clip_line(line: [ClipVertex; 2]) -> Option<[ClipVertex; 2]> {
+    let [mut a, mut b] = line;
+    let planes: [fn(&ClipVertex) -> f32; 2] = [|v| v[2], |v| v[3] - v[2]];
+    for distance in planes.iter() {
+        let (d_a, d_b) = (distance(&a), distance(&b));
+        if d_a < 0.0 && d_b < 0.0 {
+            return None;
+        }
+        if (d_a >= 0.0) != (d_b >= 0.0) {
+            let intersection = lerp(&a, &b, d_a / (d_a - d_b));
+            if d_a < 0.0 {
+                a = intersection;
+            } else {
+                b = intersection;
+            }
+        }
+    }
+    Some([a, b])
+}
+
+fn lerp(a: &[f32; 8], b: &[f32; 8], t: f32) -> [f32; 8] {
+    let mut v = [0.0; 8];
+    for (k, value) in v.iter_mut().enumerate() {
+        *value = a[k] + (b[k] - a[k]) * t;
+    }
+    v
+}
+
+fn to_screen(framebuffer: &Framebuffer, v: ClipVertex) -> Option<ScreenVertex> {
+    if v[3] <= f32::EPSILON {
+        return None;
+    }
+    let inv_w = 1.0 / v[3];
+    let (x, y, z) = (v[0] * inv_w, v[1] * inv_w, v[2] * inv_w);
+    Some([
+        (x + 1.0) * 0.5 * framebuffer.width as f32,
+        (1.0 - y) * 0.5 * framebuffer.height as f32,
+        z,
+        inv_w,
+        v[4],
+        v[5],
+        v[6],
+        v[7],
+    ])
+}
+
+/// Blends the colours of `vertices` by `weights` with perspective correction.
+/// Primitives with a single colour skip the division, keeping it exact.
+fn interpolate_color(vertices: &[ScreenVertex], weights: &[f32]) -> [f32; 4] {
+    let mut color = [0.0; 4];
+    color.copy_from_slice(&vertices[0][4..]);
+    if vertices.iter().all(|v| v[4..] == color) {
+        return color;
+    }
+
+    let mut color = [0.0; 4];
+    let mut total = 0.0;
+    for (v, &weight) in vertices.iter().zip(weights) {
+        let weight = weight * v[3];
+        total += weight;
+        for (value, &channel) in color.iter_mut().zip(v[4..].iter()) {
+            *value += weight * channel;
+        }
+    }
+    color.map(|value| value / total)
+}
+
+fn rasterize_polygon(framebuffer: &mut Framebuffer, state: &PipelineState, polygon: &[ClipVertex]) {
+    if polygon.len() < 3 {
+        return;
+    }
+
+    let mut screen = Vec::with_capacity(polygon.len());
+    for &v in polygon {
+        match to_screen(framebuffer, v) {
+            Some(v) => screen.push(v),
+            None => return,
+        }
+    }
+
+    // Clipping keeps the winding, so the whole polygon faces one way.
+    let mut area = 0.0;
+    for i in 1..screen.len() - 1 {
+        area += edge(&screen[0], &screen[i], screen[i + 1][0], screen[i + 1][1]);
+    }
+    let culled = match state.cull_mode {
+        CullMode::None => false,
+        CullMode::Front => area > 0.0,
+        CullMode::Back => area < 0.0,
+    };
+    if culled {
+        return;
+    }
+
+    match state.fill_mode {
+        FillMode::Solid => {
+            for i in 1..screen.len() - 1 {
+                rasterize_triangle(framebuffer, state, [screen[0], screen[i], screen[i + 1]]);
+            }
+        }
+        FillMode::Wireframe => {
+            for i in 0..screen.len() {
+                let next = screen[(i + 1) % screen.len()];
+                rasterize_line(framebuffer, state, [screen[i], next]);
+            }
+        }
+    }
+}
+
+fn edge(a: &ScreenVertex, b: &ScreenVertex, x: f32, y: f32) -> f32 {
+    (b[0] - a[0]) * (y - a[1]) - (b[1] - a[1]) * (x - a[0])
+}
+
+// Direct3D's top-left fill rule for a triangle wound clockwise on screen.
+fn is_top_left(a: &ScreenVertex, b: &ScreenVertex) -> bool {
+    (a[1] == b[1] && b[0] > a[0]) || b[1] < a[1]
+}
+
+fn rasterize_triangle(
+    framebuffer: &mut Framebuffer,
+    state: &PipelineState,
+    triangle: [ScreenVertex; 3],
+) {
+    let [v0, mut v1, mut v2] = triangle;
+    let mut area = edge(&v0, &v1, v2[0], v2[1]);
+    if area == 0.0 {
+        return;
+    }
+    if area < 0.0 {
+        std::mem::swap(&mut v1, &mut v2);
+        area = -area;
+    }
+
+    let min_x = v0[0].min(v1[0]).min(v2[0]).floor().max(0.0) as u32;
+    let min_y = v0[1].min(v1[1]).min(v2[1]).floor().max(0.0) as u32;
+    let max_x = (v0[0].max(v1[0]).max(v2[0]).ceil() as i64).min(framebuffer.width as i64);
+    let max_y = (v0[1].max(v1[1]).max(v2[1]).ceil() as i64).min(framebuffer.height as i64);
+
+    let vertices = [v0, v1, v2];
+    let edges = [(v1, v2), (v2, v0), (v0, v1)];
+    let top_left = [
+        is_top_left(&v1, &v2),
+        is_top_left(&v2, &v0),
+        is_top_left(&v0, &v1),
+    ];
+
+    for y in min_y..max_y.max(0) as u32 {
+        for x in min_x..max_x.max(0) as u32 {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+            let mut weights = [0.0; 3];
+            let mut inside = true;
+            for (i, (a, b)) in edges.iter().enumerate() {
+                weights[i] = edge(a, b, px, py);
+                inside &= weights[i] > 0.0 || (weights[i] == 0.0 && top_left[i]);
+            }
+            if !inside {
+                continue;
+            }
+
+            let depth = (weights[0] * v0[2] + weights[1] * v1[2] + weights[2] * v2[2]) / area;
+            let color = interpolate_color(&vertices, &weights);
+            write_pixel(framebuffer, state, x, y, depth, color);
+        }
+    }
+}
+
+/// Clips the line to the framebuffer, then steps one pixel at a time along the
+/// major axis, interpolating depth and colour.
+fn rasterize_line(framebuffer: &mut Framebuffer, state: &PipelineState, line: [ScreenVertex; 2]) {
+    let [a, b] = line;
+    // Liang-Barsky clipping, so lines running far off screen cost no more than
+    // their visible part. Done in f64 to keep the clipped end points accurate
+    // for lines much longer than the framebuffer.
+    let (ax, ay) = (a[0] as f64, a[1] as f64);
+    let (dx, dy) = (b[0] as f64 - ax, b[1] as f64 - ay);
+    let (width, height) = (framebuffer.width as f64, framebuffer.height as f64);
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (p, q) in [(-dx, ax), (dx, width - ax), (-dy, ay), (dy, height - ay)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return;
+            }
+        } else if p < 0.0 {
+            t0 = t0.max(q / p);
+        } else {
+            t1 = t1.min(q / p);
+        }
+    }
+    if t0 > t1 {
+        return;
+    }
+
+    let (x0, y0) = (ax + dx * t0, ay + dy * t0);
+    let (span_x, span_y) = (dx * (t1 - t0), dy * (t1 - t0));
+    let steps = span_x.abs().max(span_y.abs()).ceil().max(1.0) as u32;
+    for step in 0..=steps {
+        let s = step as f64 / steps as f64;
+        let x = (x0 + span_x * s).floor();
+        let y = (y0 + span_y * s).floor();
+        if x < 0.0 || y < 0.0 || x >= width || y >= height {
+            continue;
+        }
+        let t = (t0 + (t1 - t0) * s) as f32;
+        let depth = a[2] + (b[2] - a[2]) * t;
+        let color = interpolate_color(&line, &[1.0 - t, t]);
+        write_pixel(framebuffer, state, x as u32, y as u32, depth, color);
+    }
+}
+
+fn write_pixel(
+    framebuffer: &mut Framebuffer,
+    state: &PipelineState,
+    x: u32,
+    y: u32,
+    depth: f32,
+    color: [f32; 4],
+) {
+    let offset = framebuffer.offset(x, y);
+    if state.depth_test && depth > framebuffer.depth[offset] {
+        return;
+    }
+    if state.depth_write {
+        framebuffer.depth[offset] = depth;
+    }
+
+    let pixel = &mut framebuffer.color[offset * 4..offset * 4 + 4];
+    let mut dst = [0.0; 4];
+    for (value, &channel) in dst.iter_mut().zip(pixel.iter()) {
+        *value = channel as f32 / 255.0;
+    }
+    pixel.copy_from_slice(&to_rgba8(state.blend_mode.blend(color, dst)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+    use crate::constant::ConstantBuffer;
+    use crate::cube;
+    use crate::math::{Mat4, Vec3};
+    use crate::mesh::Mesh;
+    use crate::vertex::Vertex;
+    use crate::{draw, upload_mesh, upload_wireframe};
+
+    const YELLOW: [u8; 4] = [204, 204, 77, 255];
+
+    fn in_frame(width: u32, height: u32) -> CpuGraphicsDevice {
+        let mut device = CpuGraphicsDevice::new(width, height);
+        device.begin_frame([0.0, 0.0, 0.0, 1.0], 1.0).unwrap();
+        device
+    }
+
+    fn triangle_at(z: f32) -> Mesh {
+        let vertices = vec![
+            Vertex::new(-1.0, -1.0, z),
+            Vertex::new(-1.0, 1.0, z),
+            Vertex::new(1.0, -1.0, z),
+        ];
+        Mesh::new(vertices, vec![0, 1, 2]).unwrap()
+    }
+
+    #[test]
+    fn draws_yellow_cube() {
+        let mut device = in_frame(64, 64);
+        let mesh = cube::unit();
+        let gpu_mesh = upload_mesh(&mut device, &mesh).unwrap();
+        draw(&mut device, &gpu_mesh).unwrap();
+
+        let framebuffer = device.framebuffer();
+        assert_eq!(framebuffer.pixel(32, 32), YELLOW);
+        assert_eq!(framebuffer.pixel(17, 46), YELLOW);
+        assert_eq!(framebuffer.pixel(15, 32), CLEAR_COLOR);
+        assert_eq!(framebuffer.pixel(2, 2), CLEAR_COLOR);
+
+        // The front half of the cube lies behind the near plane, leaving the back face.
+        assert!((framebuffer.depth(32, 32) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn depth_test_keeps_nearest() {
+        let mut device = in_frame(16, 16);
+        let far = upload_mesh(&mut device, &triangle_at(0.8)).unwrap();
+        let near = upload_mesh(&mut device, &triangle_at(0.2)).unwrap();
+
+        draw(&mut device, &near).unwrap();
+        draw(&mut device, &far).unwrap();
+        assert!((device.framebuffer().depth(2, 8) - 0.2).abs() < 1e-6);
+
+        draw(&mut device, &near).unwrap();
+        assert!((device.framebuffer().depth(2, 8) - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn clips_geometry_outside_depth_range() {
+        let mut device = in_frame(16, 16);
+        for &z in [-0.1, 1.1].iter() {
+            let mesh = upload_mesh(&mut device, &triangle_at(z)).unwrap();
+            draw(&mut device, &mesh).unwrap();
+        }
+        assert_eq!(device.framebuffer(), &Framebuffer::new(16, 16));
+    }
+
+    #[test]
+    fn slot_zero_constants_transform_and_colour() {
+        let mut device = in_frame(16, 16);
+        let mesh = upload_mesh(&mut device, &triangle_at(0.5)).unwrap();
+        let constants = DrawConstants {
+            transform: Mat4::translation(Vec3::new(1.0, 0.0, 0.0)),
+            color: [1.0, 0.0, 0.0, 1.0],
+        };
+        let buffer = ConstantBuffer::new(&mut device, &constants).unwrap();
+        buffer.bind(&mut device, DrawConstants::SLOT).unwrap();
+        draw(&mut device, &mesh).unwrap();
+
+        // Shifted right by half the viewport, so the left half stays clear.
+        assert_eq!(device.framebuffer().pixel(2, 12), CLEAR_COLOR);
+        assert_eq!(device.framebuffer().pixel(9, 12), [255, 0, 0, 255]);
+
+        buffer.destroy(&mut device).unwrap();
+        assert_eq!(draw(&mut device, &mesh), Err(Error::InvalidHandle));
+    }
+
+    #[test]
+    fn instanced_draw_matches_separate_draws() {
+        let offsets = [-0.5, 0.5];
+        let tint = [0.5, 1.0, 1.0, 1.0];
+
+        let mut separate = in_frame(16, 16);
+        let mesh = upload_mesh(&mut separate, &triangle_at(0.5)).unwrap();
+        let mut constants = DrawConstants::default();
+        constants.color[0] *= tint[0];
+        let buffer = ConstantBuffer::new(&mut separate, &constants).unwrap();
+        buffer.bind(&mut separate, DrawConstants::SLOT).unwrap();
+        for &x in &offsets {
+            constants.transform = Mat4::translation(Vec3::new(x, 0.0, 0.0));
+            buffer.update(&mut separate, &constants).unwrap();
+            draw(&mut separate, &mesh).unwrap();
+        }
+
+        let mut instanced = in_frame(16, 16);
+        let mesh = upload_mesh(&mut instanced, &triangle_at(0.5)).unwrap();
+        let instances: Vec<Instance> = offsets
+            .iter()
+            .map(|&x| Instance::new(Mat4::translation(Vec3::new(x, 0.0, 0.0)), tint))
+            .collect();
+        let instance_buffer = instanced.create_instance_buffer(&instances).unwrap();
+        instanced
+            .draw_instanced(
+                mesh.vertex_buffer,
+                mesh.index_buffer,
+                mesh.index_count,
+                instance_buffer,
+                2,
+            )
+            .unwrap();
+
+        assert_eq!(separate.framebuffer(), instanced.framebuffer());
+        assert_eq!(
+            instanced.draw_instanced(
+                mesh.vertex_buffer,
+                mesh.index_buffer,
+                mesh.index_count,
+                instance_buffer,
+                3,
+            ),
+            Err(Error::InvalidInstanceRange {
+                requested: 3,
+                available: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn pipeline_state_culls_blends_and_skips_depth_writes() {
+        let mut device = in_frame(16, 16);
+        let mesh = upload_mesh(&mut device, &triangle_at(0.5)).unwrap();
+
+        // The triangle is wound clockwise on screen, so it faces the camera.
+        let cull_front = device
+            .create_pipeline_state(&PipelineState {
+                cull_mode: CullMode::Front,
+                ..PipelineState::default()
+            })
+            .unwrap();
+        device.bind_pipeline_state(cull_front).unwrap();
+        draw(&mut device, &mesh).unwrap();
+        assert_eq!(device.framebuffer(), &Framebuffer::new(16, 16));
+
+        let transparent = device
+            .create_pipeline_state(&PipelineState::transparent())
+            .unwrap();
+        device.bind_pipeline_state(transparent).unwrap();
+        device.destroy_pipeline_state(transparent).unwrap();
+        let constants = DrawConstants {
+            color: [1.0, 0.0, 0.0, 0.5],
+            ..DrawConstants::default()
+        };
+        let buffer = ConstantBuffer::new(&mut device, &constants).unwrap();
+        buffer.bind(&mut device, DrawConstants::SLOT).unwrap();
+        draw(&mut device, &mesh).unwrap();
+        assert_eq!(device.framebuffer().pixel(2, 12), [128, 0, 0, 191]);
+        assert_eq!(device.framebuffer().depth(2, 12), CLEAR_DEPTH);
+
+        assert_eq!(
+            device.bind_pipeline_state(transparent),
+            Err(Error::InvalidHandle)
+        );
+    }
+
+    #[test]
+    fn wireframe_draws_only_edges() {
+        let mut device = in_frame(16, 16);
+        let mesh = upload_mesh(&mut device, &triangle_at(0.5)).unwrap();
+        let wireframe = device
+            .create_pipeline_state(&PipelineState::wireframe())
+            .unwrap();
+        device.bind_pipeline_state(wireframe).unwrap();
+        draw(&mut device, &mesh).unwrap();
+
+        let framebuffer = device.framebuffer();
+        assert_eq!(framebuffer.pixel(0, 8), YELLOW);
+        assert_eq!(framebuffer.pixel(8, 8), YELLOW);
+        assert_eq!(framebuffer.pixel(4, 10), CLEAR_COLOR);
+        assert!((framebuffer.depth(0, 8) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lines_are_depth_tested() {
+        let mut device = in_frame(16, 16);
+        let quad = Mesh::new(
+            vec![
+                Vertex::new(-1.0, -1.0, 0.5),
+                Vertex::new(-1.0, 1.0, 0.5),
+                Vertex::new(1.0, 1.0, 0.5),
+                Vertex::new(1.0, -1.0, 0.5),
+            ],
+            vec![0, 1, 2, 0, 2, 3],
+        )
+        .unwrap();
+        let quad = upload_mesh(&mut device, &quad).unwrap();
+        draw(&mut device, &quad).unwrap();
+
+        // A horizontal line in front of the quad and a vertical one behind it.
+        let lines = Mesh::new_unchecked(
+            vec![
+                Vertex::new(-1.0, 0.0, 0.25),
+                Vertex::new(1.0, 0.0, 0.25),
+                Vertex::new(0.5, -1.0, 0.75),
+                Vertex::new(0.5, 1.0, 0.75),
+            ],
+            vec![0, 1, 2, 3],
+        );
+        let lines = upload_mesh(&mut device, &lines).unwrap();
+        let line_list = device
+            .create_pipeline_state(&PipelineState::lines())
+            .unwrap();
+        device.bind_pipeline_state(line_list).unwrap();
+        let constants = DrawConstants {
+            color: [1.0, 0.0, 0.0, 1.0],
+            ..DrawConstants::default()
+        };
+        let buffer = ConstantBuffer::new(&mut device, &constants).unwrap();
+        buffer.bind(&mut device, DrawConstants::SLOT).unwrap();
+        draw(&mut device, &lines).unwrap();
+
+        let framebuffer = device.framebuffer();
+        for x in 0..16 {
+            assert_eq!(framebuffer.pixel(x, 8), [255, 0, 0, 255]);
+            assert!((framebuffer.depth(x, 8) - 0.25).abs() < 1e-6);
+        }
+        assert_eq!(framebuffer.pixel(12, 3), YELLOW);
+        assert_eq!(framebuffer.pixel(12, 12), YELLOW);
+    }
+
+    #[test]
+    fn vertex_colours_are_interpolated() {
+        let mut device = in_frame(16, 16);
+        let mut vertices = vec![Vertex::new(-1.0, 0.0, 0.5), Vertex::new(1.0, 0.0, 0.5)];
+        vertices[0].color = [1.0, 0.0, 0.0, 1.0];
+        vertices[1].color = [0.0, 0.0, 1.0, 1.0];
+        let line = Mesh::new_unchecked(vertices, vec![0, 1]).with_attribute(VertexSemantic::Color);
+        let line = upload_mesh(&mut device, &line).unwrap();
+        let line_list = device
+            .create_pipeline_state(&PipelineState::lines())
+            .unwrap();
+        device.bind_pipeline_state(line_list).unwrap();
+        draw(&mut device, &line).unwrap();
+
+        // Vertex colours are multiplied by the default yellow.
+        let framebuffer = device.framebuffer();
+        assert_eq!(framebuffer.pixel(0, 8), [204, 0, 0, 255]);
+        assert_eq!(framebuffer.pixel(8, 8), [102, 0, 38, 255]);
+        assert_eq!(framebuffer.pixel(15, 8)[0], 13);
+    }
+
+    #[test]
+    fn huge_lines_are_clipped_to_the_framebuffer() {
+        let mut device = in_frame(64, 64);
+        // Already in clip space, so the screen space end points are a billion
+        // pixels either side of the target, and the second line misses it.
+        let line = Mesh::new_unchecked(
+            vec![
+                Vertex::new(-3e7, 0.0, 0.5),
+                Vertex::new(3e7, 0.0, 0.5),
+                Vertex::new(-3e7, 2.0, 0.5),
+                Vertex::new(3e7, 3.0, 0.5),
+            ],
+            vec![0, 1, 2, 3],
+        );
+        let line = upload_mesh(&mut device, &line).unwrap();
+        let line_list = device
+            .create_pipeline_state(&PipelineState::lines())
+            .unwrap();
+        device.bind_pipeline_state(line_list).unwrap();
+        draw(&mut device, &line).unwrap();
+
+        let framebuffer = device.framebuffer();
+        for x in 0..64 {
+            assert_eq!(framebuffer.pixel(x, 32), YELLOW);
+            assert_eq!(framebuffer.pixel(x, 0), [0, 0, 0, 255]);
+        }
+    }
+
+    #[test]
+    fn wireframe_of_subdivided_cube() {
+        let mesh = cube::generate(9);
+        let mut camera = Camera::default();
+        camera.look_at(Vec3::new(1.0, 1.5, -2.0), Vec3::ZERO, Vec3::Y);
+        let constants = DrawConstants {
+            transform: camera.view_projection(),
+            ..DrawConstants::default()
+        };
+
+        let mut solid = in_frame(64, 64);
+        let gpu_mesh = upload_mesh(&mut solid, &mesh).unwrap();
+        let buffer = ConstantBuffer::new(&mut solid, &constants).unwrap();
+        buffer.bind(&mut solid, DrawConstants::SLOT).unwrap();
+        draw(&mut solid, &gpu_mesh).unwrap();
+
+        let mut wireframe = in_frame(64, 64);
+        let gpu_mesh = upload_wireframe(&mut wireframe, &mesh).unwrap();
+        let buffer = ConstantBuffer::new(&mut wireframe, &constants).unwrap();
+        buffer.bind(&mut wireframe, DrawConstants::SLOT).unwrap();
+        let lines = wireframe
+            .create_pipeline_state(&PipelineState::lines())
+            .unwrap();
+        wireframe.bind_pipeline_state(lines).unwrap();
+        draw(&mut wireframe, &gpu_mesh).unwrap();
+
+        // Every line lies on the surface, so lines only land on or next to
+        // pixels covered by the solid cube, and leave gaps between them.
+        let covered =
+            |framebuffer: &Framebuffer, x: u32, y: u32| framebuffer.pixel(x, y) != CLEAR_COLOR;
+        let (mut lines, mut gaps) = (0, 0);
+        for y in 1..63 {
+            for x in 1..63 {
+                if covered(wireframe.framebuffer(), x, y) {
+                    let near_solid = (y - 1..=y + 1)
+                        .any(|ny| (x - 1..=x + 1).any(|nx| covered(solid.framebuffer(), nx, ny)));
+                    assert!(near_solid, "line drawn off the cube at ({}, {})", x, y);
+                    lines += 1;
+                } else if covered(solid.framebuffer(), x, y) {
+                    gaps += 1;
+                }
+            }
+        }
+        assert!(lines > 0 && gaps > 0);
+    }
+
+    #[test]
+    fn begin_frame_clears_target() {
+        let mut device = in_frame(4, 4);
+        let mesh = upload_mesh(&mut device, &triangle_at(0.5)).unwrap();
+        draw(&mut device, &mesh).unwrap();
+        device.end_frame().unwrap();
+        assert_eq!(draw(&mut device, &mesh), Err(Error::NoFrameInProgress));
+
+        device.begin_frame([0.0, 0.0, 1.0, 1.0], 0.25).unwrap();
+        assert_eq!(device.framebuffer().pixel(0, 3), [0, 0, 255, 255]);
+        assert_eq!(device.framebuffer().depth(0, 3), 0.25);
+
+        // Nothing passes a depth test against a buffer cleared to the near plane.
+        draw(&mut device, &mesh).unwrap();
+        assert_eq!(device.framebuffer().pixel(0, 3), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn rejects_stale_handles_and_index_ranges() {
+        let mut device = in_frame(4, 4);
+        let mesh = upload_mesh(&mut device, &triangle_at(0.5)).unwrap();
+
+        assert_eq!(
+            device.draw(mesh.vertex_buffer, mesh.index_buffer, 6),
+            Err(Error::InvalidIndexRange {
+                requested: 6,
+                available: 3,
+            })
+        );
+
+        device.destroy_index_buffer(mesh.index_buffer).unwrap();
+        assert_eq!(draw(&mut device, &mesh), Err(Error::InvalidHandle));
+    }
+}
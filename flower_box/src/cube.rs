@@ -1,2787 +1,272 @@
-pub struct Vertex {
-    x: f32,
-    y: f32,
-    z: f32,
+use std::collections::HashMap;
+
+use crate::bake;
+use crate::mesh::Mesh;
+use crate::uv::{self, Axis};
+use crate::vertex::{Vertex, VertexSemantic};
+
+/// The most segments `generate` and `generate_textured` accept: even with
+/// every face unwrapped separately, the `6 * (segments + 1)^2` vertices stay
+/// within reach of `u32` indices.
+pub const MAX_SEGMENTS: u32 = 26_753;
+
+// Baked from assets/cube.obj by the bake_mesh tool.
+const UNIT_CUBE: &[u8] = include_bytes!("../assets/cube.mesh");
+
+/// A unit cube centred on the origin made of 12 triangles with no shared
+/// vertices, wound clockwise when viewed from outside.
+pub fn unit() -> Mesh {
+    bake::decode(UNIT_CUBE).expect("the baked unit cube is valid")
 }
 
-pub const CUBE_VERTS: [Vertex; 36] = [
-    Vertex {
-        x: -0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: -0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: 0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: 0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: -0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: 0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: 0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: -0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: -0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: -0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: -0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: 0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: -0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: -0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: 0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: 0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: 0.5,
-        z: -0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: -0.5,
-        y: 0.5,
-        z: 0.5,
-    },
-    Vertex {
-        x: 0.5,
-        y: -0.5,
-        z: 0.5,
-    },
+// (normal axis, normal sign, u axis, v axis), chosen so that u x v points out of the cube.
+const FACES: [(usize, bool, usize, usize); 6] = [
+    (0, true, 1, 2),
+    (0, false, 2, 1),
+    (1, true, 2, 0),
+    (1, false, 0, 2),
+    (2, true, 0, 1),
+    (2, false, 1, 0),
 ];
 
-// pub const CUBE_VERTS: [Vertex; 488] = [
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: -0.05555,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: -0.05555,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: -0.05555,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: -0.05555,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: -0.05555,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: -0.16666,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: -0.16666,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: -0.16666,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: -0.16666,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: -0.16666,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: -0.27777,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: -0.27777,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: -0.27777,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: -0.27777,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: -0.27777,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: -0.38888,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: -0.38888,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: -0.38888,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: -0.38888,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.388889,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: -0.05555,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: -0.05555,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: -0.05555,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: -0.05555,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: -0.16666,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: -0.16666,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: -0.16666,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: -0.16666,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: -0.27777,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: -0.27777,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: -0.27777,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: -0.27777,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.500000,
-//         z: -0.38888,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: -0.38888,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: -0.38888,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: -0.38888,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: -0.38888,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.500000,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.500000,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.500000,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: 0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: 0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: 0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: 0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: -0.055556,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: -0.166667,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: -0.277778,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: -0.388889,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.055556,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.277778,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.388889,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.500000,
-//         z: 0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.500000,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.388889,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.388889,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.388889,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.388889,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.277778,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.277778,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.277778,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.277778,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.277778,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.166667,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.166667,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.166667,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.166667,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.166667,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: -0.055556,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: -0.055556,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: -0.055556,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: -0.055556,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: -0.055556,
-//         z: -0.50000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: -0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: -0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: -0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: -0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: -0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.055556,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.166667,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.277778,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.388889,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.166667,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.055556,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.388889,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.388889,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.500000,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.277778,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: -0.055556,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.166667,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.277778,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-//     Vertex {
-//         x: 0.500000,
-//         y: 0.500000,
-//         z: -0.500000,
-//     },
-// ];
+// The (column, row) cell of each entry in FACES in the 4x3 cross atlas:
+//
+//        +Y
+//    -X  -Z  +X  +Z
+//        -Y
+const CROSS_CELLS: [(u32, u32); 6] = [(2, 1), (0, 1), (1, 0), (1, 2), (3, 1), (1, 1)];
 
-pub const CUBE_INDICES: [u32; 36] = [
-    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
-    26, 27, 28, 29, 30, 31, 32, 33, 34, 35,
-];
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UvLayout {
+    /// Every face covers the whole 0..1 texture.
+    PerFace,
+    /// The faces are unfolded into a cross on a 4x3 grid of cells.
+    Cross,
+}
+
+/// Builds a unit cube centred on the origin with each face split into a
+/// `segments` x `segments` grid of quads.
+///
+/// Vertices on face edges and corners are shared between faces, giving
+/// `6 * segments^2 + 2` vertices and `12 * segments^2` triangles. Triangles are
+/// wound clockwise when viewed from outside the cube.
+///
+/// # Panics
+///
+/// If `segments` is zero or greater than `MAX_SEGMENTS`.
+pub fn generate(segments: u32) -> Mesh {
+    build(segments, None)
+}
+
+/// Like `generate`, but with texture coordinates unwrapped according to
+/// `layout`. Faces are split apart along every UV seam, so vertices are only
+/// shared across a cube edge where the two faces also touch in the texture.
+///
+/// # Panics
+///
+/// If `segments` is zero or greater than `MAX_SEGMENTS`.
+pub fn generate_textured(segments: u32, layout: UvLayout) -> Mesh {
+    build(segments, Some(layout)).with_attribute(VertexSemantic::TexCoord)
+}
+
+fn build(segments: u32, uv_layout: Option<UvLayout>) -> Mesh {
+    assert!(segments > 0, "a cube needs at least one segment per face");
+    assert!(
+        segments <= MAX_SEGMENTS,
+        "{} segments would need more vertices than u32 indices can address",
+        segments
+    );
+
+    let n = segments;
+    let quads = n as usize * n as usize;
+    let mut vertices = Vec::with_capacity(6 * quads + 2);
+    let mut indices = Vec::with_capacity(36 * quads);
+    // Vertices are shared between faces unless their texture coordinates differ;
+    // per-face unwrapping additionally keeps every face as its own island.
+    let mut lattice: HashMap<([u32; 3], [u32; 2], usize), u32> = HashMap::new();
+
+    for (face, (&(axis, positive, u_axis, v_axis), &cell)) in
+        FACES.iter().zip(CROSS_CELLS.iter()).enumerate()
+    {
+        let (right, up) = uv::plane_frame([Axis::X, Axis::Y, Axis::Z][axis], positive);
+        let island = match uv_layout {
+            Some(UvLayout::PerFace) => face,
+            _ => 0,
+        };
+        let mut vertex_index = |u: u32, v: u32| {
+            let mut coord = [0; 3];
+            coord[axis] = if positive { n } else { 0 };
+            coord[u_axis] = u;
+            coord[v_axis] = v;
+
+            let to_position = |c: u32| c as f32 / n as f32 - 0.5;
+            let mut vertex = Vertex::new(
+                to_position(coord[0]),
+                to_position(coord[1]),
+                to_position(coord[2]),
+            );
+
+            if let Some(uv_layout) = uv_layout {
+                let along = |direction: [f32; 3]| {
+                    let p = vertex.position;
+                    p[0] * direction[0] + p[1] * direction[1] + p[2] * direction[2]
+                };
+                let face_uv = [along(right) + 0.5, 0.5 - along(up)];
+                vertex.uv = match uv_layout {
+                    UvLayout::PerFace => face_uv,
+                    UvLayout::Cross => [
+                        (cell.0 as f32 + face_uv[0]) / 4.0,
+                        (cell.1 as f32 + face_uv[1]) / 3.0,
+                    ],
+                };
+            }
+
+            *lattice
+                .entry((coord, vertex.uv.map(f32::to_bits), island))
+                .or_insert_with(|| {
+                    vertices.push(vertex);
+                    (vertices.len() - 1) as u32
+                })
+        };
 
-//pub const CUBE_INDICES: [u32; 2916] = [
-//    390, 82, 399, 381, 5, 390, 0, 6, 381, 363, 7, 0, 1, 8, 363, 2, 9, 1, 337, 10, 2, 3, 11, 337, 4,
-//    12, 3, 5, 92, 82, 6, 14, 5, 7, 15, 6, 8, 16, 7, 9, 17, 8, 10, 18, 9, 11, 19, 10, 12, 20, 11,
-//    13, 21, 12, 14, 101, 92, 15, 22, 14, 16, 23, 15, 17, 24, 16, 18, 25, 17, 19, 26, 18, 20, 27,
-//    19, 21, 28, 20, 174, 29, 21, 22, 31, 101, 23, 32, 22, 24, 33, 23, 25, 34, 24, 26, 35, 25, 27,
-//    36, 26, 28, 37, 27, 29, 38, 28, 30, 39, 29, 32, 119, 31, 33, 40, 32, 34, 41, 33, 35, 42, 34,
-//    36, 43, 35, 37, 44, 36, 38, 45, 37, 39, 46, 38, 192, 47, 39, 40, 129, 119, 41, 49, 40, 42, 50,
-//    41, 43, 51, 42, 44, 52, 43, 45, 53, 44, 46, 54, 45, 47, 55, 46, 48, 56, 47, 49, 58, 129, 50,
-//    59, 49, 51, 60, 50, 52, 61, 51, 53, 62, 52, 54, 63, 53, 55, 64, 54, 56, 65, 55, 57, 66, 56, 59,
-//    146, 58, 60, 68, 59, 61, 69, 60, 62, 70, 61, 63, 71, 62, 64, 72, 63, 65, 73, 64, 66, 74, 65,
-//    67, 75, 66, 68, 482, 146, 69, 76, 68, 69, 453, 463, 71, 453, 70, 71, 434, 444, 73, 434, 72, 73,
-//    416, 425, 75, 416, 74, 225, 77, 75, 79, 82, 399, 400, 83, 79, 401, 84, 400, 402, 85, 401, 403,
-//    86, 402, 80, 87, 403, 81, 88, 80, 404, 89, 81, 239, 90, 404, 83, 92, 82, 84, 93, 83, 85, 94,
-//    84, 86, 95, 85, 87, 96, 86, 88, 97, 87, 89, 98, 88, 90, 99, 89, 91, 100, 90, 93, 101, 92, 94,
-//    102, 93, 95, 103, 94, 96, 104, 95, 97, 105, 96, 98, 106, 97, 99, 107, 98, 100, 108, 99, 255,
-//    109, 100, 102, 31, 101, 103, 110, 102, 104, 111, 103, 105, 112, 104, 106, 113, 105, 107, 114,
-//    106, 108, 115, 107, 109, 116, 108, 265, 117, 109, 31, 120, 119, 111, 120, 110, 112, 121, 111,
-//    113, 122, 112, 114, 123, 113, 115, 124, 114, 116, 125, 115, 117, 126, 116, 118, 127, 117, 120,
-//    129, 119, 121, 130, 120, 122, 131, 121, 123, 132, 122, 124, 133, 123, 125, 134, 124, 126, 135,
-//    125, 127, 136, 126, 128, 137, 127, 130, 58, 129, 131, 138, 130, 132, 139, 131, 133, 140, 132,
-//    134, 141, 133, 135, 142, 134, 136, 143, 135, 137, 144, 136, 292, 145, 137, 58, 147, 146, 139,
-//    147, 138, 140, 148, 139, 141, 149, 140, 142, 150, 141, 143, 151, 142, 144, 152, 143, 145, 153,
-//    144, 302, 154, 145, 147, 482, 146, 148, 155, 147, 149, 483, 148, 150, 156, 149, 151, 484, 150,
-//    152, 157, 151, 153, 485, 152, 154, 486, 153, 312, 158, 154, 159, 13, 4, 160, 166, 159, 161,
-//    167, 160, 162, 168, 161, 163, 169, 162, 164, 170, 163, 165, 171, 164, 327, 172, 165, 245, 173,
-//    327, 166, 174, 13, 167, 175, 166, 168, 176, 167, 169, 177, 168, 170, 178, 169, 171, 179, 170,
-//    172, 180, 171, 173, 181, 172, 254, 182, 173, 175, 30, 174, 176, 183, 175, 177, 184, 176, 178,
-//    185, 177, 179, 186, 178, 180, 187, 179, 181, 188, 180, 182, 189, 181, 264, 190, 182, 183, 192,
-//    30, 184, 193, 183, 185, 194, 184, 186, 195, 185, 187, 196, 186, 188, 197, 187, 189, 198, 188,
-//    190, 199, 189, 191, 200, 190, 193, 48, 192, 194, 201, 193, 195, 202, 194, 196, 203, 195, 197,
-//    204, 196, 198, 205, 197, 199, 206, 198, 200, 207, 199, 282, 208, 200, 201, 57, 48, 202, 209,
-//    201, 203, 210, 202, 204, 211, 203, 205, 212, 204, 206, 213, 205, 207, 214, 206, 208, 215, 207,
-//    291, 216, 208, 209, 67, 57, 210, 217, 209, 211, 218, 210, 212, 219, 211, 213, 220, 212, 214,
-//    221, 213, 215, 222, 214, 216, 223, 215, 301, 224, 216, 217, 225, 67, 218, 226, 217, 219, 227,
-//    218, 220, 228, 219, 221, 229, 220, 222, 230, 221, 223, 231, 222, 224, 232, 223, 311, 233, 224,
-//    226, 78, 225, 227, 234, 226, 228, 235, 227, 229, 405, 228, 230, 236, 229, 231, 237, 230, 232,
-//    238, 231, 233, 406, 232, 321, 407, 233, 240, 91, 239, 241, 246, 240, 380, 247, 241, 242, 248,
-//    380, 243, 249, 242, 354, 250, 243, 244, 251, 354, 336, 252, 244, 245, 253, 336, 246, 255, 91,
-//    247, 256, 246, 248, 257, 247, 249, 258, 248, 250, 259, 249, 251, 260, 250, 252, 261, 251, 253,
-//    262, 252, 254, 263, 253, 256, 265, 255, 257, 266, 256, 258, 267, 257, 259, 268, 258, 260, 269,
-//    259, 261, 270, 260, 262, 271, 261, 263, 272, 262, 264, 273, 263, 266, 118, 265, 267, 274, 266,
-//    268, 275, 267, 269, 276, 268, 270, 277, 269, 271, 278, 270, 272, 279, 271, 273, 280, 272, 191,
-//    281, 273, 274, 128, 118, 275, 283, 274, 276, 284, 275, 277, 285, 276, 278, 286, 277, 279, 287,
-//    278, 280, 288, 279, 281, 289, 280, 282, 290, 281, 283, 292, 128, 284, 293, 283, 285, 294, 284,
-//    286, 295, 285, 287, 296, 286, 288, 297, 287, 289, 298, 288, 290, 299, 289, 291, 300, 290, 293,
-//    302, 292, 294, 303, 293, 295, 304, 294, 296, 305, 295, 297, 306, 296, 298, 307, 297, 299, 308,
-//    298, 300, 309, 299, 301, 310, 300, 303, 312, 302, 304, 313, 303, 305, 314, 304, 306, 315, 305,
-//    307, 316, 306, 308, 317, 307, 309, 318, 308, 310, 319, 309, 311, 320, 310, 313, 487, 312, 314,
-//    481, 313, 314, 462, 472, 315, 322, 462, 316, 443, 322, 317, 323, 443, 319, 323, 318, 320, 324,
-//    319, 321, 325, 320, 159, 3, 4, 160, 328, 159, 161, 329, 160, 162, 330, 161, 163, 331, 162, 164,
-//    332, 163, 165, 333, 164, 327, 334, 165, 245, 335, 327, 328, 337, 3, 329, 338, 328, 330, 339,
-//    329, 331, 340, 330, 332, 341, 331, 333, 342, 332, 334, 343, 333, 335, 344, 334, 336, 345, 335,
-//    338, 2, 337, 339, 346, 338, 340, 347, 339, 341, 348, 340, 342, 349, 341, 343, 350, 342, 344,
-//    351, 343, 345, 352, 344, 244, 353, 345, 346, 1, 2, 347, 355, 346, 348, 356, 347, 349, 357, 348,
-//    350, 358, 349, 351, 359, 350, 352, 360, 351, 353, 361, 352, 354, 362, 353, 355, 363, 1, 356,
-//    364, 355, 357, 365, 356, 358, 366, 357, 359, 367, 358, 360, 368, 359, 361, 369, 360, 362, 370,
-//    361, 362, 242, 371, 364, 0, 363, 365, 372, 364, 366, 373, 365, 367, 374, 366, 368, 375, 367,
-//    369, 376, 368, 370, 377, 369, 371, 378, 370, 371, 380, 379, 372, 381, 0, 373, 382, 372, 374,
-//    383, 373, 375, 384, 374, 376, 385, 375, 377, 386, 376, 378, 387, 377, 379, 388, 378, 380, 389,
-//    379, 382, 390, 381, 383, 391, 382, 384, 392, 383, 385, 393, 384, 386, 394, 385, 387, 395, 386,
-//    388, 396, 387, 389, 397, 388, 241, 398, 389, 391, 399, 390, 392, 79, 391, 393, 400, 392, 394,
-//    401, 393, 395, 402, 394, 396, 403, 395, 397, 80, 396, 398, 81, 397, 240, 404, 398, 234, 77, 78,
-//    235, 408, 234, 405, 409, 235, 236, 410, 405, 237, 411, 236, 238, 412, 237, 406, 413, 238, 407,
-//    414, 406, 326, 415, 407, 408, 416, 77, 409, 417, 408, 410, 418, 409, 411, 419, 410, 412, 420,
-//    411, 413, 421, 412, 414, 422, 413, 415, 423, 414, 325, 424, 415, 417, 425, 416, 418, 426, 417,
-//    419, 427, 418, 420, 428, 419, 421, 429, 420, 422, 430, 421, 423, 431, 422, 424, 432, 423, 424,
-//    323, 433, 426, 434, 425, 427, 435, 426, 428, 436, 427, 429, 437, 428, 430, 438, 429, 431, 439,
-//    430, 432, 440, 431, 433, 441, 432, 433, 443, 442, 435, 444, 434, 436, 445, 435, 437, 446, 436,
-//    438, 447, 437, 439, 448, 438, 440, 449, 439, 441, 450, 440, 442, 451, 441, 442, 322, 452, 445,
-//    453, 444, 446, 454, 445, 447, 455, 446, 448, 456, 447, 449, 457, 448, 450, 458, 449, 451, 459,
-//    450, 452, 460, 451, 452, 462, 461, 454, 463, 453, 455, 464, 454, 456, 465, 455, 457, 466, 456,
-//    458, 467, 457, 459, 468, 458, 460, 469, 459, 461, 470, 460, 462, 471, 461, 464, 76, 463, 465,
-//    473, 464, 466, 474, 465, 467, 475, 466, 468, 476, 467, 469, 477, 468, 470, 478, 469, 471, 479,
-//    470, 472, 480, 471, 76, 155, 482, 474, 155, 473, 475, 483, 474, 476, 156, 475, 477, 484, 476,
-//    478, 157, 477, 479, 485, 478, 480, 486, 479, 481, 158, 480, 390, 5, 82, 381, 6, 5, 0, 7, 6,
-//    363, 8, 7, 1, 9, 8, 2, 10, 9, 337, 11, 10, 3, 12, 11, 4, 13, 12, 5, 14, 92, 6, 15, 14, 7, 16,
-//    15, 8, 17, 16, 9, 18, 17, 10, 19, 18, 11, 20, 19, 12, 21, 20, 13, 174, 21, 14, 22, 101, 15, 23,
-//    22, 16, 24, 23, 17, 25, 24, 18, 26, 25, 19, 27, 26, 20, 28, 27, 21, 29, 28, 174, 30, 29, 22,
-//    32, 31, 23, 33, 32, 24, 34, 33, 25, 35, 34, 26, 36, 35, 27, 37, 36, 28, 38, 37, 29, 39, 38, 30,
-//    192, 39, 32, 40, 119, 33, 41, 40, 34, 42, 41, 35, 43, 42, 36, 44, 43, 37, 45, 44, 38, 46, 45,
-//    39, 47, 46, 192, 48, 47, 40, 49, 129, 41, 50, 49, 42, 51, 50, 43, 52, 51, 44, 53, 52, 45, 54,
-//    53, 46, 55, 54, 47, 56, 55, 48, 57, 56, 49, 59, 58, 50, 60, 59, 51, 61, 60, 52, 62, 61, 53, 63,
-//    62, 54, 64, 63, 55, 65, 64, 56, 66, 65, 57, 67, 66, 59, 68, 146, 60, 69, 68, 61, 70, 69, 62,
-//    71, 70, 63, 72, 71, 64, 73, 72, 65, 74, 73, 66, 75, 74, 67, 225, 75, 68, 76, 482, 69, 463, 76,
-//    69, 70, 453, 71, 444, 453, 71, 72, 434, 73, 425, 434, 73, 74, 416, 75, 77, 416, 225, 78, 77,
-//    79, 83, 82, 400, 84, 83, 401, 85, 84, 402, 86, 85, 403, 87, 86, 80, 88, 87, 81, 89, 88, 404,
-//    90, 89, 239, 91, 90, 83, 93, 92, 84, 94, 93, 85, 95, 94, 86, 96, 95, 87, 97, 96, 88, 98, 97,
-//    89, 99, 98, 90, 100, 99, 91, 255, 100, 93, 102, 101, 94, 103, 102, 95, 104, 103, 96, 105, 104,
-//    97, 106, 105, 98, 107, 106, 99, 108, 107, 100, 109, 108, 255, 265, 109, 102, 110, 31, 103, 111,
-//    110, 104, 112, 111, 105, 113, 112, 106, 114, 113, 107, 115, 114, 108, 116, 115, 109, 117, 116,
-//    265, 118, 117, 31, 110, 120, 111, 121, 120, 112, 122, 121, 113, 123, 122, 114, 124, 123, 115,
-//    125, 124, 116, 126, 125, 117, 127, 126, 118, 128, 127, 120, 130, 129, 121, 131, 130, 122, 132,
-//    131, 123, 133, 132, 124, 134, 133, 125, 135, 134, 126, 136, 135, 127, 137, 136, 128, 292, 137,
-//    130, 138, 58, 131, 139, 138, 132, 140, 139, 133, 141, 140, 134, 142, 141, 135, 143, 142, 136,
-//    144, 143, 137, 145, 144, 292, 302, 145, 58, 138, 147, 139, 148, 147, 140, 149, 148, 141, 150,
-//    149, 142, 151, 150, 143, 152, 151, 144, 153, 152, 145, 154, 153, 302, 312, 154, 147, 155, 482,
-//    148, 483, 155, 149, 156, 483, 150, 484, 156, 151, 157, 484, 152, 485, 157, 153, 486, 485, 154,
-//    158, 486, 312, 487, 158, 159, 166, 13, 160, 167, 166, 161, 168, 167, 162, 169, 168, 163, 170,
-//    169, 164, 171, 170, 165, 172, 171, 327, 173, 172, 245, 254, 173, 166, 175, 174, 167, 176, 175,
-//    168, 177, 176, 169, 178, 177, 170, 179, 178, 171, 180, 179, 172, 181, 180, 173, 182, 181, 254,
-//    264, 182, 175, 183, 30, 176, 184, 183, 177, 185, 184, 178, 186, 185, 179, 187, 186, 180, 188,
-//    187, 181, 189, 188, 182, 190, 189, 264, 191, 190, 183, 193, 192, 184, 194, 193, 185, 195, 194,
-//    186, 196, 195, 187, 197, 196, 188, 198, 197, 189, 199, 198, 190, 200, 199, 191, 282, 200, 193,
-//    201, 48, 194, 202, 201, 195, 203, 202, 196, 204, 203, 197, 205, 204, 198, 206, 205, 199, 207,
-//    206, 200, 208, 207, 282, 291, 208, 201, 209, 57, 202, 210, 209, 203, 211, 210, 204, 212, 211,
-//    205, 213, 212, 206, 214, 213, 207, 215, 214, 208, 216, 215, 291, 301, 216, 209, 217, 67, 210,
-//    218, 217, 211, 219, 218, 212, 220, 219, 213, 221, 220, 214, 222, 221, 215, 223, 222, 216, 224,
-//    223, 301, 311, 224, 217, 226, 225, 218, 227, 226, 219, 228, 227, 220, 229, 228, 221, 230, 229,
-//    222, 231, 230, 223, 232, 231, 224, 233, 232, 311, 321, 233, 226, 234, 78, 227, 235, 234, 228,
-//    405, 235, 229, 236, 405, 230, 237, 236, 231, 238, 237, 232, 406, 238, 233, 407, 406, 321, 326,
-//    407, 240, 246, 91, 241, 247, 246, 380, 248, 247, 242, 249, 248, 243, 250, 249, 354, 251, 250,
-//    244, 252, 251, 336, 253, 252, 245, 254, 253, 246, 256, 255, 247, 257, 256, 248, 258, 257, 249,
-//    259, 258, 250, 260, 259, 251, 261, 260, 252, 262, 261, 253, 263, 262, 254, 264, 263, 256, 266,
-//    265, 257, 267, 266, 258, 268, 267, 259, 269, 268, 260, 270, 269, 261, 271, 270, 262, 272, 271,
-//    263, 273, 272, 264, 191, 273, 266, 274, 118, 267, 275, 274, 268, 276, 275, 269, 277, 276, 270,
-//    278, 277, 271, 279, 278, 272, 280, 279, 273, 281, 280, 191, 282, 281, 274, 283, 128, 275, 284,
-//    283, 276, 285, 284, 277, 286, 285, 278, 287, 286, 279, 288, 287, 280, 289, 288, 281, 290, 289,
-//    282, 291, 290, 283, 293, 292, 284, 294, 293, 285, 295, 294, 286, 296, 295, 287, 297, 296, 288,
-//    298, 297, 289, 299, 298, 290, 300, 299, 291, 301, 300, 293, 303, 302, 294, 304, 303, 295, 305,
-//    304, 296, 306, 305, 297, 307, 306, 298, 308, 307, 299, 309, 308, 300, 310, 309, 301, 311, 310,
-//    303, 313, 312, 304, 314, 313, 305, 315, 314, 306, 316, 315, 307, 317, 316, 308, 318, 317, 309,
-//    319, 318, 310, 320, 319, 311, 321, 320, 313, 481, 487, 314, 472, 481, 314, 315, 462, 315, 316,
-//    322, 316, 317, 443, 317, 318, 323, 319, 324, 323, 320, 325, 324, 321, 326, 325, 159, 328, 3,
-//    160, 329, 328, 161, 330, 329, 162, 331, 330, 163, 332, 331, 164, 333, 332, 165, 334, 333, 327,
-//    335, 334, 245, 336, 335, 328, 338, 337, 329, 339, 338, 330, 340, 339, 331, 341, 340, 332, 342,
-//    341, 333, 343, 342, 334, 344, 343, 335, 345, 344, 336, 244, 345, 338, 346, 2, 339, 347, 346,
-//    340, 348, 347, 341, 349, 348, 342, 350, 349, 343, 351, 350, 344, 352, 351, 345, 353, 352, 244,
-//    354, 353, 346, 355, 1, 347, 356, 355, 348, 357, 356, 349, 358, 357, 350, 359, 358, 351, 360,
-//    359, 352, 361, 360, 353, 362, 361, 354, 243, 362, 355, 364, 363, 356, 365, 364, 357, 366, 365,
-//    358, 367, 366, 359, 368, 367, 360, 369, 368, 361, 370, 369, 362, 371, 370, 362, 243, 242, 364,
-//    372, 0, 365, 373, 372, 366, 374, 373, 367, 375, 374, 368, 376, 375, 369, 377, 376, 370, 378,
-//    377, 371, 379, 378, 371, 242, 380, 372, 382, 381, 373, 383, 382, 374, 384, 383, 375, 385, 384,
-//    376, 386, 385, 377, 387, 386, 378, 388, 387, 379, 389, 388, 380, 241, 389, 382, 391, 390, 383,
-//    392, 391, 384, 393, 392, 385, 394, 393, 386, 395, 394, 387, 396, 395, 388, 397, 396, 389, 398,
-//    397, 241, 240, 398, 391, 79, 399, 392, 400, 79, 393, 401, 400, 394, 402, 401, 395, 403, 402,
-//    396, 80, 403, 397, 81, 80, 398, 404, 81, 240, 239, 404, 234, 408, 77, 235, 409, 408, 405, 410,
-//    409, 236, 411, 410, 237, 412, 411, 238, 413, 412, 406, 414, 413, 407, 415, 414, 326, 325, 415,
-//    408, 417, 416, 409, 418, 417, 410, 419, 418, 411, 420, 419, 412, 421, 420, 413, 422, 421, 414,
-//    423, 422, 415, 424, 423, 325, 324, 424, 417, 426, 425, 418, 427, 426, 419, 428, 427, 420, 429,
-//    428, 421, 430, 429, 422, 431, 430, 423, 432, 431, 424, 433, 432, 424, 324, 323, 426, 435, 434,
-//    427, 436, 435, 428, 437, 436, 429, 438, 437, 430, 439, 438, 431, 440, 439, 432, 441, 440, 433,
-//    442, 441, 433, 323, 443, 435, 445, 444, 436, 446, 445, 437, 447, 446, 438, 448, 447, 439, 449,
-//    448, 440, 450, 449, 441, 451, 450, 442, 452, 451, 442, 443, 322, 445, 454, 453, 446, 455, 454,
-//    447, 456, 455, 448, 457, 456, 449, 458, 457, 450, 459, 458, 451, 460, 459, 452, 461, 460, 452,
-//    322, 462, 454, 464, 463, 455, 465, 464, 456, 466, 465, 457, 467, 466, 458, 468, 467, 459, 469,
-//    468, 460, 470, 469, 461, 471, 470, 462, 472, 471, 464, 473, 76, 465, 474, 473, 466, 475, 474,
-//    467, 476, 475, 468, 477, 476, 469, 478, 477, 470, 479, 478, 471, 480, 479, 472, 481, 480, 76,
-//    473, 155, 474, 483, 155, 475, 156, 483, 476, 484, 156, 477, 157, 484, 478, 485, 157, 479, 486,
-//    485, 480, 158, 486, 481, 487, 158,
-//];
+        for v in 0..n {
+            for u in 0..n {
+                let p00 = vertex_index(u, v);
+                let p10 = vertex_index(u + 1, v);
+                let p11 = vertex_index(u + 1, v + 1);
+                let p01 = vertex_index(u, v + 1);
+
+                indices.extend_from_slice(&[p00, p10, p11, p00, p11, p01]);
+            }
+        }
+    }
+
+    Mesh::new_unchecked(vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::obj;
+
+    #[test]
+    fn baked_unit_cube_matches_source() {
+        let source = obj::parse_mesh(include_str!("../assets/cube.obj")).unwrap();
+        assert_eq!(
+            unit(),
+            source,
+            "rebake with `cargo run --bin bake_mesh assets/cube.obj assets/cube.mesh`"
+        );
+        assert_eq!(unit().vertices().len(), 36);
+        assert_eq!(unit().triangle_count(), 12);
+    }
+
+    #[test]
+    fn max_segments_fit_u32_indices() {
+        let vertices = |segments: u64| 6 * (segments + 1) * (segments + 1);
+        assert!(vertices(MAX_SEGMENTS as u64) <= u32::MAX as u64);
+        assert!(vertices(MAX_SEGMENTS as u64 + 1) > u32::MAX as u64);
+    }
+
+    #[test]
+    fn vertex_and_triangle_counts() {
+        for segments in 1..=9 {
+            let (vertices, indices) = generate(segments).into_parts();
+            assert_eq!(vertices.len() as u32, 6 * segments * segments + 2);
+            assert_eq!(indices.len() as u32, 3 * 12 * segments * segments);
+        }
+    }
+
+    #[test]
+    fn matches_exported_subdivided_cube() {
+        let (vertices, indices) = generate(9).into_parts();
+        assert_eq!(vertices.len(), 488);
+        assert_eq!(indices.len(), 2916);
+    }
+
+    #[test]
+    fn vertices_lie_on_unit_cube() {
+        let vertices = generate(4).vertices().to_vec();
+        for v in vertices {
+            let [x, y, z] = v.position;
+            let max = x.abs().max(y.abs()).max(z.abs());
+            assert!((max - 0.5).abs() < 1e-6, "{:?} is not on the surface", v);
+        }
+    }
+
+    #[test]
+    fn triangles_face_outwards() {
+        let (vertices, indices) = generate(3).into_parts();
+        for triangle in indices.chunks(3) {
+            let [a, b, c] = [
+                vertices[triangle[0] as usize].position,
+                vertices[triangle[1] as usize].position,
+                vertices[triangle[2] as usize].position,
+            ];
+            let e1 = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let e2 = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let normal = [
+                e1[1] * e2[2] - e1[2] * e2[1],
+                e1[2] * e2[0] - e1[0] * e2[2],
+                e1[0] * e2[1] - e1[1] * e2[0],
+            ];
+            let centroid = [
+                (a[0] + b[0] + c[0]) / 3.0,
+                (a[1] + b[1] + c[1]) / 3.0,
+                (a[2] + b[2] + c[2]) / 3.0,
+            ];
+            let facing =
+                normal[0] * centroid[0] + normal[1] * centroid[1] + normal[2] * centroid[2];
+            assert!(facing > 0.0);
+        }
+    }
+
+    #[test]
+    fn per_face_uvs_split_every_edge() {
+        for segments in 1..=4 {
+            let mesh = generate_textured(segments, UvLayout::PerFace);
+            let per_face = (segments + 1) * (segments + 1);
+            assert_eq!(mesh.vertices().len() as u32, 6 * per_face);
+            assert_eq!(mesh.triangle_count() as u32, 12 * segments * segments);
+            assert!(mesh.has_attribute(VertexSemantic::TexCoord));
+
+            for vertex in mesh.vertices() {
+                assert!(vertex.uv.iter().all(|&c| (0.0..=1.0).contains(&c)));
+            }
+        }
+    }
+
+    #[test]
+    fn cross_uvs_share_edges_that_touch_in_the_atlas() {
+        for segments in 1..=4 {
+            let mesh = generate_textured(segments, UvLayout::Cross);
+            let per_face = (segments + 1) * (segments + 1);
+            assert_eq!(
+                mesh.vertices().len() as u32,
+                6 * per_face - 5 * (segments + 1)
+            );
+        }
+    }
+
+    #[test]
+    fn cross_faces_stay_in_their_cells() {
+        let mesh = generate_textured(2, UvLayout::Cross);
+        for triangle in mesh.indices().chunks(3) {
+            let uvs: Vec<[f32; 2]> = triangle
+                .iter()
+                .map(|&i| mesh.vertices()[i as usize].uv)
+                .collect();
+            let centre = [
+                (uvs[0][0] + uvs[1][0] + uvs[2][0]) / 3.0,
+                (uvs[0][1] + uvs[1][1] + uvs[2][1]) / 3.0,
+            ];
+            let cell = ((centre[0] * 4.0) as u32, (centre[1] * 3.0) as u32);
+            assert!(CROSS_CELLS.contains(&cell));
+
+            for uv in uvs {
+                assert!(uv[0] >= cell.0 as f32 / 4.0 && uv[0] <= (cell.0 + 1) as f32 / 4.0);
+                assert!(uv[1] >= cell.1 as f32 / 3.0 && uv[1] <= (cell.1 + 1) as f32 / 3.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use std::f32::consts::PI;
+
+use crate::constant::{ConstantBuffer, DrawConstants};
+use crate::math::{Mat4, Vec3};
+use crate::pipeline::PipelineState;
+use crate::vertex::{Vertex, VertexLayout, VertexSemantic};
+use crate::{GraphicsDevice, IndexBufferHandle, PipelineStateHandle, Result, VertexBufferHandle};
+
+/// Segments in each of the three circles drawn for a sphere.
+pub const SPHERE_SEGMENTS: u32 = 24;
+
+// Buffers are never smaller than this many vertices.
+const MIN_CAPACITY: usize = 256;
+
+pub const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+pub const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+pub const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+struct LineBuffers {
+    vertex_buffer: VertexBufferHandle,
+    index_buffer: IndexBufferHandle,
+    capacity: usize,
+}
+
+/// Collects coloured lines in world space during a frame and draws them all
+/// with a single line-list draw in `flush`.
+///
+/// The vertex buffer is overwritten in place each frame and only recreated,
+/// at twice the size, when a frame has more lines than it can hold. The
+/// index buffer simply counts up, so it never needs updating.
+pub struct DebugDraw {
+    vertices: Vec<Vertex>,
+    layout: VertexLayout,
+    buffers: Option<LineBuffers>,
+    constants: ConstantBuffer<DrawConstants>,
+    pipeline_state: PipelineStateHandle,
+}
+
+impl DebugDraw {
+    pub fn new(graphics_device: &mut dyn GraphicsDevice) -> Result<DebugDraw> {
+        let constants = ConstantBuffer::new(graphics_device, &DrawConstants::default())?;
+        let pipeline_state = graphics_device.create_pipeline_state(&PipelineState::lines())?;
+        Ok(DebugDraw {
+            vertices: Vec::new(),
+            layout: Vertex::layout(&[VertexSemantic::Position, VertexSemantic::Color]),
+            buffers: None,
+            constants,
+            pipeline_state,
+        })
+    }
+
+    /// The number of lines waiting for the next `flush`.
+    pub fn line_count(&self) -> usize {
+        self.vertices.len() / 2
+    }
+
+    pub fn line(&mut self, from: Vec3, to: Vec3, color: [f32; 4]) {
+        for point in [from, to].iter() {
+            let mut vertex = Vertex::new(point.x, point.y, point.z);
+            vertex.color = color;
+            self.vertices.push(vertex);
+        }
+    }
+
+    /// The twelve edges of an axis-aligned box.
+    pub fn aabb(&mut self, min: Vec3, max: Vec3, color: [f32; 4]) {
+        let corner = |i: usize| {
+            Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        };
+        for i in 0..8 {
+            for &axis in [1, 2, 4].iter() {
+                if i & axis == 0 {
+                    self.line(corner(i), corner(i | axis), color);
+                }
+            }
+        }
+    }
+
+    /// The x, y and z axes of `transform` in red, green and blue, each `size`
+    /// long.
+    pub fn axes(&mut self, transform: &Mat4, size: f32) {
+        let origin = transform.transform_point(Vec3::ZERO);
+        for &(axis, color) in [(Vec3::X, RED), (Vec3::Y, GREEN), (Vec3::Z, BLUE)].iter() {
+            let end = transform.transform_point(axis * size);
+            self.line(origin, end, color);
+        }
+    }
+
+    /// A square grid on the plane `y = center.y`, `size` wide with `cells`
+    /// cells along each side.
+    pub fn grid(&mut self, center: Vec3, size: f32, cells: u32, color: [f32; 4]) {
+        let half = size * 0.5;
+        for i in 0..=cells {
+            let offset = -half + size * i as f32 / cells.max(1) as f32;
+            self.line(
+                center + Vec3::new(offset, 0.0, -half),
+                center + Vec3::new(offset, 0.0, half),
+                color,
+            );
+            self.line(
+                center + Vec3::new(-half, 0.0, offset),
+                center + Vec3::new(half, 0.0, offset),
+                color,
+            );
+        }
+    }
+
+    /// Circles around the x, y and z axes.
+    pub fn sphere(&mut self, center: Vec3, radius: f32, color: [f32; 4]) {
+        let point = |axis: usize, segment: u32| {
+            let angle = 2.0 * PI * segment as f32 / SPHERE_SEGMENTS as f32;
+            let (sin, cos) = angle.sin_cos();
+            let offset = match axis {
+                0 => Vec3::new(0.0, cos, sin),
+                1 => Vec3::new(cos, 0.0, sin),
+                _ => Vec3::new(cos, sin, 0.0),
+            };
+            center + offset * radius
+        };
+        for axis in 0..3 {
+            for segment in 0..SPHERE_SEGMENTS {
+                self.line(point(axis, segment), point(axis, segment + 1), color);
+            }
+        }
+    }
+
+    /// Draws everything collected since the last flush and clears it. Must be
+    /// called between `begin_frame` and `end_frame`.
+    ///
+    /// Leaves the line-list pipeline state and the debug draw's constants
+    /// bound, so other draws need to bind their own afterwards.
+    pub fn flush(
+        &mut self,
+        graphics_device: &mut dyn GraphicsDevice,
+        view_projection: &Mat4,
+    ) -> Result<()> {
+        if self.vertices.is_empty() {
+            return Ok(());
+        }
+
+        let (vertex_buffer, index_buffer) = self.upload(graphics_device)?;
+        let constants = DrawConstants {
+            transform: *view_projection,
+            color: [1.0, 1.0, 1.0, 1.0],
+        };
+        self.constants.update(graphics_device, &constants)?;
+        self.constants.bind(graphics_device, DrawConstants::SLOT)?;
+        graphics_device.bind_pipeline_state(self.pipeline_state)?;
+        graphics_device.draw(vertex_buffer, index_buffer, self.vertices.len() as u32)?;
+
+        self.vertices.clear();
+        Ok(())
+    }
+
+    pub fn release(self, graphics_device: &mut dyn GraphicsDevice) -> Result<()> {
+        if let Some(buffers) = self.buffers {
+            graphics_device.destroy_vertex_buffer(buffers.vertex_buffer)?;
+            graphics_device.destroy_index_buffer(buffers.index_buffer)?;
+        }
+        graphics_device.destroy_pipeline_state(self.pipeline_state)?;
+        self.constants.destroy(graphics_device)
+    }
+
+    fn upload(
+        &mut self,
+        graphics_device: &mut dyn GraphicsDevice,
+    ) -> Result<(VertexBufferHandle, IndexBufferHandle)> {
+        let mut data = Vertex::write(&self.vertices, &self.layout);
+        if let Some(buffers) = &self.buffers {
+            if buffers.capacity >= self.vertices.len() {
+                graphics_device.update_vertex_buffer(buffers.vertex_buffer, &data)?;
+                return Ok((buffers.vertex_buffer, buffers.index_buffer));
+            }
+        }
+
+        if let Some(buffers) = self.buffers.take() {
+            graphics_device.destroy_vertex_buffer(buffers.vertex_buffer)?;
+            graphics_device.destroy_index_buffer(buffers.index_buffer)?;
+        }
+
+        let capacity = self.vertices.len().next_power_of_two().max(MIN_CAPACITY);
+        data.resize(capacity * self.layout.stride() as usize, 0);
+        let indices: Vec<u32> = (0..capacity as u32).collect();
+        let vertex_buffer = graphics_device.create_vertex_buffer(&data, &self.layout)?;
+        let index_buffer = match graphics_device.create_index_buffer(&indices) {
+            Ok(index_buffer) => index_buffer,
+            Err(error) => {
+                graphics_device.destroy_vertex_buffer(vertex_buffer)?;
+                return Err(error);
+            }
+        };
+        self.buffers = Some(LineBuffers {
+            vertex_buffer,
+            index_buffer,
+            capacity,
+        });
+        Ok((vertex_buffer, index_buffer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::CpuGraphicsDevice;
+    use crate::recording::{Command, RecordingGraphicsDevice};
+    use crate::CLEAR_DEPTH;
+
+    #[test]
+    fn shapes_add_expected_lines() {
+        let mut device = RecordingGraphicsDevice::new();
+        let mut debug = DebugDraw::new(&mut device).unwrap();
+
+        debug.aabb(Vec3::ZERO, Vec3::ONE, RED);
+        assert_eq!(debug.line_count(), 12);
+        debug.axes(&Mat4::IDENTITY, 1.0);
+        assert_eq!(debug.line_count(), 15);
+        debug.grid(Vec3::ZERO, 10.0, 10, GREEN);
+        assert_eq!(debug.line_count(), 37);
+        debug.sphere(Vec3::ZERO, 1.0, BLUE);
+        assert_eq!(debug.line_count(), 37 + 3 * SPHERE_SEGMENTS as usize);
+
+        // Every box edge runs along one axis and has unit length.
+        for edge in debug.vertices[..24].chunks(2) {
+            let from = Vec3::from(edge[0].position);
+            let to = Vec3::from(edge[1].position);
+            assert_eq!((to - from).length(), 1.0);
+        }
+    }
+
+    #[test]
+    fn flush_reuses_buffers_until_they_overflow() {
+        let mut device = RecordingGraphicsDevice::new();
+        let mut debug = DebugDraw::new(&mut device).unwrap();
+        device.begin_frame([0.0; 4], CLEAR_DEPTH).unwrap();
+
+        let creates = |commands: &[Command]| {
+            commands
+                .iter()
+                .filter(|command| matches!(command, Command::CreateVertexBuffer { .. }))
+                .count()
+        };
+
+        debug.flush(&mut device, &Mat4::IDENTITY).unwrap();
+        assert!(device.take_commands().iter().all(|command| matches!(
+            command,
+            Command::CreateConstantBuffer { .. }
+                | Command::CreatePipelineState { .. }
+                | Command::BeginFrame { .. }
+        )));
+
+        debug.line(Vec3::ZERO, Vec3::X, RED);
+        debug.flush(&mut device, &Mat4::IDENTITY).unwrap();
+        let commands = device.take_commands();
+        assert_eq!(creates(&commands), 1);
+        assert_eq!(
+            commands.last(),
+            Some(&Command::Draw {
+                vertex_buffer: debug.buffers.as_ref().unwrap().vertex_buffer,
+                index_buffer: debug.buffers.as_ref().unwrap().index_buffer,
+                num_indices: 2,
+            })
+        );
+        assert_eq!(debug.line_count(), 0);
+
+        debug.grid(Vec3::ZERO, 1.0, 4, GREEN);
+        debug.flush(&mut device, &Mat4::IDENTITY).unwrap();
+        let commands = device.take_commands();
+        assert_eq!(creates(&commands), 0);
+        assert!(commands
+            .iter()
+            .any(|command| matches!(command, Command::UpdateVertexBuffer { .. })));
+
+        debug.grid(Vec3::ZERO, 1.0, 100, GREEN);
+        debug.flush(&mut device, &Mat4::IDENTITY).unwrap();
+        let commands = device.take_commands();
+        assert_eq!(creates(&commands), 1);
+        assert_eq!(debug.buffers.as_ref().unwrap().capacity, 512);
+
+        debug.release(&mut device).unwrap();
+    }
+
+    #[test]
+    fn lines_keep_their_colours() {
+        let mut device = CpuGraphicsDevice::new(16, 16);
+        let mut debug = DebugDraw::new(&mut device).unwrap();
+        debug.line(Vec3::new(-1.0, 0.0, 0.5), Vec3::new(1.0, 0.0, 0.5), RED);
+        debug.line(Vec3::new(0.5, -1.0, 0.5), Vec3::new(0.5, 1.0, 0.5), BLUE);
+
+        device
+            .begin_frame([0.0, 0.0, 0.0, 1.0], CLEAR_DEPTH)
+            .unwrap();
+        debug.flush(&mut device, &Mat4::IDENTITY).unwrap();
+        device.end_frame().unwrap();
+
+        assert_eq!(device.framebuffer().pixel(3, 8), [255, 0, 0, 255]);
+        assert_eq!(device.framebuffer().pixel(12, 3), [0, 0, 255, 255]);
+    }
+}
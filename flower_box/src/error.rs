@@ -0,0 +1,63 @@
+use std::error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    BufferCreation(String),
+    PipelineStateCreation(String),
+    InvalidHandle,
+    InvalidVertexData(String),
+    InvalidIndexRange {
+        requested: u32,
+        available: u32,
+    },
+    InvalidConstantData(String),
+    InvalidInstanceRange {
+        requested: u32,
+        available: u32,
+    },
+    /// A draw or `end_frame` was issued outside `begin_frame`/`end_frame`.
+    NoFrameInProgress,
+    /// `begin_frame` was called before the previous frame ended.
+    FrameInProgress,
+    DeviceLost,
+    PresentFailed(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BufferCreation(reason) => write!(f, "failed to create buffer: {}", reason),
+            Error::PipelineStateCreation(reason) => {
+                write!(f, "failed to create pipeline state: {}", reason)
+            }
+            Error::InvalidHandle => write!(f, "resource handle is not valid on this device"),
+            Error::InvalidVertexData(reason) => write!(f, "invalid vertex data: {}", reason),
+            Error::InvalidIndexRange {
+                requested,
+                available,
+            } => write!(
+                f,
+                "draw requested {} indices but only {} are bound",
+                requested, available
+            ),
+            Error::InvalidInstanceRange {
+                requested,
+                available,
+            } => write!(
+                f,
+                "requested {} instances but the buffer holds {}",
+                requested, available
+            ),
+            Error::InvalidConstantData(reason) => write!(f, "invalid constant data: {}", reason),
+            Error::NoFrameInProgress => write!(f, "no frame has been begun"),
+            Error::FrameInProgress => write!(f, "the previous frame has not ended"),
+            Error::DeviceLost => write!(f, "the graphics device was lost"),
+            Error::PresentFailed(reason) => write!(f, "failed to present frame: {}", reason),
+        }
+    }
+}
+
+impl error::Error for Error {}
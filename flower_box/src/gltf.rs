@@ -0,0 +1,1197 @@
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::math::{Mat4, Quat, Vec3, Vec4};
+use crate::mesh::{Mesh, MeshError};
+use crate::scene::{Node, NodeHandle, Scene, SceneError};
+use crate::vertex::{Vertex, VertexSemantic};
+use crate::{upload_mesh, GpuMesh, GraphicsDevice};
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+const TRIANGLES: u64 = 4;
+// Accessors without a buffer view are zeros that take no space in the file, so
+// their count is limited separately.
+const MAX_UNBACKED_COUNT: usize = 1 << 20;
+
+/// The PBR metallic-roughness base colour of a material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub base_color: [f32; 4],
+}
+
+impl Default for Material {
+    /// The glTF default material is plain white.
+    fn default() -> Material {
+        Material {
+            name: String::new(),
+            base_color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Primitive {
+    pub mesh: Mesh,
+    /// An index into `Asset::materials`.
+    pub material: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfMesh {
+    pub name: String,
+    pub primitives: Vec<Primitive>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfNode {
+    pub name: String,
+    pub local: Mat4,
+    /// `local` combined with the transforms of every ancestor.
+    pub world: Mat4,
+    /// An index into `Asset::meshes`.
+    pub mesh: Option<usize>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+/// The meshes, materials and node hierarchy of a glTF 2.0 file.
+///
+/// glTF is right-handed with counter-clockwise front faces, so like the OBJ
+/// importer z is negated and triangles are rewound clockwise, both in vertex
+/// data and in node transforms. Texture coordinates already start at the top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Asset {
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<Material>,
+    pub nodes: Vec<GltfNode>,
+    /// The root nodes of the default scene, or of every node tree when the file
+    /// has no scenes.
+    pub roots: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GltfError {
+    Io(String),
+    Json(String),
+    InvalidGlb(String),
+    /// A required property is missing or has the wrong type.
+    Malformed(String),
+    /// An index that refers past the end of a top-level array.
+    MissingReference {
+        kind: &'static str,
+        index: usize,
+    },
+    InvalidBuffer {
+        index: usize,
+        reason: String,
+    },
+    InvalidAccessor {
+        index: usize,
+        reason: String,
+    },
+    /// A node with more than one parent, or that is its own ancestor.
+    InvalidHierarchy {
+        node: usize,
+    },
+    Unsupported(String),
+    /// The meshes passed to `Asset::add_to_scene` do not match `Asset::upload`'s
+    /// output for this mesh.
+    UploadMismatch {
+        mesh: usize,
+    },
+    Scene(SceneError),
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Io(reason) => write!(f, "failed to read glTF file: {}", reason),
+            GltfError::Json(reason) => write!(f, "invalid glTF JSON: {}", reason),
+            GltfError::InvalidGlb(reason) => write!(f, "invalid GLB container: {}", reason),
+            GltfError::Malformed(reason) => write!(f, "malformed glTF: {}", reason),
+            GltfError::MissingReference { kind, index } => {
+                write!(f, "{} {} does not exist", kind, index)
+            }
+            GltfError::InvalidBuffer { index, reason } => {
+                write!(f, "buffer {}: {}", index, reason)
+            }
+            GltfError::InvalidAccessor { index, reason } => {
+                write!(f, "accessor {}: {}", index, reason)
+            }
+            GltfError::InvalidHierarchy { node } => {
+                write!(f, "node {} is not part of a tree", node)
+            }
+            GltfError::Unsupported(feature) => write!(f, "unsupported glTF feature: {}", feature),
+            GltfError::UploadMismatch { mesh } => {
+                write!(f, "uploaded primitives do not match mesh {}", mesh)
+            }
+            GltfError::Scene(error) => write!(f, "failed to build scene: {}", error),
+        }
+    }
+}
+
+impl Error for GltfError {}
+
+/// Loads a `.gltf` or `.glb` file. External buffers are read relative to the
+/// file's directory.
+pub fn load(path: impl AsRef<Path>) -> Result<Asset, GltfError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|error| GltfError::Io(error.to_string()))?;
+    parse(&bytes, path.parent())
+}
+
+/// Parses glTF JSON or a GLB container. Buffers with a relative `uri` are
+/// read from `base_dir`; without one, only embedded data can be used.
+pub fn parse(bytes: &[u8], base_dir: Option<&Path>) -> Result<Asset, GltfError> {
+    let (json, glb_bin) = if bytes.starts_with(GLB_MAGIC) {
+        split_glb(bytes)?
+    } else {
+        (bytes, None)
+    };
+    let root: Value =
+        serde_json::from_slice(json).map_err(|error| GltfError::Json(error.to_string()))?;
+
+    let version = root
+        .pointer("/asset/version")
+        .and_then(Value::as_str)
+        .ok_or_else(|| GltfError::Malformed("asset.version is missing".into()))?;
+    if !version.starts_with("2.") {
+        return Err(GltfError::Unsupported(format!("version {}", version)));
+    }
+
+    let buffers = array(&root, "buffers")
+        .iter()
+        .enumerate()
+        .map(|(index, buffer)| load_buffer(index, buffer, glb_bin, base_dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    let document = Document {
+        root: &root,
+        buffers,
+    };
+
+    let materials = array(&root, "materials")
+        .iter()
+        .map(read_material)
+        .collect::<Result<Vec<_>, _>>()?;
+    let meshes = array(&root, "meshes")
+        .iter()
+        .map(|mesh| document.read_mesh(mesh, materials.len()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (nodes, roots) = read_nodes(&root, meshes.len())?;
+
+    Ok(Asset {
+        meshes,
+        materials,
+        nodes,
+        roots,
+    })
+}
+
+impl Asset {
+    /// Uploads every primitive, indexed by mesh and then primitive.
+    pub fn upload(
+        &self,
+        graphics_device: &mut dyn GraphicsDevice,
+    ) -> crate::Result<Vec<Vec<GpuMesh>>> {
+        let mut uploaded = Vec::with_capacity(self.meshes.len());
+        for mesh in &self.meshes {
+            let mut primitives = Vec::with_capacity(mesh.primitives.len());
+            for primitive in &mesh.primitives {
+                primitives.push(upload_mesh(graphics_device, &primitive.mesh)?);
+            }
+            uploaded.push(primitives);
+        }
+        Ok(uploaded)
+    }
+
+    /// Recreates the node hierarchy under `parent`, returning the scene node
+    /// for each of `roots`. Each primitive becomes a child node of its glTF
+    /// node, tinted with its material's base colour.
+    ///
+    /// `gpu_meshes` is the result of `upload`.
+    pub fn add_to_scene(
+        &self,
+        scene: &mut Scene,
+        parent: Option<NodeHandle>,
+        gpu_meshes: &[Vec<GpuMesh>],
+    ) -> Result<Vec<NodeHandle>, GltfError> {
+        for (mesh, gltf_mesh) in self.meshes.iter().enumerate() {
+            let uploaded = gpu_meshes.get(mesh).map(Vec::len);
+            if uploaded != Some(gltf_mesh.primitives.len()) {
+                return Err(GltfError::UploadMismatch { mesh });
+            }
+        }
+
+        let mut roots = Vec::with_capacity(self.roots.len());
+        // Walked with an explicit stack, as node chains can be arbitrarily deep.
+        let mut stack: Vec<(Option<NodeHandle>, usize)> =
+            self.roots.iter().rev().map(|&root| (None, root)).collect();
+        while let Some((node_parent, index)) = stack.pop() {
+            let node = &self.nodes[index];
+            let handle = scene
+                .add(node_parent.or(parent), Node::new().with_local(node.local))
+                .map_err(GltfError::Scene)?;
+            if node_parent.is_none() {
+                roots.push(handle);
+            }
+
+            if let Some(mesh) = node.mesh {
+                let primitives = self.meshes[mesh].primitives.iter();
+                for (primitive, &gpu_mesh) in primitives.zip(&gpu_meshes[mesh]) {
+                    let material = primitive
+                        .material
+                        .map(|material| self.materials[material].clone())
+                        .unwrap_or_default();
+                    let child = Node::new()
+                        .with_mesh(gpu_mesh)
+                        .with_color(material.base_color);
+                    scene.add(Some(handle), child).map_err(GltfError::Scene)?;
+                }
+            }
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|&child| (Some(handle), child)),
+            );
+        }
+        Ok(roots)
+    }
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or(&[])
+}
+
+fn optional_index(value: &Value, key: &str) -> Result<Option<usize>, GltfError> {
+    match value.get(key) {
+        None => Ok(None),
+        Some(index) => index
+            .as_u64()
+            .map(|index| Some(index as usize))
+            .ok_or_else(|| GltfError::Malformed(format!("{} is not an index", key))),
+    }
+}
+
+fn required_index(value: &Value, key: &str) -> Result<usize, GltfError> {
+    optional_index(value, key)?.ok_or_else(|| GltfError::Malformed(format!("{} is missing", key)))
+}
+
+fn reference(kind: &'static str, index: usize, count: usize) -> Result<usize, GltfError> {
+    if index < count {
+        Ok(index)
+    } else {
+        Err(GltfError::MissingReference { kind, index })
+    }
+}
+
+/// Reads an array of exactly `N` numbers, or `default` when `key` is absent.
+fn floats<const N: usize>(
+    value: &Value,
+    key: &str,
+    default: [f32; N],
+) -> Result<[f32; N], GltfError> {
+    let values = match value.get(key) {
+        None => return Ok(default),
+        Some(values) => values.as_array(),
+    };
+    let malformed = || GltfError::Malformed(format!("{} must be {} numbers", key, N));
+    let values = values
+        .filter(|values| values.len() == N)
+        .ok_or_else(malformed)?;
+    let mut out = [0.0; N];
+    for (out, value) in out.iter_mut().zip(values) {
+        *out = value.as_f64().ok_or_else(malformed)? as f32;
+    }
+    Ok(out)
+}
+
+fn name(value: &Value) -> String {
+    value
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn read_material(material: &Value) -> Result<Material, GltfError> {
+    let default = Material::default();
+    let base_color = match material.get("pbrMetallicRoughness") {
+        Some(pbr) => floats(pbr, "baseColorFactor", default.base_color)?,
+        None => default.base_color,
+    };
+    Ok(Material {
+        name: name(material),
+        base_color,
+    })
+}
+
+/// Mirrors positions, normals and transforms in z to convert between glTF's
+/// right-handed space and ours.
+const FLIP_Z: Mat4 = Mat4::from_cols(
+    Vec4::new(1.0, 0.0, 0.0, 0.0),
+    Vec4::new(0.0, 1.0, 0.0, 0.0),
+    Vec4::new(0.0, 0.0, -1.0, 0.0),
+    Vec4::new(0.0, 0.0, 0.0, 1.0),
+);
+
+fn read_nodes(root: &Value, mesh_count: usize) -> Result<(Vec<GltfNode>, Vec<usize>), GltfError> {
+    let values = array(root, "nodes");
+    let mut nodes = Vec::with_capacity(values.len());
+    for node in values {
+        let local = match node.get("matrix") {
+            Some(_) => Mat4::from_cols_array(floats(node, "matrix", [0.0; 16])?),
+            None => {
+                let [x, y, z] = floats(node, "translation", [0.0; 3])?;
+                let [qx, qy, qz, qw] = floats(node, "rotation", [0.0, 0.0, 0.0, 1.0])?;
+                let [sx, sy, sz] = floats(node, "scale", [1.0; 3])?;
+                Mat4::from_scale_rotation_translation(
+                    Vec3::new(sx, sy, sz),
+                    Quat::new(qx, qy, qz, qw),
+                    Vec3::new(x, y, z),
+                )
+            }
+        };
+        let mesh = match optional_index(node, "mesh")? {
+            Some(mesh) => Some(reference("mesh", mesh, mesh_count)?),
+            None => None,
+        };
+        let children = array(node, "children")
+            .iter()
+            .map(|child| {
+                let child = child
+                    .as_u64()
+                    .ok_or_else(|| GltfError::Malformed("children must be indices".into()))?;
+                reference("node", child as usize, values.len())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        nodes.push(GltfNode {
+            name: name(node),
+            local: FLIP_Z * local * FLIP_Z,
+            world: Mat4::IDENTITY,
+            mesh,
+            parent: None,
+            children,
+        });
+    }
+
+    for index in 0..nodes.len() {
+        for child in nodes[index].children.clone() {
+            if nodes[child].parent.is_some() || child == index {
+                return Err(GltfError::InvalidHierarchy { node: child });
+            }
+            nodes[child].parent = Some(index);
+        }
+    }
+
+    let orphans: Vec<usize> = (0..nodes.len())
+        .filter(|&index| nodes[index].parent.is_none())
+        .collect();
+    let scene = match optional_index(root, "scene")? {
+        Some(scene) => Some(reference("scene", scene, array(root, "scenes").len())?),
+        None if array(root, "scenes").is_empty() => None,
+        None => Some(0),
+    };
+    let roots = match scene {
+        Some(scene) => array(&array(root, "scenes")[scene], "nodes")
+            .iter()
+            .map(|node| {
+                let node = node
+                    .as_u64()
+                    .ok_or_else(|| GltfError::Malformed("scene nodes must be indices".into()))?;
+                let node = reference("node", node as usize, nodes.len())?;
+                if nodes[node].parent.is_some() {
+                    return Err(GltfError::InvalidHierarchy { node });
+                }
+                Ok(node)
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => orphans.clone(),
+    };
+
+    // Every node reachable from a parentless node is visited exactly once, so
+    // any left over are part of a cycle.
+    let mut visited = vec![false; nodes.len()];
+    let mut stack: Vec<(usize, Mat4)> =
+        orphans.iter().map(|&node| (node, Mat4::IDENTITY)).collect();
+    while let Some((index, parent_world)) = stack.pop() {
+        visited[index] = true;
+        let world = parent_world * nodes[index].local;
+        nodes[index].world = world;
+        stack.extend(nodes[index].children.iter().map(|&child| (child, world)));
+    }
+    if let Some(node) = visited.iter().position(|&visited| !visited) {
+        return Err(GltfError::InvalidHierarchy { node });
+    }
+
+    Ok((nodes, roots))
+}
+
+struct Document<'a> {
+    root: &'a Value,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Document<'_> {
+    fn read_mesh(&self, mesh: &Value, material_count: usize) -> Result<GltfMesh, GltfError> {
+        let primitives = array(mesh, "primitives")
+            .iter()
+            .map(|primitive| self.read_primitive(primitive, material_count))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(GltfMesh {
+            name: name(mesh),
+            primitives,
+        })
+    }
+
+    fn read_primitive(
+        &self,
+        primitive: &Value,
+        material_count: usize,
+    ) -> Result<Primitive, GltfError> {
+        let mode = primitive
+            .get("mode")
+            .and_then(Value::as_u64)
+            .unwrap_or(TRIANGLES);
+        if mode != TRIANGLES {
+            return Err(GltfError::Unsupported(format!("primitive mode {}", mode)));
+        }
+        let attributes = primitive
+            .get("attributes")
+            .ok_or_else(|| GltfError::Malformed("primitive has no attributes".into()))?;
+
+        let positions = self.read_accessor(required_index(attributes, "POSITION")?, 3)?;
+        let mut vertices: Vec<Vertex> = positions
+            .iter()
+            .map(|p| Vertex::new(p[0], p[1], -p[2]))
+            .collect();
+        let mut semantics = vec![VertexSemantic::Position];
+
+        if let Some(accessor) = optional_index(attributes, "NORMAL")? {
+            let normals = self.read_accessor(accessor, 3)?;
+            self.check_count(accessor, normals.len(), vertices.len())?;
+            for (vertex, n) in vertices.iter_mut().zip(&normals) {
+                vertex.normal = [n[0], n[1], -n[2]];
+            }
+            semantics.push(VertexSemantic::Normal);
+        }
+        if let Some(accessor) = optional_index(attributes, "TEXCOORD_0")? {
+            let uvs = self.read_accessor(accessor, 2)?;
+            self.check_count(accessor, uvs.len(), vertices.len())?;
+            for (vertex, uv) in vertices.iter_mut().zip(&uvs) {
+                vertex.uv = [uv[0], uv[1]];
+            }
+            semantics.push(VertexSemantic::TexCoord);
+        }
+        if let Some(accessor) = optional_index(attributes, "COLOR_0")? {
+            // RGB colours are read with an alpha of one.
+            let colors = self.read_accessor(accessor, 3)?;
+            self.check_count(accessor, colors.len(), vertices.len())?;
+            for (vertex, color) in vertices.iter_mut().zip(&colors) {
+                vertex.color = [color[0], color[1], color[2], color[3]];
+            }
+            semantics.push(VertexSemantic::Color);
+        }
+
+        let indices: Vec<u32> = match optional_index(primitive, "indices")? {
+            Some(accessor) => self.read_indices(accessor)?,
+            None => (0..vertices.len() as u32).collect(),
+        };
+        if !indices.len().is_multiple_of(3) {
+            let error = MeshError::IncompleteTriangle {
+                index_count: indices.len(),
+            };
+            return Err(GltfError::Malformed(error.to_string()));
+        }
+        let mut rewound = Vec::with_capacity(indices.len());
+        for triangle in indices.chunks_exact(3) {
+            rewound.extend_from_slice(&[triangle[0], triangle[2], triangle[1]]);
+        }
+        let mesh = Mesh::new(vertices, rewound)
+            .and_then(|mesh| mesh.with_attributes(&semantics))
+            .map_err(|error| GltfError::Malformed(error.to_string()))?;
+
+        let material = match optional_index(primitive, "material")? {
+            Some(material) => Some(reference("material", material, material_count)?),
+            None => None,
+        };
+        Ok(Primitive { mesh, material })
+    }
+
+    fn check_count(&self, accessor: usize, count: usize, expected: usize) -> Result<(), GltfError> {
+        if count != expected {
+            return Err(GltfError::InvalidAccessor {
+                index: accessor,
+                reason: format!("has {} elements but POSITION has {}", count, expected),
+            });
+        }
+        Ok(())
+    }
+
+    /// Reads every element of an accessor as floats, normalizing integers when
+    /// the accessor says to. Elements need at least `components` components;
+    /// any after those are filled from `(0, 0, 0, 1)`.
+    fn read_accessor(&self, index: usize, components: usize) -> Result<Vec<[f32; 4]>, GltfError> {
+        let view = self.accessor(index)?;
+        if view.components < components {
+            return Err(GltfError::InvalidAccessor {
+                index,
+                reason: format!(
+                    "has {} components but at least {} are needed",
+                    view.components, components
+                ),
+            });
+        }
+
+        let mut elements = Vec::with_capacity(view.count);
+        for i in 0..view.count {
+            let mut element = [0.0, 0.0, 0.0, 1.0];
+            for (c, component) in element.iter_mut().take(view.components).enumerate() {
+                *component = match view.component(i, c) {
+                    Some(bytes) => read_component(bytes, view.signed, view.float, view.normalized),
+                    // Accessors without a buffer view are all zeros.
+                    None => 0.0,
+                };
+            }
+            elements.push(element);
+        }
+        Ok(elements)
+    }
+
+    /// Reads an index accessor, which must hold unsigned integer scalars.
+    fn read_indices(&self, index: usize) -> Result<Vec<u32>, GltfError> {
+        let view = self.accessor(index)?;
+        if view.float || view.signed || view.normalized || view.components != 1 {
+            return Err(GltfError::InvalidAccessor {
+                index,
+                reason: "indices must be unsigned integer scalars".into(),
+            });
+        }
+
+        Ok((0..view.count)
+            .map(|i| {
+                let mut word = [0; 4];
+                if let Some(bytes) = view.component(i, 0) {
+                    word[..bytes.len()].copy_from_slice(bytes);
+                }
+                u32::from_le_bytes(word)
+            })
+            .collect())
+    }
+
+    /// Looks up an accessor and checks that all of its elements lie within its
+    /// buffer view, before anything is sized from its count.
+    fn accessor(&self, index: usize) -> Result<AccessorView<'_>, GltfError> {
+        let accessors = array(self.root, "accessors");
+        let accessor = &accessors[reference("accessor", index, accessors.len())?];
+        let invalid = |reason: String| GltfError::InvalidAccessor { index, reason };
+
+        if accessor.get("sparse").is_some() {
+            return Err(GltfError::Unsupported("sparse accessors".into()));
+        }
+        let count = accessor
+            .get("count")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| invalid("count is missing".into()))? as usize;
+        let component_type = accessor
+            .get("componentType")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| invalid("componentType is missing".into()))?;
+        let (component_size, signed, float) = match component_type {
+            5120 => (1, true, false),
+            5121 => (1, false, false),
+            5122 => (2, true, false),
+            5123 => (2, false, false),
+            5125 => (4, false, false),
+            5126 => (4, false, true),
+            other => return Err(invalid(format!("unknown componentType {}", other))),
+        };
+        let components = match accessor.get("type").and_then(Value::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some(other) => return Err(invalid(format!("unsupported type {}", other))),
+            None => return Err(invalid("type is missing".into())),
+        };
+        let normalized = accessor
+            .get("normalized")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let mut view = AccessorView {
+            data: None,
+            count,
+            stride: 0,
+            offset: 0,
+            component_size,
+            components,
+            signed,
+            float,
+            normalized,
+        };
+        let buffer_view = match optional_index(accessor, "bufferView")? {
+            Some(buffer_view) => buffer_view,
+            None if count > MAX_UNBACKED_COUNT => {
+                return Err(invalid(format!(
+                    "has {} elements without a buffer view",
+                    count
+                )))
+            }
+            None => return Ok(view),
+        };
+        let (data, stride) = self.buffer_view(buffer_view)?;
+        let element_size = component_size * components;
+        // A stride narrower than an element would let elements overlap, and a
+        // zero stride would let any count pass the bounds check below.
+        if let Some(stride) = stride {
+            if !(4..=252).contains(&stride) || !stride.is_multiple_of(4) || stride < element_size {
+                return Err(invalid(format!("byteStride {} is invalid", stride)));
+            }
+        }
+        view.stride = stride.unwrap_or(element_size);
+        view.offset = optional_index(accessor, "byteOffset")?.unwrap_or(0);
+        let end = match count.checked_sub(1) {
+            Some(last) => last
+                .checked_mul(view.stride)
+                .and_then(|start| start.checked_add(view.offset))
+                .and_then(|start| start.checked_add(element_size)),
+            None => Some(0),
+        };
+        if !matches!(end, Some(end) if end <= data.len()) {
+            return Err(invalid("extends past the end of its buffer view".into()));
+        }
+        view.data = Some(data);
+        Ok(view)
+    }
+
+    fn buffer_view(&self, index: usize) -> Result<(&[u8], Option<usize>), GltfError> {
+        let views = array(self.root, "bufferViews");
+        let view = &views[reference("buffer view", index, views.len())?];
+        let buffer = index_in(view, "buffer", "buffer", self.buffers.len())?;
+        let offset = optional_index(view, "byteOffset")?.unwrap_or(0);
+        let length = required_index(view, "byteLength")?;
+        let stride = optional_index(view, "byteStride")?;
+        let data = offset
+            .checked_add(length)
+            .and_then(|end| self.buffers[buffer].get(offset..end))
+            .ok_or_else(|| GltfError::InvalidBuffer {
+                index: buffer,
+                reason: format!("buffer view {} extends past its end", index),
+            })?;
+        Ok((data, stride))
+    }
+}
+
+/// An accessor whose elements are known to fit in `data`.
+struct AccessorView<'a> {
+    /// `None` for accessors without a buffer view.
+    data: Option<&'a [u8]>,
+    count: usize,
+    stride: usize,
+    offset: usize,
+    component_size: usize,
+    components: usize,
+    signed: bool,
+    float: bool,
+    normalized: bool,
+}
+
+impl<'a> AccessorView<'a> {
+    fn component(&self, element: usize, component: usize) -> Option<&'a [u8]> {
+        let start = self.offset + element * self.stride + component * self.component_size;
+        self.data
+            .map(|data| &data[start..start + self.component_size])
+    }
+}
+
+fn index_in(
+    value: &Value,
+    key: &str,
+    kind: &'static str,
+    count: usize,
+) -> Result<usize, GltfError> {
+    reference(kind, required_index(value, key)?, count)
+}
+
+fn read_component(bytes: &[u8], signed: bool, float: bool, normalized: bool) -> f32 {
+    let mut word = [0; 4];
+    word[..bytes.len()].copy_from_slice(bytes);
+    if float {
+        return f32::from_le_bytes(word);
+    }
+    let (value, max) = match (bytes.len(), signed) {
+        (1, true) => (word[0] as i8 as f32, i8::MAX as f32),
+        (1, false) => (word[0] as f32, u8::MAX as f32),
+        (2, true) => (
+            i16::from_le_bytes([word[0], word[1]]) as f32,
+            i16::MAX as f32,
+        ),
+        (2, false) => (
+            u16::from_le_bytes([word[0], word[1]]) as f32,
+            u16::MAX as f32,
+        ),
+        _ => (u32::from_le_bytes(word) as f32, u32::MAX as f32),
+    };
+    if normalized {
+        (value / max).max(-1.0)
+    } else {
+        value
+    }
+}
+
+fn load_buffer(
+    index: usize,
+    buffer: &Value,
+    glb_bin: Option<&[u8]>,
+    base_dir: Option<&Path>,
+) -> Result<Vec<u8>, GltfError> {
+    let invalid = |reason: String| GltfError::InvalidBuffer { index, reason };
+    let length = buffer
+        .get("byteLength")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| invalid("byteLength is missing".into()))? as usize;
+
+    let mut data = match buffer.get("uri").and_then(Value::as_str) {
+        Some(uri) if uri.starts_with("data:") => {
+            let encoded = uri
+                .find(";base64,")
+                .map(|start| &uri[start + ";base64,".len()..])
+                .ok_or_else(|| invalid("data URI is not base64".into()))?;
+            decode_base64(encoded).ok_or_else(|| invalid("invalid base64 data".into()))?
+        }
+        Some(uri) => {
+            let base_dir = base_dir.ok_or_else(|| invalid(format!("cannot resolve {}", uri)))?;
+            let path = base_dir.join(percent_decode(uri));
+            std::fs::read(&path)
+                .map_err(|error| invalid(format!("{}: {}", path.display(), error)))?
+        }
+        // Only the first buffer of a GLB file may omit its URI.
+        None if index == 0 => glb_bin
+            .ok_or_else(|| invalid("no uri and no GLB binary chunk".into()))?
+            .to_vec(),
+        None => return Err(invalid("no uri".into())),
+    };
+    if data.len() < length {
+        return Err(invalid(format!(
+            "has {} bytes but byteLength is {}",
+            data.len(),
+            length
+        )));
+    }
+    data.truncate(length);
+    Ok(data)
+}
+
+/// Decodes `%20` style escapes in relative URIs.
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(encoded.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for byte in encoded.bytes().filter(|&byte| byte != b'=') {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        bits = bits << 6 | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            out.push((bits >> bit_count) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// Splits a GLB file into its JSON chunk and optional binary chunk.
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), GltfError> {
+    let invalid = |reason: &str| GltfError::InvalidGlb(reason.to_string());
+    let word = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .ok_or_else(|| invalid("file is truncated"))
+    };
+
+    if word(4)? != 2 {
+        return Err(GltfError::Unsupported(format!("GLB version {}", word(4)?)));
+    }
+    let length = word(8)? as usize;
+    if length > bytes.len() {
+        return Err(invalid("file is truncated"));
+    }
+
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while offset < length {
+        let chunk_length = word(offset)? as usize;
+        let chunk_type = word(offset + 4)?;
+        let chunk = bytes
+            .get(offset + 8..offset + 8 + chunk_length)
+            .filter(|_| offset + 8 + chunk_length <= length)
+            .ok_or_else(|| invalid("chunk extends past the end of the file"))?;
+        match chunk_type {
+            GLB_JSON_CHUNK if json.is_none() => json = Some(chunk),
+            GLB_BIN_CHUNK if bin.is_none() => bin = Some(chunk),
+            // Unknown chunks must be ignored.
+            _ => {}
+        }
+        offset += 8 + chunk_length;
+    }
+    Ok((json.ok_or_else(|| invalid("no JSON chunk"))?, bin))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::CpuGraphicsDevice;
+    use crate::render_frame;
+    use crate::scene::SceneRenderer;
+    use serde_json::json;
+
+    // A triangle with normals and 16-bit indices, followed by a red material.
+    fn triangle_buffer() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let positions = [[-0.5, -0.5, 0.0], [0.5, -0.5, 0.0], [0.0, 0.5, 0.0f32]];
+        for value in positions.iter().flatten() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for _ in 0..3 {
+            for value in &[0.0f32, 0.0, 1.0] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        for index in &[0u16, 1, 2, 0] {
+            bytes.extend_from_slice(&index.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn document(buffer: Value) -> Value {
+        json!({
+            "asset": { "version": "2.0" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [
+                { "name": "pot", "translation": [0.5, 0.0, 0.0], "children": [1] },
+                { "name": "flower", "translation": [0.0, 0.0, -0.5], "mesh": 0 }
+            ],
+            "meshes": [{
+                "name": "petal",
+                "primitives": [{
+                    "attributes": { "POSITION": 0, "NORMAL": 1 },
+                    "indices": 2,
+                    "material": 0
+                }]
+            }],
+            "materials": [{
+                "name": "red",
+                "pbrMetallicRoughness": { "baseColorFactor": [1.0, 0.0, 0.0, 1.0] }
+            }],
+            "buffers": [buffer],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 72 },
+                { "buffer": 0, "byteOffset": 72, "byteLength": 6 }
+            ],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" },
+                {
+                    "bufferView": 0,
+                    "byteOffset": 36,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3"
+                },
+                { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }
+            ]
+        })
+    }
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let word = chunk.iter().enumerate().fold(0u32, |word, (i, &byte)| {
+                word | (byte as u32) << (16 - 8 * i)
+            });
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(ALPHABET[(word >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    fn embedded() -> Vec<u8> {
+        let buffer = triangle_buffer();
+        let uri = format!(
+            "data:application/octet-stream;base64,{}",
+            encode_base64(&buffer)
+        );
+        document(json!({ "byteLength": buffer.len(), "uri": uri }))
+            .to_string()
+            .into_bytes()
+    }
+
+    fn glb() -> Vec<u8> {
+        let buffer = triangle_buffer();
+        let mut json = document(json!({ "byteLength": buffer.len() })).to_string();
+        while !json.len().is_multiple_of(4) {
+            json.push(' ');
+        }
+        let mut bin = buffer;
+        bin.resize(bin.len().div_ceil(4) * 4, 0);
+
+        let mut bytes = GLB_MAGIC.to_vec();
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        for word in &[2, length as u32, json.len() as u32, GLB_JSON_CHUNK] {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes.extend_from_slice(json.as_bytes());
+        for word in &[bin.len() as u32, GLB_BIN_CHUNK] {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes.extend_from_slice(&bin);
+        bytes
+    }
+
+    #[test]
+    fn reads_meshes_materials_and_hierarchy() {
+        let asset = parse(&embedded(), None).unwrap();
+
+        assert_eq!(asset.roots, vec![0]);
+        assert_eq!(asset.nodes[0].children, vec![1]);
+        assert_eq!(asset.nodes[1].parent, Some(0));
+        assert_eq!(asset.nodes[1].mesh, Some(0));
+        assert_eq!(
+            asset.nodes[1].world.transform_point(Vec3::ZERO),
+            Vec3::new(0.5, 0.0, 0.5)
+        );
+        assert_eq!(asset.materials[0].name, "red");
+        assert_eq!(asset.materials[0].base_color, [1.0, 0.0, 0.0, 1.0]);
+
+        let primitive = &asset.meshes[0].primitives[0];
+        assert_eq!(asset.meshes[0].name, "petal");
+        assert_eq!(primitive.material, Some(0));
+        assert_eq!(primitive.mesh.indices(), &[0, 2, 1]);
+        assert!(primitive.mesh.has_attribute(VertexSemantic::Normal));
+        assert_eq!(primitive.mesh.vertices()[2].position, [0.0, 0.5, -0.0]);
+        assert_eq!(primitive.mesh.vertices()[0].normal, [0.0, 0.0, -1.0]);
+    }
+
+    #[test]
+    fn glb_and_external_buffers_match_embedded() {
+        let expected = parse(&embedded(), None).unwrap();
+        assert_eq!(parse(&glb(), None).unwrap(), expected);
+
+        let dir = std::env::temp_dir().join(format!("flower_box_gltf_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let buffer = triangle_buffer();
+        std::fs::write(dir.join("flower data.bin"), &buffer).unwrap();
+        let json = document(json!({ "byteLength": buffer.len(), "uri": "flower%20data.bin" }));
+        std::fs::write(dir.join("flower.gltf"), json.to_string()).unwrap();
+        std::fs::write(dir.join("flower.glb"), glb()).unwrap();
+
+        assert_eq!(load(dir.join("flower.gltf")).unwrap(), expected);
+        assert_eq!(load(dir.join("flower.glb")).unwrap(), expected);
+        assert!(matches!(
+            parse(json.to_string().as_bytes(), None),
+            Err(GltfError::InvalidBuffer { index: 0, .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn deep_hierarchies_do_not_recurse() {
+        const DEPTH: usize = 100_000;
+        let nodes = (0..DEPTH)
+            .map(|i| GltfNode {
+                name: String::new(),
+                local: Mat4::IDENTITY,
+                world: Mat4::IDENTITY,
+                mesh: None,
+                parent: i.checked_sub(1),
+                children: if i + 1 < DEPTH { vec![i + 1] } else { vec![] },
+            })
+            .collect();
+        let asset = Asset {
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            nodes,
+            roots: vec![0],
+        };
+
+        let mut scene = Scene::new();
+        let roots = asset.add_to_scene(&mut scene, None, &[]).unwrap();
+        assert_eq!(scene.len(), DEPTH);
+        assert_eq!(scene.roots(), roots.as_slice());
+    }
+
+    #[test]
+    fn draws_through_scene() {
+        let asset = parse(&embedded(), None).unwrap();
+        let mut device = CpuGraphicsDevice::new(16, 16);
+        let gpu_meshes = asset.upload(&mut device).unwrap();
+        let mut scene = Scene::new();
+        assert_eq!(
+            asset.add_to_scene(&mut scene, None, &gpu_meshes[..0]),
+            Err(GltfError::UploadMismatch { mesh: 0 })
+        );
+        assert!(scene.is_empty());
+        let roots = asset.add_to_scene(&mut scene, None, &gpu_meshes).unwrap();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(scene.len(), 3);
+
+        let mut renderer = SceneRenderer::new(&mut device).unwrap();
+        render_frame(&mut device, [0.0, 0.0, 0.0, 1.0], |device| {
+            renderer.draw(device, &scene, &Mat4::IDENTITY)
+        })
+        .unwrap();
+
+        // The triangle is moved half a unit right by its parent.
+        assert_eq!(device.framebuffer().pixel(12, 9), [255, 0, 0, 255]);
+        assert_eq!(device.framebuffer().pixel(3, 9), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn large_indices_are_exact() {
+        let root = json!({
+            "bufferViews": [{ "buffer": 0, "byteLength": 8 }],
+            "accessors": [{ "bufferView": 0, "componentType": 5125, "count": 2, "type": "SCALAR" }]
+        });
+        let indices = [16_777_217u32, u32::MAX];
+        let document = Document {
+            root: &root,
+            buffers: vec![indices.iter().flat_map(|i| i.to_le_bytes()).collect()],
+        };
+        assert_eq!(document.read_indices(0).unwrap(), indices);
+    }
+
+    #[test]
+    fn malformed_files_are_reported() {
+        let with = |edit: &dyn Fn(&mut Value)| {
+            let mut document: Value = serde_json::from_slice(&embedded()).unwrap();
+            edit(&mut document);
+            parse(document.to_string().as_bytes(), None)
+        };
+
+        assert_eq!(
+            with(&|document| document["asset"]["version"] = json!("1.0")),
+            Err(GltfError::Unsupported("version 1.0".into()))
+        );
+        assert_eq!(
+            with(&|document| document["nodes"][1]["mesh"] = json!(3)),
+            Err(GltfError::MissingReference {
+                kind: "mesh",
+                index: 3
+            })
+        );
+        assert_eq!(
+            with(&|document| document["nodes"][1]["children"] = json!([0])),
+            Err(GltfError::InvalidHierarchy { node: 0 })
+        );
+        assert!(matches!(
+            with(&|document| document["accessors"][0]["count"] = json!(30)),
+            Err(GltfError::InvalidAccessor { index: 0, .. })
+        ));
+        assert!(matches!(
+            with(&|document| {
+                document["accessors"][0]["count"] = json!(2_000_000_000_000_000_000u64);
+                document["accessors"][0]
+                    .as_object_mut()
+                    .unwrap()
+                    .remove("bufferView");
+            }),
+            Err(GltfError::InvalidAccessor { index: 0, .. })
+        ));
+        assert!(matches!(
+            with(&|document| document["accessors"][0]["byteOffset"] = json!(u64::MAX)),
+            Err(GltfError::InvalidAccessor { index: 0, .. })
+        ));
+        assert!(matches!(
+            with(&|document| document["bufferViews"][0]["byteStride"] = json!(u64::MAX / 2)),
+            Err(GltfError::InvalidAccessor { index: 0, .. })
+        ));
+        assert!(matches!(
+            with(&|document| {
+                document["bufferViews"][0]["byteStride"] = json!(0);
+                document["accessors"][0]["count"] = json!(1u64 << 40);
+            }),
+            Err(GltfError::InvalidAccessor { index: 0, .. })
+        ));
+        assert!(matches!(
+            with(&|document| document["bufferViews"][0]["byteStride"] = json!(8)),
+            Err(GltfError::InvalidAccessor { index: 0, .. })
+        ));
+        assert!(matches!(
+            with(&|document| document["bufferViews"][0]["byteOffset"] = json!(u64::MAX)),
+            Err(GltfError::InvalidBuffer { index: 0, .. })
+        ));
+        assert!(matches!(
+            with(&|document| document["accessors"][2]["componentType"] = json!(5126)),
+            Err(GltfError::InvalidAccessor { index: 2, .. })
+        ));
+        assert!(matches!(
+            with(&|document| document["accessors"][2]["normalized"] = json!(true)),
+            Err(GltfError::InvalidAccessor { index: 2, .. })
+        ));
+        assert!(matches!(
+            with(&|document| document["accessors"][2]["count"] = json!(2)),
+            Err(GltfError::Malformed(_))
+        ));
+        assert!(matches!(
+            with(&|document| document["buffers"][0]["uri"] = json!("data:;base64,*")),
+            Err(GltfError::InvalidBuffer { index: 0, .. })
+        ));
+        assert!(matches!(
+            parse(b"{ not json", None),
+            Err(GltfError::Json(_))
+        ));
+        assert!(matches!(
+            parse(&glb()[..40], None),
+            Err(GltfError::InvalidGlb(_))
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::math::Mat4;
+use crate::{Error, Result};
+
+/// Per-instance data for `GraphicsDevice::draw_instanced`.
+///
+/// Each instance is drawn with `DrawConstants::transform * transform` and its
+/// colour multiplied by `tint`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Instance {
+    pub transform: Mat4,
+    pub tint: [f32; 4],
+}
+
+impl Default for Instance {
+    fn default() -> Instance {
+        Instance {
+            transform: Mat4::IDENTITY,
+            tint: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+}
+
+impl Instance {
+    pub const SIZE: u32 = std::mem::size_of::<Instance>() as u32;
+
+    pub fn new(transform: Mat4, tint: [f32; 4]) -> Instance {
+        Instance { transform, tint }
+    }
+}
+
+/// Instance buffers cannot be empty, as Direct3D has no zero-sized buffers.
+pub fn validate(instances: &[Instance]) -> Result<()> {
+    if instances.is_empty() {
+        return Err(Error::BufferCreation("instance buffer is empty".into()));
+    }
+    Ok(())
+}
+
+/// Checks that an update or draw touching `requested` instances stays within a
+/// buffer holding `available`.
+pub fn validate_range(available: u32, requested: u32) -> Result<()> {
+    if requested > available {
+        return Err(Error::InvalidInstanceRange {
+            requested,
+            available,
+        });
+    }
+    Ok(())
+}
@@ -1,30 +1,256 @@
-use std::ops::Deref;
+use instance::Instance;
+use mesh::Mesh;
+use pipeline::PipelineState;
+use vertex::VertexLayout;
 
-use cube::CUBE_VERTS;
-use cube::{Vertex, CUBE_INDICES};
+pub use error::{Error, Result};
+pub use resource::{
+    ConstantBufferHandle, IndexBufferHandle, InstanceBufferHandle, PipelineStateHandle,
+    VertexBufferHandle,
+};
 
+/// A rendering backend.
+///
+/// Each frame is bracketed by `begin_frame`, which clears the render target and
+/// depth buffer, and `end_frame`, which presents it. Draws are only valid in
+/// between; resources can be created, updated and bound at any time.
+///
+/// Draws use the most recently bound pipeline state, or
+/// `PipelineState::default()` until one is bound. Binding takes effect
+/// immediately and survives destroying the state's handle.
 pub trait GraphicsDevice {
-    fn set_vertex_buffer(&self, vertices: &[Vertex]);
-    fn set_index_buffer(&self, vertices: &[u32]);
-    fn draw(&self, num_vertices: u32);
+    fn create_vertex_buffer(
+        &mut self,
+        data: &[u8],
+        layout: &VertexLayout,
+    ) -> Result<VertexBufferHandle>;
+    fn create_index_buffer(&mut self, indices: &[u32]) -> Result<IndexBufferHandle>;
+    /// Overwrites the first vertices of the buffer, which keeps its original
+    /// size and layout; see `VertexLayout::validate_update`.
+    fn update_vertex_buffer(&mut self, handle: VertexBufferHandle, data: &[u8]) -> Result<()>;
+    fn destroy_vertex_buffer(&mut self, handle: VertexBufferHandle) -> Result<()>;
+    fn destroy_index_buffer(&mut self, handle: IndexBufferHandle) -> Result<()>;
+    /// `data` must be a whole number of 16-byte registers; see `constant::validate`.
+    fn create_constant_buffer(&mut self, data: &[u8]) -> Result<ConstantBufferHandle>;
+    /// Replaces the whole contents of the buffer, which keeps its original size.
+    fn update_constant_buffer(&mut self, handle: ConstantBufferHandle, data: &[u8]) -> Result<()>;
+    fn destroy_constant_buffer(&mut self, handle: ConstantBufferHandle) -> Result<()>;
+    /// Makes the buffer visible to shaders at register `slot` for later draws.
+    fn bind_constant_buffer(&mut self, slot: u32, handle: ConstantBufferHandle) -> Result<()>;
+    fn create_instance_buffer(&mut self, instances: &[Instance]) -> Result<InstanceBufferHandle>;
+    /// Overwrites the first `instances.len()` instances of the buffer.
+    fn update_instance_buffer(
+        &mut self,
+        handle: InstanceBufferHandle,
+        instances: &[Instance],
+    ) -> Result<()>;
+    fn destroy_instance_buffer(&mut self, handle: InstanceBufferHandle) -> Result<()>;
+    fn create_pipeline_state(&mut self, state: &PipelineState) -> Result<PipelineStateHandle>;
+    fn destroy_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()>;
+    fn bind_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()>;
+    fn begin_frame(&mut self, clear_color: [f32; 4], clear_depth: f32) -> Result<()>;
+    fn end_frame(&mut self) -> Result<()>;
+    fn draw(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+    ) -> Result<()>;
+    /// Draws the indexed geometry once for each of the first `num_instances`
+    /// instances in `instance_buffer`.
+    fn draw_instanced(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+        instance_buffer: InstanceBufferHandle,
+        num_instances: u32,
+    ) -> Result<()>;
 }
 
+pub mod bake;
+pub mod camera;
+pub mod capture;
+pub mod constant;
+pub mod cpu;
 pub mod cube;
+pub mod debug_draw;
+pub mod error;
+pub mod gltf;
+pub mod instance;
+pub mod math;
+pub mod mesh;
+pub mod normals;
+pub mod obj;
+pub mod optimize;
+pub mod pipeline;
+pub mod ply;
+mod reader;
+pub mod recording;
+pub mod resource;
+pub mod scene;
+pub mod uv;
+pub mod vertex;
 
-pub fn upload_mesh(graphics_device: &Box<dyn GraphicsDevice>) {
-    let graphics_device = graphics_device.deref();
-    graphics_device.set_vertex_buffer(&CUBE_VERTS);
-    graphics_device.set_index_buffer(&CUBE_INDICES);
+/// A mesh whose vertices and indices live on a `GraphicsDevice`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GpuMesh {
+    pub vertex_buffer: VertexBufferHandle,
+    pub index_buffer: IndexBufferHandle,
+    pub index_count: u32,
 }
 
-pub fn draw(graphics_device: &Box<dyn GraphicsDevice>) {
-    graphics_device.deref().draw(CUBE_VERTS.len() as u32);
+pub fn upload_mesh(graphics_device: &mut dyn GraphicsDevice, mesh: &Mesh) -> Result<GpuMesh> {
+    upload(graphics_device, mesh, mesh.indices())
+}
+
+/// Uploads the unique edges of `mesh` as a line list, to be drawn with a
+/// `Topology::LineList` pipeline state.
+pub fn upload_wireframe(graphics_device: &mut dyn GraphicsDevice, mesh: &Mesh) -> Result<GpuMesh> {
+    upload(graphics_device, mesh, &mesh.edge_indices())
+}
+
+fn upload(
+    graphics_device: &mut dyn GraphicsDevice,
+    mesh: &Mesh,
+    indices: &[u32],
+) -> Result<GpuMesh> {
+    let vertex_buffer = graphics_device.create_vertex_buffer(&mesh.vertex_data(), mesh.layout())?;
+    let index_buffer = match graphics_device.create_index_buffer(indices) {
+        Ok(index_buffer) => index_buffer,
+        Err(error) => {
+            graphics_device.destroy_vertex_buffer(vertex_buffer)?;
+            return Err(error);
+        }
+    };
+
+    Ok(GpuMesh {
+        vertex_buffer,
+        index_buffer,
+        index_count: indices.len() as u32,
+    })
+}
+
+pub fn release_mesh(graphics_device: &mut dyn GraphicsDevice, mesh: GpuMesh) -> Result<()> {
+    graphics_device.destroy_vertex_buffer(mesh.vertex_buffer)?;
+    graphics_device.destroy_index_buffer(mesh.index_buffer)
+}
+
+pub fn draw(graphics_device: &mut dyn GraphicsDevice, mesh: &GpuMesh) -> Result<()> {
+    graphics_device.draw(mesh.vertex_buffer, mesh.index_buffer, mesh.index_count)
+}
+
+/// Depth buffers are cleared to the far plane.
+pub const CLEAR_DEPTH: f32 = 1.0;
+
+/// Renders one frame: clears to `clear_color`, runs `draw` and presents.
+///
+/// The frame is ended even when `draw` fails, so the device is ready for the
+/// next one; the first error is returned.
+pub fn render_frame(
+    graphics_device: &mut dyn GraphicsDevice,
+    clear_color: [f32; 4],
+    draw: impl FnOnce(&mut dyn GraphicsDevice) -> Result<()>,
+) -> Result<()> {
+    graphics_device.begin_frame(clear_color, CLEAR_DEPTH)?;
+    let result = draw(graphics_device);
+    let presented = graphics_device.end_frame();
+    result.and(presented)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::cube;
+    use crate::recording::{Command, RecordingGraphicsDevice};
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn upload_then_draw_issues_expected_commands() {
+        let mut device = RecordingGraphicsDevice::new();
+        let mesh = cube::unit();
+
+        let gpu_mesh = upload_mesh(&mut device, &mesh).unwrap();
+        render_frame(&mut device, [0.0, 0.0, 0.0, 1.0], |device| {
+            draw(device, &gpu_mesh)
+        })
+        .unwrap();
+
+        assert_eq!(
+            device.commands(),
+            &[
+                Command::CreateVertexBuffer {
+                    handle: gpu_mesh.vertex_buffer,
+                    data: mesh.vertex_data(),
+                    layout: mesh.layout().clone(),
+                },
+                Command::CreateIndexBuffer {
+                    handle: gpu_mesh.index_buffer,
+                    indices: mesh.indices().to_vec(),
+                },
+                Command::BeginFrame {
+                    clear_color: [0.0, 0.0, 0.0, 1.0],
+                    clear_depth: CLEAR_DEPTH,
+                },
+                Command::Draw {
+                    vertex_buffer: gpu_mesh.vertex_buffer,
+                    index_buffer: gpu_mesh.index_buffer,
+                    num_indices: 36,
+                },
+                Command::EndFrame,
+            ]
+        );
+    }
+
+    #[test]
+    fn frames_bracket_draws() {
+        let mut device = RecordingGraphicsDevice::new();
+        let gpu_mesh = upload_mesh(&mut device, &cube::generate(1)).unwrap();
+
+        assert_eq!(draw(&mut device, &gpu_mesh), Err(Error::NoFrameInProgress));
+        assert_eq!(device.end_frame(), Err(Error::NoFrameInProgress));
+
+        device.begin_frame([0.0; 4], CLEAR_DEPTH).unwrap();
+        assert_eq!(
+            device.begin_frame([0.0; 4], CLEAR_DEPTH),
+            Err(Error::FrameInProgress)
+        );
+        device.end_frame().unwrap();
+
+        // A failed draw still ends the frame.
+        let stale = GpuMesh {
+            index_count: 1000,
+            ..gpu_mesh
+        };
+        assert!(render_frame(&mut device, [0.0; 4], |device| draw(device, &stale)).is_err());
+        assert_eq!(device.commands().last(), Some(&Command::EndFrame));
+        assert!(render_frame(&mut device, [0.0; 4], |device| draw(device, &gpu_mesh)).is_ok());
+    }
+
+    #[test]
+    fn release_destroys_both_buffers() {
+        let mut device = RecordingGraphicsDevice::new();
+        let gpu_mesh = upload_mesh(&mut device, &cube::generate(2)).unwrap();
+        device.take_commands();
+
+        release_mesh(&mut device, gpu_mesh).unwrap();
+
+        assert_eq!(
+            device.commands(),
+            &[
+                Command::DestroyVertexBuffer {
+                    handle: gpu_mesh.vertex_buffer
+                },
+                Command::DestroyIndexBuffer {
+                    handle: gpu_mesh.index_buffer
+                },
+            ]
+        );
+        device.begin_frame([0.0; 4], CLEAR_DEPTH).unwrap();
+        assert_eq!(draw(&mut device, &gpu_mesh), Err(Error::InvalidHandle));
+    }
 }
@@ -0,0 +1,727 @@
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+macro_rules! impl_vector {
+    ($name:ident { $($field:ident),+ }, $array:ty) => {
+        impl $name {
+            pub const fn new($($field: f32),+) -> $name {
+                $name { $($field),+ }
+            }
+
+            pub fn splat(value: f32) -> $name {
+                $name { $($field: value),+ }
+            }
+
+            pub fn dot(self, other: $name) -> f32 {
+                0.0 $(+ self.$field * other.$field)+
+            }
+
+            pub fn length_squared(self) -> f32 {
+                self.dot(self)
+            }
+
+            pub fn length(self) -> f32 {
+                self.length_squared().sqrt()
+            }
+
+            /// Scales the vector to unit length, leaving a zero vector unchanged.
+            pub fn normalize(self) -> $name {
+                let length = self.length();
+                if length > 0.0 {
+                    self / length
+                } else {
+                    self
+                }
+            }
+
+            pub fn lerp(self, other: $name, t: f32) -> $name {
+                self + (other - self) * t
+            }
+
+            pub fn min(self, other: $name) -> $name {
+                $name { $($field: self.$field.min(other.$field)),+ }
+            }
+
+            pub fn max(self, other: $name) -> $name {
+                $name { $($field: self.$field.max(other.$field)),+ }
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+            fn add(self, other: $name) -> $name {
+                $name { $($field: self.$field + other.$field),+ }
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+            fn sub(self, other: $name) -> $name {
+                $name { $($field: self.$field - other.$field),+ }
+            }
+        }
+
+        impl Mul<f32> for $name {
+            type Output = $name;
+            fn mul(self, scale: f32) -> $name {
+                $name { $($field: self.$field * scale),+ }
+            }
+        }
+
+        impl Mul<$name> for $name {
+            type Output = $name;
+            fn mul(self, other: $name) -> $name {
+                $name { $($field: self.$field * other.$field),+ }
+            }
+        }
+
+        impl Div<f32> for $name {
+            type Output = $name;
+            fn div(self, scale: f32) -> $name {
+                $name { $($field: self.$field / scale),+ }
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+            fn neg(self) -> $name {
+                $name { $($field: -self.$field),+ }
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, other: $name) {
+                *self = *self + other;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, other: $name) {
+                *self = *self - other;
+            }
+        }
+
+        impl MulAssign<f32> for $name {
+            fn mul_assign(&mut self, scale: f32) {
+                *self = *self * scale;
+            }
+        }
+
+        impl From<$array> for $name {
+            fn from(array: $array) -> $name {
+                let [$($field),+] = array;
+                $name { $($field),+ }
+            }
+        }
+
+        impl From<$name> for $array {
+            fn from(vector: $name) -> $array {
+                [$(vector.$field),+]
+            }
+        }
+    };
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec4 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl_vector!(Vec2 { x, y }, [f32; 2]);
+impl_vector!(Vec3 { x, y, z }, [f32; 3]);
+impl_vector!(Vec4 { x, y, z, w }, [f32; 4]);
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+    pub const ONE: Vec3 = Vec3::new(1.0, 1.0, 1.0);
+    pub const X: Vec3 = Vec3::new(1.0, 0.0, 0.0);
+    pub const Y: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+    pub const Z: Vec3 = Vec3::new(0.0, 0.0, 1.0);
+
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn extend(self, w: f32) -> Vec4 {
+        Vec4::new(self.x, self.y, self.z, w)
+    }
+}
+
+impl Vec4 {
+    pub fn truncate(self) -> Vec3 {
+        Vec3::new(self.x, self.y, self.z)
+    }
+}
+
+/// A 4x4 matrix stored as four columns, acting on column vectors (`M * v`).
+///
+/// Column-major storage matches the default HLSL constant buffer packing, so a
+/// `Mat4` can be uploaded as-is and used with `mul(M, v)` in a shader.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mat4 {
+    pub cols: [Vec4; 4],
+}
+
+impl Default for Mat4 {
+    fn default() -> Mat4 {
+        Mat4::IDENTITY
+    }
+}
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4 {
+        cols: [
+            Vec4::new(1.0, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 1.0, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, 1.0, 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        ],
+    };
+
+    pub const fn from_cols(x: Vec4, y: Vec4, z: Vec4, w: Vec4) -> Mat4 {
+        Mat4 { cols: [x, y, z, w] }
+    }
+
+    pub fn from_cols_array(m: [f32; 16]) -> Mat4 {
+        Mat4::from_cols(
+            Vec4::new(m[0], m[1], m[2], m[3]),
+            Vec4::new(m[4], m[5], m[6], m[7]),
+            Vec4::new(m[8], m[9], m[10], m[11]),
+            Vec4::new(m[12], m[13], m[14], m[15]),
+        )
+    }
+
+    pub fn to_cols_array(&self) -> [f32; 16] {
+        let mut m = [0.0; 16];
+        for (c, col) in self.cols.iter().enumerate() {
+            m[c * 4..c * 4 + 4].copy_from_slice(&<[f32; 4]>::from(*col));
+        }
+        m
+    }
+
+    pub fn row(&self, r: usize) -> Vec4 {
+        let m = self.to_cols_array();
+        Vec4::new(m[r], m[4 + r], m[8 + r], m[12 + r])
+    }
+
+    pub fn translation(offset: Vec3) -> Mat4 {
+        let mut m = Mat4::IDENTITY;
+        m.cols[3] = offset.extend(1.0);
+        m
+    }
+
+    pub fn scale(scale: Vec3) -> Mat4 {
+        let mut m = Mat4::IDENTITY;
+        m.cols[0].x = scale.x;
+        m.cols[1].y = scale.y;
+        m.cols[2].z = scale.z;
+        m
+    }
+
+    pub fn rotation_x(angle: f32) -> Mat4 {
+        Mat4::from_quat(Quat::from_axis_angle(Vec3::X, angle))
+    }
+
+    pub fn rotation_y(angle: f32) -> Mat4 {
+        Mat4::from_quat(Quat::from_axis_angle(Vec3::Y, angle))
+    }
+
+    pub fn rotation_z(angle: f32) -> Mat4 {
+        Mat4::from_quat(Quat::from_axis_angle(Vec3::Z, angle))
+    }
+
+    /// Scale, then rotate, then translate.
+    pub fn from_scale_rotation_translation(scale: Vec3, rotation: Quat, translation: Vec3) -> Mat4 {
+        let r = Mat4::from_quat(rotation);
+        Mat4::from_cols(
+            r.cols[0] * scale.x,
+            r.cols[1] * scale.y,
+            r.cols[2] * scale.z,
+            translation.extend(1.0),
+        )
+    }
+
+    pub fn from_quat(q: Quat) -> Mat4 {
+        let Quat { x, y, z, w } = q.normalize();
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        let (xy, xz, yz) = (x * y, x * z, y * z);
+        let (wx, wy, wz) = (w * x, w * y, w * z);
+
+        Mat4::from_cols(
+            Vec4::new(1.0 - 2.0 * (yy + zz), 2.0 * (xy + wz), 2.0 * (xz - wy), 0.0),
+            Vec4::new(2.0 * (xy - wz), 1.0 - 2.0 * (xx + zz), 2.0 * (yz + wx), 0.0),
+            Vec4::new(2.0 * (xz + wy), 2.0 * (yz - wx), 1.0 - 2.0 * (xx + yy), 0.0),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
+
+    pub fn transpose(&self) -> Mat4 {
+        Mat4::from_cols(self.row(0), self.row(1), self.row(2), self.row(3))
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let (cofactors, _) = self.cofactors();
+        let m = self.to_cols_array();
+        m[0] * cofactors[0] + m[1] * cofactors[4] + m[2] * cofactors[8] + m[3] * cofactors[12]
+    }
+
+    /// Returns `None` when the matrix is singular.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let (inv, m) = self.cofactors();
+        let det = m[0] * inv[0] + m[1] * inv[4] + m[2] * inv[8] + m[3] * inv[12];
+        if det.abs() <= f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+        Some(Mat4::from_cols_array(inv.map(|v| v / det)))
+    }
+
+    // The transposed cofactor matrix (adjugate) in column-major order, alongside
+    // the source elements.
+    fn cofactors(&self) -> ([f32; 16], [f32; 16]) {
+        let m = self.to_cols_array();
+        let mut inv = [0.0; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14]
+            + m[13] * m[6] * m[11]
+            - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14]
+            - m[12] * m[6] * m[11]
+            + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13]
+            + m[12] * m[5] * m[11]
+            - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13]
+            - m[12] * m[5] * m[10]
+            + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14]
+            - m[13] * m[2] * m[11]
+            + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14]
+            + m[12] * m[2] * m[11]
+            - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13]
+            - m[12] * m[1] * m[11]
+            + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13]
+            + m[12] * m[1] * m[10]
+            - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14]
+            + m[13] * m[2] * m[7]
+            - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14]
+            - m[12] * m[2] * m[7]
+            + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13]
+            + m[12] * m[1] * m[7]
+            - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13]
+            - m[12] * m[1] * m[6]
+            + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10]
+            - m[9] * m[2] * m[7]
+            + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10]
+            + m[8] * m[2] * m[7]
+            - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9]
+            - m[8] * m[1] * m[7]
+            + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9]
+            + m[8] * m[1] * m[6]
+            - m[8] * m[2] * m[5];
+
+        (inv, m)
+    }
+
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        let v = *self * point.extend(1.0);
+        v.truncate() / v.w
+    }
+
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        (*self * vector.extend(0.0)).truncate()
+    }
+
+    /// A view matrix for a left-handed world, with the camera looking along +z.
+    pub fn look_at_lh(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+        let forward = (target - eye).normalize();
+        let right = up.cross(forward).normalize();
+        let up = forward.cross(right);
+        Mat4::view(eye, right, up, forward)
+    }
+
+    /// A view matrix for a right-handed world, with the camera looking along -z.
+    pub fn look_at_rh(eye: Vec3, target: Vec3, up: Vec3) -> Mat4 {
+        let forward = (target - eye).normalize();
+        let right = forward.cross(up).normalize();
+        let up = right.cross(forward);
+        Mat4::view(eye, right, up, -forward)
+    }
+
+    fn view(eye: Vec3, x: Vec3, y: Vec3, z: Vec3) -> Mat4 {
+        Mat4::from_cols(
+            Vec4::new(x.x, y.x, z.x, 0.0),
+            Vec4::new(x.y, y.y, z.y, 0.0),
+            Vec4::new(x.z, y.z, z.z, 0.0),
+            Vec4::new(-x.dot(eye), -y.dot(eye), -z.dot(eye), 1.0),
+        )
+    }
+
+    /// A perspective projection mapping view depth `near..far` to the Direct3D
+    /// clip range `0..1`, for a camera looking along +z.
+    pub fn perspective_lh(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+        let y_scale = 1.0 / (fov_y * 0.5).tan();
+        let x_scale = y_scale / aspect;
+        let range = far / (far - near);
+        Mat4::from_cols(
+            Vec4::new(x_scale, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, y_scale, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, range, 1.0),
+            Vec4::new(0.0, 0.0, -range * near, 0.0),
+        )
+    }
+
+    /// Like `perspective_lh`, for a camera looking along -z.
+    pub fn perspective_rh(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+        let y_scale = 1.0 / (fov_y * 0.5).tan();
+        let x_scale = y_scale / aspect;
+        let range = far / (near - far);
+        Mat4::from_cols(
+            Vec4::new(x_scale, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, y_scale, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, range, -1.0),
+            Vec4::new(0.0, 0.0, range * near, 0.0),
+        )
+    }
+
+    /// An orthographic projection of a `width` x `height` view volume centred
+    /// on the view axis, mapping `near..far` to `0..1`, looking along +z.
+    pub fn orthographic_lh(width: f32, height: f32, near: f32, far: f32) -> Mat4 {
+        let range = 1.0 / (far - near);
+        Mat4::from_cols(
+            Vec4::new(2.0 / width, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0 / height, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, range, 0.0),
+            Vec4::new(0.0, 0.0, -range * near, 1.0),
+        )
+    }
+
+    /// Like `orthographic_lh`, looking along -z.
+    pub fn orthographic_rh(width: f32, height: f32, near: f32, far: f32) -> Mat4 {
+        let range = 1.0 / (near - far);
+        Mat4::from_cols(
+            Vec4::new(2.0 / width, 0.0, 0.0, 0.0),
+            Vec4::new(0.0, 2.0 / height, 0.0, 0.0),
+            Vec4::new(0.0, 0.0, range, 0.0),
+            Vec4::new(0.0, 0.0, range * near, 1.0),
+        )
+    }
+}
+
+impl Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+    fn mul(self, v: Vec4) -> Vec4 {
+        self.cols[0] * v.x + self.cols[1] * v.y + self.cols[2] * v.z + self.cols[3] * v.w
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Mat4;
+    fn mul(self, other: Mat4) -> Mat4 {
+        Mat4 {
+            cols: other.cols.map(|col| self * col),
+        }
+    }
+}
+
+/// A rotation quaternion `xi + yj + zk + w`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Default for Quat {
+    fn default() -> Quat {
+        Quat::IDENTITY
+    }
+}
+
+impl Quat {
+    pub const IDENTITY: Quat = Quat::new(0.0, 0.0, 0.0, 1.0);
+
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Quat {
+        Quat { x, y, z, w }
+    }
+
+    /// A rotation of `angle` radians about `axis`, anticlockwise when looking
+    /// down the axis towards the origin in a right-handed frame.
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Quat {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle * 0.5).sin_cos();
+        Quat::new(axis.x * sin, axis.y * sin, axis.z * sin, cos)
+    }
+
+    /// Extracts the rotation from the upper 3x3 of a matrix with no scale or
+    /// shear.
+    pub fn from_mat4(m: &Mat4) -> Quat {
+        let [m00, m10, m20, _] = <[f32; 4]>::from(m.cols[0]);
+        let [m01, m11, m21, _] = <[f32; 4]>::from(m.cols[1]);
+        let [m02, m12, m22, _] = <[f32; 4]>::from(m.cols[2]);
+
+        let trace = m00 + m11 + m22;
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quat::new((m21 - m12) / s, (m02 - m20) / s, (m10 - m01) / s, 0.25 * s)
+        } else if m00 > m11 && m00 > m22 {
+            let s = (1.0 + m00 - m11 - m22).sqrt() * 2.0;
+            Quat::new(0.25 * s, (m01 + m10) / s, (m02 + m20) / s, (m21 - m12) / s)
+        } else if m11 > m22 {
+            let s = (1.0 + m11 - m00 - m22).sqrt() * 2.0;
+            Quat::new((m01 + m10) / s, 0.25 * s, (m12 + m21) / s, (m02 - m20) / s)
+        } else {
+            let s = (1.0 + m22 - m00 - m11).sqrt() * 2.0;
+            Quat::new((m02 + m20) / s, (m12 + m21) / s, 0.25 * s, (m10 - m01) / s)
+        };
+        q.normalize()
+    }
+
+    pub fn dot(self, other: Quat) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Quat {
+        let length = self.length();
+        if length > 0.0 {
+            Quat::new(
+                self.x / length,
+                self.y / length,
+                self.z / length,
+                self.w / length,
+            )
+        } else {
+            Quat::IDENTITY
+        }
+    }
+
+    pub fn conjugate(self) -> Quat {
+        Quat::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn rotate(self, v: Vec3) -> Vec3 {
+        let u = Vec3::new(self.x, self.y, self.z);
+        let t = u.cross(v) * 2.0;
+        v + t * self.w + u.cross(t)
+    }
+
+    /// Spherical interpolation along the shorter arc between two rotations.
+    pub fn slerp(self, other: Quat, t: f32) -> Quat {
+        let mut other = other;
+        let mut cos = self.dot(other);
+        if cos < 0.0 {
+            other = Quat::new(-other.x, -other.y, -other.z, -other.w);
+            cos = -cos;
+        }
+
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+
+        Quat::new(
+            self.x * a + other.x * b,
+            self.y * a + other.y * b,
+            self.z * a + other.z * b,
+            self.w * a + other.w * b,
+        )
+        .normalize()
+    }
+}
+
+impl Mul for Quat {
+    type Output = Quat;
+    /// The rotation `other` followed by `self`.
+    fn mul(self, other: Quat) -> Quat {
+        Quat::new(
+            self.w * other.x + self.x * other.w + self.y * other.z - self.z * other.y,
+            self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x,
+            self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w,
+            self.w * other.w - self.x * other.x - self.y * other.y - self.z * other.z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_mat_near(a: &Mat4, b: &Mat4, epsilon: f32) {
+        for (x, y) in a.to_cols_array().iter().zip(b.to_cols_array().iter()) {
+            assert!((x - y).abs() <= epsilon, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn assert_vec_near(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    fn vec3() -> impl Strategy<Value = Vec3> {
+        (-10.0f32..10.0, -10.0f32..10.0, -10.0f32..10.0).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+    }
+
+    fn quat() -> impl Strategy<Value = Quat> {
+        (vec3(), -10.0f32..10.0)
+            .prop_filter("axis must be non-zero", |(axis, _)| axis.length() > 1e-3)
+            .prop_map(|(axis, angle)| Quat::from_axis_angle(axis, angle))
+    }
+
+    fn scale() -> impl Strategy<Value = Vec3> {
+        (0.1f32..5.0, 0.1f32..5.0, 0.1f32..5.0).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+    }
+
+    proptest! {
+        #[test]
+        fn inverse_times_matrix_is_identity(s in scale(), r in quat(), t in vec3()) {
+            let m = Mat4::from_scale_rotation_translation(s, r, t);
+            let inverse = m.inverse().unwrap();
+            assert_mat_near(&(inverse * m), &Mat4::IDENTITY, 1e-4);
+            assert_mat_near(&(m * inverse), &Mat4::IDENTITY, 1e-4);
+        }
+
+        #[test]
+        fn quaternion_matrix_round_trip(q in quat(), v in vec3()) {
+            let m = Mat4::from_quat(q);
+            let back = Quat::from_mat4(&m);
+
+            // q and -q are the same rotation.
+            prop_assert!(back.dot(q).abs() > 1.0 - 1e-4);
+            assert_vec_near(m.transform_vector(v), q.rotate(v));
+            assert_mat_near(&Mat4::from_quat(back), &m, 1e-5);
+        }
+
+        #[test]
+        fn transpose_is_an_involution(s in scale(), r in quat(), t in vec3()) {
+            let m = Mat4::from_scale_rotation_translation(s, r, t);
+            prop_assert_eq!(m.transpose().transpose(), m);
+        }
+
+        #[test]
+        fn slerp_hits_endpoints(a in quat(), b in quat()) {
+            prop_assert!(a.slerp(b, 0.0).dot(a).abs() > 1.0 - 1e-4);
+            prop_assert!(a.slerp(b, 1.0).dot(b).abs() > 1.0 - 1e-4);
+        }
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        assert_eq!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+    }
+
+    #[test]
+    fn rotations_follow_right_hand_rule() {
+        assert_vec_near(
+            Mat4::rotation_z(FRAC_PI_2).transform_vector(Vec3::X),
+            Vec3::Y,
+        );
+        assert_vec_near(
+            Mat4::rotation_x(FRAC_PI_2).transform_vector(Vec3::Y),
+            Vec3::Z,
+        );
+        assert_vec_near(
+            Mat4::rotation_y(FRAC_PI_2).transform_vector(Vec3::Z),
+            Vec3::X,
+        );
+    }
+
+    #[test]
+    fn slerp_halfway() {
+        let a = Quat::IDENTITY;
+        let b = Quat::from_axis_angle(Vec3::Y, FRAC_PI_2);
+        let half = a.slerp(b, 0.5);
+        assert_vec_near(half.rotate(Vec3::Z), Vec3::new(1.0, 0.0, 1.0).normalize());
+    }
+
+    #[test]
+    fn perspective_maps_near_and_far_to_unit_depth() {
+        for &(projection, near_z, far_z) in &[
+            (Mat4::perspective_lh(1.0, 1.5, 0.5, 20.0), 0.5, 20.0),
+            (Mat4::perspective_rh(1.0, 1.5, 0.5, 20.0), -0.5, -20.0),
+            (Mat4::orthographic_lh(4.0, 3.0, 0.5, 20.0), 0.5, 20.0),
+            (Mat4::orthographic_rh(4.0, 3.0, 0.5, 20.0), -0.5, -20.0),
+        ] {
+            let near = projection.transform_point(Vec3::new(0.0, 0.0, near_z));
+            let far = projection.transform_point(Vec3::new(0.0, 0.0, far_z));
+            assert!(near.z.abs() < 1e-5);
+            assert!((far.z - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn look_at_puts_target_in_front() {
+        let eye = Vec3::new(3.0, 2.0, -5.0);
+        let lh = Mat4::look_at_lh(eye, Vec3::ZERO, Vec3::Y);
+        let rh = Mat4::look_at_rh(eye, Vec3::ZERO, Vec3::Y);
+
+        let distance = eye.length();
+        assert_vec_near(
+            lh.transform_point(Vec3::ZERO),
+            Vec3::new(0.0, 0.0, distance),
+        );
+        assert_vec_near(
+            rh.transform_point(Vec3::ZERO),
+            Vec3::new(0.0, 0.0, -distance),
+        );
+        assert_vec_near(lh.transform_point(eye), Vec3::ZERO);
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+use crate::vertex::{Vertex, VertexLayout, VertexSemantic};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mesh {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    layout: VertexLayout,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MeshError {
+    IncompleteTriangle {
+        index_count: usize,
+    },
+    IndexOutOfRange {
+        position: usize,
+        index: u32,
+        vertex_count: usize,
+    },
+    MissingPosition,
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::IncompleteTriangle { index_count } => {
+                write!(f, "index count {} is not a multiple of three", index_count)
+            }
+            MeshError::IndexOutOfRange {
+                position,
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "index {} at position {} is out of range for {} vertices",
+                index, position, vertex_count
+            ),
+            MeshError::MissingPosition => write!(f, "vertex layout has no position attribute"),
+        }
+    }
+}
+
+impl Error for MeshError {}
+
+impl Mesh {
+    /// Creates an indexed triangle list, checking that the indices form whole
+    /// triangles and only refer to existing vertices.
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Result<Mesh, MeshError> {
+        if !indices.len().is_multiple_of(3) {
+            return Err(MeshError::IncompleteTriangle {
+                index_count: indices.len(),
+            });
+        }
+
+        if let Some((position, &index)) = indices
+            .iter()
+            .enumerate()
+            .find(|(_, &index)| index as usize >= vertices.len())
+        {
+            return Err(MeshError::IndexOutOfRange {
+                position,
+                index,
+                vertex_count: vertices.len(),
+            });
+        }
+
+        Ok(Mesh::new_unchecked(vertices, indices))
+    }
+
+    pub(crate) fn new_unchecked(vertices: Vec<Vertex>, indices: Vec<u32>) -> Mesh {
+        Mesh {
+            vertices,
+            indices,
+            layout: Vertex::layout(&[VertexSemantic::Position]),
+        }
+    }
+
+    /// A mesh with the same attribute layout as `self` but new geometry.
+    pub(crate) fn with_geometry(&self, vertices: Vec<Vertex>, indices: Vec<u32>) -> Mesh {
+        Mesh {
+            layout: self.layout.clone(),
+            ..Mesh::new_unchecked(vertices, indices)
+        }
+    }
+
+    /// Selects which `Vertex` attributes are uploaded with the mesh. Meshes start
+    /// out with positions only.
+    pub fn with_attributes(mut self, semantics: &[VertexSemantic]) -> Result<Mesh, MeshError> {
+        if !semantics.contains(&VertexSemantic::Position) {
+            return Err(MeshError::MissingPosition);
+        }
+        self.layout = Vertex::layout(semantics);
+        Ok(self)
+    }
+
+    /// Adds `semantic` to the uploaded attributes if it is not already there.
+    pub fn with_attribute(mut self, semantic: VertexSemantic) -> Mesh {
+        if !self.layout.contains(semantic) {
+            self.layout = self.layout.with(semantic, Vertex::format(semantic));
+        }
+        self
+    }
+
+    pub fn layout(&self) -> &VertexLayout {
+        &self.layout
+    }
+
+    pub fn has_attribute(&self, semantic: VertexSemantic) -> bool {
+        self.layout.contains(semantic)
+    }
+
+    /// The vertices interleaved according to `layout`, ready for upload.
+    pub fn vertex_data(&self) -> Vec<u8> {
+        Vertex::write(&self.vertices, &self.layout)
+    }
+
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// A line list with each edge shared by one or more triangles exactly once,
+    /// in the order the edges are first used.
+    pub fn edge_indices(&self) -> Vec<u32> {
+        let mut seen = HashSet::new();
+        let mut edges = Vec::new();
+        for triangle in self.indices.chunks_exact(3) {
+            for i in 0..3 {
+                let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+                if seen.insert((a.min(b), a.max(b))) {
+                    edges.extend_from_slice(&[a, b]);
+                }
+            }
+        }
+        edges
+    }
+
+    pub fn into_parts(self) -> (Vec<Vertex>, Vec<u32>) {
+        (self.vertices, self.indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube;
+
+    #[test]
+    fn accepts_cube() {
+        let mesh = cube::unit();
+        assert_eq!(mesh.vertices().len(), 36);
+        assert_eq!(mesh.triangle_count(), 12);
+    }
+
+    #[test]
+    fn rejects_partial_triangle() {
+        let result = Mesh::new(cube::unit().vertices().to_vec(), vec![0, 1, 2, 3]);
+        assert_eq!(
+            result,
+            Err(MeshError::IncompleteTriangle { index_count: 4 })
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_index() {
+        let result = Mesh::new(
+            cube::unit().vertices()[..3].to_vec(),
+            vec![0, 1, 2, 2, 1, 3],
+        );
+        assert_eq!(
+            result,
+            Err(MeshError::IndexOutOfRange {
+                position: 5,
+                index: 3,
+                vertex_count: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn edges_are_unique() {
+        let vertices = cube::unit().vertices()[..4].to_vec();
+        let mesh = Mesh::new(vertices, vec![0, 1, 2, 2, 1, 3]).unwrap();
+        assert_eq!(mesh.edge_indices(), vec![0, 1, 1, 2, 2, 0, 1, 3, 3, 2]);
+
+        // Closed meshes satisfy Euler's formula, V - E + F = 2.
+        let cube = cube::generate(9);
+        let edge_count = cube.edge_indices().len() / 2;
+        assert_eq!(
+            edge_count,
+            cube.vertices().len() + cube.triangle_count() - 2
+        );
+    }
+
+    #[test]
+    fn attributes_select_layout() {
+        let mesh = cube::unit();
+        assert_eq!(mesh.vertex_data().len(), 36 * 12);
+
+        let mesh = mesh
+            .with_attributes(&[VertexSemantic::Position, VertexSemantic::Color])
+            .unwrap();
+        assert!(mesh.has_attribute(VertexSemantic::Color));
+        assert_eq!(mesh.vertex_data().len(), 36 * 28);
+
+        assert_eq!(
+            mesh.with_attributes(&[VertexSemantic::Normal]),
+            Err(MeshError::MissingPosition)
+        );
+    }
+}
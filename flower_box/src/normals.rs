@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use crate::mesh::Mesh;
+use crate::vertex::{Vertex, VertexSemantic};
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt();
+    if length > 0.0 {
+        [v[0] / length, v[1] / length, v[2] / length]
+    } else {
+        [0.0, 0.0, 0.0]
+    }
+}
+
+/// The unit normal of triangle `abc`, pointing towards the side from which the
+/// triangle appears clockwise (the front face in the Direct3D convention).
+pub fn face_normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    normalize(cross(sub(b, a), sub(c, a)))
+}
+
+fn corner_angle(corner: [f32; 3], a: [f32; 3], b: [f32; 3]) -> f32 {
+    let (u, v) = (normalize(sub(a, corner)), normalize(sub(b, corner)));
+    dot(u, v).clamp(-1.0, 1.0).acos()
+}
+
+fn triangle_positions(mesh: &Mesh, triangle: &[u32]) -> [[f32; 3]; 3] {
+    let vertices = mesh.vertices();
+    [
+        vertices[triangle[0] as usize].position,
+        vertices[triangle[1] as usize].position,
+        vertices[triangle[2] as usize].position,
+    ]
+}
+
+/// Gives every triangle its own three vertices carrying the face normal, so
+/// no vertex is shared between faces.
+pub fn flat(mesh: &Mesh) -> Mesh {
+    let mut vertices = Vec::with_capacity(mesh.indices().len());
+    for triangle in mesh.indices().chunks_exact(3) {
+        let [a, b, c] = triangle_positions(mesh, triangle);
+        let normal = face_normal(a, b, c);
+        for &index in triangle {
+            vertices.push(Vertex {
+                normal,
+                ..mesh.vertices()[index as usize]
+            });
+        }
+    }
+
+    let indices = (0..vertices.len() as u32).collect();
+    mesh.with_geometry(vertices, indices)
+        .with_attribute(VertexSemantic::Normal)
+}
+
+/// Averages face normals around each vertex position, weighted by the angle
+/// each face makes at that corner.
+///
+/// Only faces within `max_angle` radians of a corner's own face contribute to
+/// it, so edges sharper than the threshold stay hard. Vertices are split
+/// wherever corners that shared a vertex end up with different normals, and
+/// corners at the same position in different vertices are smoothed together.
+pub fn smooth(mesh: &Mesh, max_angle: f32) -> Mesh {
+    let cos_threshold = max_angle.cos();
+    let triangles: Vec<&[u32]> = mesh.indices().chunks_exact(3).collect();
+
+    let face_normals: Vec<[f32; 3]> = triangles
+        .iter()
+        .map(|triangle| {
+            let [a, b, c] = triangle_positions(mesh, triangle);
+            face_normal(a, b, c)
+        })
+        .collect();
+
+    // Every (triangle, corner angle) touching a position, keyed by exact bits so
+    // unwelded copies of a vertex are still treated as the same point.
+    let position_key = |index: u32| mesh.vertices()[index as usize].position.map(f32::to_bits);
+    let mut corners_at: HashMap<[u32; 3], Vec<(usize, f32)>> = HashMap::new();
+    for (t, triangle) in triangles.iter().enumerate() {
+        let positions = triangle_positions(mesh, triangle);
+        for k in 0..3 {
+            let angle = corner_angle(positions[k], positions[(k + 1) % 3], positions[(k + 2) % 3]);
+            corners_at
+                .entry(position_key(triangle[k]))
+                .or_default()
+                .push((t, angle));
+        }
+    }
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::with_capacity(mesh.indices().len());
+    let mut split: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+    for (t, triangle) in triangles.iter().enumerate() {
+        for &index in triangle.iter() {
+            let mut sum = [0.0; 3];
+            for &(other, angle) in &corners_at[&position_key(index)] {
+                if dot(face_normals[t], face_normals[other]) >= cos_threshold {
+                    for (total, component) in sum.iter_mut().zip(face_normals[other].iter()) {
+                        *total += component * angle;
+                    }
+                }
+            }
+            let normal = normalize(sum);
+
+            let new_index = *split
+                .entry((index, normal.map(f32::to_bits)))
+                .or_insert_with(|| {
+                    vertices.push(Vertex {
+                        normal,
+                        ..mesh.vertices()[index as usize]
+                    });
+                    (vertices.len() - 1) as u32
+                });
+            indices.push(new_index);
+        }
+    }
+
+    mesh.with_geometry(vertices, indices)
+        .with_attribute(VertexSemantic::Normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube;
+
+    fn assert_axis_aligned(mesh: &Mesh) {
+        for vertex in mesh.vertices() {
+            let n = vertex.normal;
+            let non_zero = n.iter().filter(|c| c.abs() > 1e-6).count();
+            assert_eq!(non_zero, 1, "{:?} is not axis-aligned", n);
+            assert!((dot(n, n) - 1.0).abs() < 1e-6);
+            assert!(dot(n, vertex.position) > 0.0, "{:?} points inwards", n);
+        }
+    }
+
+    #[test]
+    fn flat_normals_split_every_corner() {
+        let mesh = flat(&cube::generate(2));
+        assert_eq!(mesh.vertices().len(), mesh.indices().len());
+        assert!(mesh.has_attribute(VertexSemantic::Normal));
+        assert_axis_aligned(&mesh);
+    }
+
+    #[test]
+    fn smooth_normals_keep_cube_edges_hard() {
+        for segments in 1..4 {
+            let mesh = smooth(&cube::generate(segments), 45f32.to_radians());
+            let per_face = (segments + 1) * (segments + 1);
+            assert_eq!(mesh.vertices().len() as u32, 6 * per_face);
+            assert_axis_aligned(&mesh);
+        }
+    }
+
+    #[test]
+    fn smooth_normals_work_on_unwelded_triangles() {
+        let mesh = cube::unit();
+        assert_axis_aligned(&smooth(&mesh, 45f32.to_radians()));
+    }
+
+    #[test]
+    fn wide_threshold_rounds_corners() {
+        let mesh = smooth(&cube::generate(1), 180f32.to_radians());
+        assert_eq!(mesh.vertices().len(), 8);
+
+        let diagonal = 1.0 / 3f32.sqrt();
+        for vertex in mesh.vertices() {
+            for (n, p) in vertex.normal.iter().zip(vertex.position.iter()) {
+                assert!((n - diagonal * p.signum()).abs() < 1e-5);
+            }
+        }
+    }
+}
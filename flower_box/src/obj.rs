@@ -0,0 +1,556 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use crate::mesh::Mesh;
+use crate::vertex::{Vertex, VertexSemantic};
+
+/// Faces between two `g` or `o` statements, as one mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjGroup {
+    pub name: String,
+    pub mesh: Mesh,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ObjError {
+    Io(String),
+    InvalidNumber {
+        line: usize,
+        token: String,
+    },
+    /// A `v`, `vt` or `vn` statement with fewer values than it needs.
+    MissingValues {
+        line: usize,
+        statement: String,
+    },
+    /// A face vertex that isn't `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+    InvalidFaceVertex {
+        line: usize,
+        token: String,
+    },
+    /// Zero, or a reference to an element that hasn't been defined yet.
+    IndexOutOfRange {
+        line: usize,
+        index: i64,
+        count: usize,
+    },
+    TooFewFaceVertices {
+        line: usize,
+        count: usize,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(reason) => write!(f, "failed to read OBJ file: {}", reason),
+            ObjError::InvalidNumber { line, token } => {
+                write!(f, "line {}: '{}' is not a number", line, token)
+            }
+            ObjError::MissingValues { line, statement } => {
+                write!(f, "line {}: too few values for '{}'", line, statement)
+            }
+            ObjError::InvalidFaceVertex { line, token } => {
+                write!(f, "line {}: '{}' is not a valid face vertex", line, token)
+            }
+            ObjError::IndexOutOfRange { line, index, count } => write!(
+                f,
+                "line {}: index {} is out of range for {} elements",
+                line, index, count
+            ),
+            ObjError::TooFewFaceVertices { line, count } => {
+                write!(
+                    f,
+                    "line {}: a face needs 3 vertices but has {}",
+                    line, count
+                )
+            }
+        }
+    }
+}
+
+impl Error for ObjError {}
+
+/// Parses Wavefront OBJ text into one mesh per group. Faces before the first
+/// `g` or `o` statement go in a group named `default`, and groups without
+/// faces are dropped.
+///
+/// OBJ is right-handed with counter-clockwise front faces and texture rows
+/// counted from the bottom, so z is negated, triangles are rewound clockwise
+/// and v is flipped. N-gons are fan triangulated and so should be convex.
+/// Material, smoothing and other unsupported statements are ignored.
+///
+/// Each mesh has positions plus normals and texture coordinates when any of
+/// its faces reference them; corners that don't are left zeroed.
+pub fn parse(source: &str) -> Result<Vec<ObjGroup>, ObjError> {
+    read(source, true)
+}
+
+/// Like `parse`, but with every face in a single mesh.
+pub fn parse_mesh(source: &str) -> Result<Mesh, ObjError> {
+    let mut groups = read(source, false)?;
+    Ok(groups
+        .pop()
+        .map(|group| group.mesh)
+        .unwrap_or_else(|| Mesh::new_unchecked(Vec::new(), Vec::new())))
+}
+
+/// Serializes `mesh` as OBJ text, undoing the conversions `parse` applies.
+/// Normals and texture coordinates are written when the mesh's layout has them.
+pub fn write(mesh: &Mesh) -> String {
+    let has_normals = mesh.has_attribute(VertexSemantic::Normal);
+    let has_uvs = mesh.has_attribute(VertexSemantic::TexCoord);
+
+    let mut out = String::from("# flower_box\n");
+    for vertex in mesh.vertices() {
+        let [x, y, z] = vertex.position;
+        out += &format!("v {} {} {}\n", x, y, -z);
+    }
+    if has_uvs {
+        for vertex in mesh.vertices() {
+            let [u, v] = vertex.uv;
+            out += &format!("vt {} {}\n", u, 1.0 - v);
+        }
+    }
+    if has_normals {
+        for vertex in mesh.vertices() {
+            let [x, y, z] = vertex.normal;
+            out += &format!("vn {} {} {}\n", x, y, -z);
+        }
+    }
+
+    let corner = |index: u32| {
+        let index = index + 1;
+        match (has_uvs, has_normals) {
+            (false, false) => index.to_string(),
+            (true, false) => format!("{}/{}", index, index),
+            (false, true) => format!("{}//{}", index, index),
+            (true, true) => format!("{}/{}/{}", index, index, index),
+        }
+    };
+    for triangle in mesh.indices().chunks_exact(3) {
+        out += &format!(
+            "f {} {} {}\n",
+            corner(triangle[0]),
+            corner(triangle[2]),
+            corner(triangle[1])
+        );
+    }
+    out
+}
+
+pub fn save(path: impl AsRef<Path>, mesh: &Mesh) -> Result<(), ObjError> {
+    std::fs::write(path, write(mesh)).map_err(|error| ObjError::Io(error.to_string()))
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<ObjGroup>, ObjError> {
+    let source = std::fs::read_to_string(path).map_err(|error| ObjError::Io(error.to_string()))?;
+    parse(&source)
+}
+
+// Indices of a face corner's position, texture coordinate and normal.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+struct GroupBuilder {
+    name: String,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    corners: HashMap<Corner, u32>,
+    has_uvs: bool,
+    has_normals: bool,
+}
+
+impl GroupBuilder {
+    fn new(name: String) -> GroupBuilder {
+        GroupBuilder {
+            name,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            corners: HashMap::new(),
+            has_uvs: false,
+            has_normals: false,
+        }
+    }
+
+    fn finish(self) -> ObjGroup {
+        let mut mesh = Mesh::new_unchecked(self.vertices, self.indices);
+        if self.has_normals {
+            mesh = mesh.with_attribute(VertexSemantic::Normal);
+        }
+        if self.has_uvs {
+            mesh = mesh.with_attribute(VertexSemantic::TexCoord);
+        }
+        ObjGroup {
+            name: self.name,
+            mesh,
+        }
+    }
+}
+
+struct Reader {
+    positions: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    normals: Vec<[f32; 3]>,
+    groups: Vec<ObjGroup>,
+    group: GroupBuilder,
+}
+
+impl Reader {
+    fn start_group(&mut self, name: String) {
+        let group = std::mem::replace(&mut self.group, GroupBuilder::new(name));
+        if !group.indices.is_empty() {
+            self.groups.push(group.finish());
+        }
+    }
+
+    fn corner_index(&mut self, corner: Corner) -> u32 {
+        let group = &mut self.group;
+        if let Some(&index) = group.corners.get(&corner) {
+            return index;
+        }
+
+        let (position, uv, normal) = corner;
+        let [x, y, z] = self.positions[position];
+        let mut vertex = Vertex::new(x, y, -z);
+        if let Some(uv) = uv {
+            let [u, v] = self.uvs[uv];
+            vertex.uv = [u, 1.0 - v];
+            group.has_uvs = true;
+        }
+        if let Some(normal) = normal {
+            let [x, y, z] = self.normals[normal];
+            vertex.normal = [x, y, -z];
+            group.has_normals = true;
+        }
+        let index = group.vertices.len() as u32;
+        group.vertices.push(vertex);
+        group.corners.insert(corner, index);
+        index
+    }
+}
+
+fn read(source: &str, split_groups: bool) -> Result<Vec<ObjGroup>, ObjError> {
+    let mut reader = Reader {
+        positions: Vec::new(),
+        uvs: Vec::new(),
+        normals: Vec::new(),
+        groups: Vec::new(),
+        group: GroupBuilder::new("default".into()),
+    };
+
+    for (line_index, text) in source.lines().enumerate() {
+        let line = line_index + 1;
+        let text = text.split('#').next().unwrap_or("");
+        let mut tokens = text.split_whitespace();
+        let statement = match tokens.next() {
+            Some(statement) => statement,
+            None => continue,
+        };
+        let values: Vec<&str> = tokens.collect();
+
+        match statement {
+            "v" => {
+                let [x, y, z] = floats(line, statement, &values)?;
+                reader.positions.push([x, y, z]);
+            }
+            "vt" => {
+                // The v coordinate is optional and defaults to zero.
+                let [u] = floats(line, statement, &values)?;
+                let v = match values.get(1) {
+                    Some(token) => float(line, token)?,
+                    None => 0.0,
+                };
+                reader.uvs.push([u, v]);
+            }
+            "vn" => {
+                let [x, y, z] = floats(line, statement, &values)?;
+                reader.normals.push([x, y, z]);
+            }
+            "f" => {
+                if values.len() < 3 {
+                    return Err(ObjError::TooFewFaceVertices {
+                        line,
+                        count: values.len(),
+                    });
+                }
+                let mut corners = Vec::with_capacity(values.len());
+                for token in &values {
+                    let corner = face_corner(&reader, line, token)?;
+                    corners.push(reader.corner_index(corner));
+                }
+                // Reversing the fan rewinds each triangle clockwise.
+                for i in 1..corners.len() - 1 {
+                    reader.group.indices.extend_from_slice(&[
+                        corners[0],
+                        corners[i + 1],
+                        corners[i],
+                    ]);
+                }
+            }
+            "g" | "o" if split_groups => {
+                let name = if values.is_empty() {
+                    "default".to_string()
+                } else {
+                    values.join(" ")
+                };
+                reader.start_group(name);
+            }
+            _ => {}
+        }
+    }
+
+    reader.start_group(String::new());
+    Ok(reader.groups)
+}
+
+fn float(line: usize, token: &str) -> Result<f32, ObjError> {
+    token.parse().map_err(|_| ObjError::InvalidNumber {
+        line,
+        token: token.to_string(),
+    })
+}
+
+/// Parses the first `N` values; any beyond those are ignored.
+fn floats<const N: usize>(
+    line: usize,
+    statement: &str,
+    values: &[&str],
+) -> Result<[f32; N], ObjError> {
+    if values.len() < N {
+        return Err(ObjError::MissingValues {
+            line,
+            statement: statement.to_string(),
+        });
+    }
+    let mut out = [0.0; N];
+    for (value, token) in out.iter_mut().zip(values) {
+        *value = float(line, token)?;
+    }
+    Ok(out)
+}
+
+fn face_corner(reader: &Reader, line: usize, token: &str) -> Result<Corner, ObjError> {
+    let invalid = || ObjError::InvalidFaceVertex {
+        line,
+        token: token.to_string(),
+    };
+    let parts: Vec<&str> = token.split('/').collect();
+    if parts.len() > 3 || parts[0].is_empty() {
+        return Err(invalid());
+    }
+
+    let part = |i: usize, count: usize| -> Result<Option<usize>, ObjError> {
+        match parts.get(i) {
+            None | Some(&"") => Ok(None),
+            Some(text) => {
+                let index: i64 = text.parse().map_err(|_| invalid())?;
+                resolve(line, index, count).map(Some)
+            }
+        }
+    };
+    let position = part(0, reader.positions.len())?.ok_or_else(invalid)?;
+    Ok((
+        position,
+        part(1, reader.uvs.len())?,
+        part(2, reader.normals.len())?,
+    ))
+}
+
+/// Converts a one-based index, or a negative one counting back from the most
+/// recent element, into a zero-based one.
+fn resolve(line: usize, index: i64, count: usize) -> Result<usize, ObjError> {
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(ObjError::IndexOutOfRange { line, index, count });
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::cube::{self, UvLayout};
+    use crate::normals::{self, face_normal};
+
+    const QUAD: &str = "\
+# A unit quad facing +z, counter-clockwise from the front.
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+";
+
+    #[test]
+    fn quad_is_triangulated_and_converted() {
+        let groups = parse(QUAD).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "default");
+
+        let mesh = &groups[0].mesh;
+        assert!(mesh.has_attribute(VertexSemantic::Normal));
+        assert!(mesh.has_attribute(VertexSemantic::TexCoord));
+        assert_eq!(mesh.vertices().len(), 4);
+        assert_eq!(mesh.indices(), &[0, 2, 1, 0, 3, 2]);
+
+        // The front face now points down -z and the winding agrees with it.
+        let vertices = mesh.vertices();
+        for triangle in mesh.indices().chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position);
+            assert_eq!(face_normal(a, b, c), [0.0, 0.0, -1.0]);
+        }
+        assert_eq!(vertices[0].normal, [0.0, 0.0, -1.0]);
+        assert_eq!(vertices[0].uv, [0.0, 1.0]);
+        assert_eq!(vertices[2].uv, [1.0, 0.0]);
+    }
+
+    #[test]
+    fn negative_indices_and_groups() {
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+g first
+f -3 -2 -1
+o second
+v 0 0 1
+f 1// 2 -1
+g empty
+";
+        let groups = parse(source).unwrap();
+        let names: Vec<&str> = groups.iter().map(|group| group.name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second"]);
+        assert_eq!(groups[1].mesh.vertices()[2].position, [0.0, 0.0, -1.0]);
+        assert!(!groups[1].mesh.has_attribute(VertexSemantic::Normal));
+
+        let merged = parse_mesh(source).unwrap();
+        assert_eq!(merged.triangle_count(), 2);
+        assert_eq!(merged.vertices().len(), 4);
+    }
+
+    #[test]
+    fn shared_corners_become_shared_vertices() {
+        let mesh = parse_mesh(&cube_obj()).unwrap();
+        assert_eq!(mesh.vertices().len(), 8);
+        assert_eq!(mesh.triangle_count(), 12);
+        assert!(Mesh::new(mesh.vertices().to_vec(), mesh.indices().to_vec()).is_ok());
+    }
+
+    #[test]
+    fn malformed_lines_are_reported() {
+        let error = |source: &str| parse(source).unwrap_err();
+        assert_eq!(
+            error("v 0 0 0\nv 1 x 0"),
+            ObjError::InvalidNumber {
+                line: 2,
+                token: "x".into()
+            }
+        );
+        assert_eq!(
+            error("\nvn 0 1"),
+            ObjError::MissingValues {
+                line: 2,
+                statement: "vn".into()
+            }
+        );
+        assert_eq!(
+            error("v 0 0 0\nf 1 2"),
+            ObjError::TooFewFaceVertices { line: 2, count: 2 }
+        );
+        assert_eq!(
+            error("v 0 0 0\nf 1 1 0"),
+            ObjError::IndexOutOfRange {
+                line: 2,
+                index: 0,
+                count: 1
+            }
+        );
+        assert_eq!(
+            error("v 0 0 0\nf 1 1 -2"),
+            ObjError::IndexOutOfRange {
+                line: 2,
+                index: -2,
+                count: 1
+            }
+        );
+        assert_eq!(
+            error("v 0 0 0\nf 1 1 1/2/3/4"),
+            ObjError::InvalidFaceVertex {
+                line: 2,
+                token: "1/2/3/4".into()
+            }
+        );
+        assert_eq!(
+            error("v 0 0 0\nf 1/1 1 1").to_string(),
+            "line 2: index 1 is out of range for 0 elements"
+        );
+    }
+
+    #[test]
+    fn written_meshes_round_trip() {
+        let plain = cube::generate(3);
+        let textured = normals::smooth(
+            &cube::generate_textured(2, UvLayout::Cross),
+            std::f32::consts::FRAC_PI_4,
+        );
+        for mesh in &[plain, textured] {
+            let imported = parse_mesh(&write(mesh)).unwrap();
+            for &semantic in &[VertexSemantic::Normal, VertexSemantic::TexCoord] {
+                assert_eq!(
+                    imported.has_attribute(semantic),
+                    mesh.has_attribute(semantic)
+                );
+            }
+            assert_same_triangles(&imported, mesh);
+        }
+    }
+
+    /// Compares the triangles of two meshes regardless of the order their
+    /// vertices are stored in. Flipping v loses a little precision, so
+    /// attributes only need to be close.
+    pub(crate) fn assert_same_triangles(actual: &Mesh, expected: &Mesh) {
+        assert_eq!(actual.indices().len(), expected.indices().len());
+        let corners = |mesh: &Mesh| -> Vec<Vertex> {
+            mesh.indices()
+                .iter()
+                .map(|&index| mesh.vertices()[index as usize])
+                .collect()
+        };
+        for (a, b) in corners(actual).iter().zip(&corners(expected)) {
+            for &semantic in &[
+                VertexSemantic::Position,
+                VertexSemantic::Normal,
+                VertexSemantic::TexCoord,
+            ] {
+                for (x, y) in a.attribute(semantic).iter().zip(b.attribute(semantic)) {
+                    assert!((x - y).abs() < 1e-6, "{:?} differs from {:?}", a, b);
+                }
+            }
+        }
+    }
+
+    fn cube_obj() -> String {
+        let mut source = String::new();
+        for i in 0..8 {
+            let coord = |bit: usize| if i & bit == 0 { -0.5 } else { 0.5 };
+            source += &format!("v {} {} {}\n", coord(1), coord(2), coord(4));
+        }
+        for face in &[
+            "1 3 4 2", "5 6 8 7", "1 2 6 5", "3 7 8 4", "1 5 7 3", "2 4 8 6",
+        ] {
+            source += &format!("f {}\n", face);
+        }
+        source
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::mesh::Mesh;
+use crate::normals::face_normal;
+use crate::vertex::{Vertex, VertexSemantic};
+
+/// Post-transform cache size assumed by `vertex_cache` and `Stats`.
+pub const CACHE_SIZE: usize = 32;
+
+// Tuning constants from Tom Forsyth's "Linear-Speed Vertex Cache Optimisation".
+const CACHE_DECAY_POWER: f32 = 1.5;
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+/// Vertex and triangle counts along with the average cache miss ratio: the
+/// number of vertex shader invocations per triangle with a FIFO cache of
+/// `CACHE_SIZE` vertices, between 0.5 at best and 3.0 at worst.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub vertex_count: usize,
+    pub triangle_count: usize,
+    pub acmr: f32,
+}
+
+impl Stats {
+    pub fn of(mesh: &Mesh) -> Stats {
+        Stats {
+            vertex_count: mesh.vertices().len(),
+            triangle_count: mesh.triangle_count(),
+            acmr: acmr(mesh.indices(), CACHE_SIZE),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Report {
+    pub before: Stats,
+    pub after: Stats,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} vertices, {} -> {} triangles, ACMR {:.3} -> {:.3}",
+            self.before.vertex_count,
+            self.after.vertex_count,
+            self.before.triangle_count,
+            self.after.triangle_count,
+            self.before.acmr,
+            self.after.acmr
+        )
+    }
+}
+
+/// The average number of cache misses per triangle when drawing `indices`
+/// through a FIFO cache holding `cache_size` vertices.
+pub fn acmr(indices: &[u32], cache_size: usize) -> f32 {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return 0.0;
+    }
+
+    let mut cache = VecDeque::with_capacity(cache_size + 1);
+    let mut misses = 0;
+    for index in indices {
+        if !cache.contains(index) {
+            misses += 1;
+            cache.push_back(*index);
+            if cache.len() > cache_size {
+                cache.pop_front();
+            }
+        }
+    }
+    misses as f32 / triangle_count as f32
+}
+
+/// Runs `weld`, `remove_degenerate_triangles`, `vertex_cache` and
+/// `vertex_fetch` in turn, reporting the statistics before and after.
+pub fn optimize(mesh: &Mesh, epsilon: f32) -> (Mesh, Report) {
+    let welded = remove_degenerate_triangles(&weld(mesh, epsilon));
+    let optimized = vertex_fetch(&vertex_cache(&welded));
+    let report = Report {
+        before: Stats::of(mesh),
+        after: Stats::of(&optimized),
+    };
+    (optimized, report)
+}
+
+/// Merges vertices whose uploaded attributes all differ by at most `epsilon`,
+/// keeping the first vertex of each group. An `epsilon` of zero only merges
+/// exact duplicates.
+pub fn weld(mesh: &Mesh, epsilon: f32) -> Mesh {
+    let semantics: Vec<VertexSemantic> = mesh
+        .layout()
+        .attributes()
+        .iter()
+        .map(|attribute| attribute.semantic)
+        .collect();
+    let close = |a: &Vertex, b: &Vertex| {
+        semantics.iter().all(|&semantic| {
+            a.attribute(semantic)
+                .iter()
+                .zip(b.attribute(semantic))
+                .all(|(x, y)| (x - y).abs() <= epsilon)
+        })
+    };
+    // Vertices within `epsilon` of each other land in the same or neighbouring
+    // grid cells. Adding zero turns -0.0 into 0.0 so they share a cell, and
+    // huge coordinates saturate, so neighbours wrap rather than overflow.
+    let cell = |position: [f32; 3]| {
+        position.map(|c| {
+            if epsilon > 0.0 {
+                (c / epsilon).floor() as i64
+            } else {
+                (c + 0.0).to_bits() as i64
+            }
+        })
+    };
+
+    let mut grid: HashMap<[i64; 3], Vec<u32>> = HashMap::new();
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let remap: Vec<u32> = mesh
+        .vertices()
+        .iter()
+        .map(|vertex| {
+            let [x, y, z] = cell(vertex.position);
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let neighbour =
+                            [x.wrapping_add(dx), y.wrapping_add(dy), z.wrapping_add(dz)];
+                        let found = grid.get(&neighbour).and_then(|candidates| {
+                            candidates
+                                .iter()
+                                .find(|&&index| close(&vertices[index as usize], vertex))
+                        });
+                        if let Some(&index) = found {
+                            return index;
+                        }
+                    }
+                }
+            }
+
+            let index = vertices.len() as u32;
+            vertices.push(*vertex);
+            grid.entry([x, y, z]).or_default().push(index);
+            index
+        })
+        .collect();
+
+    let indices = mesh
+        .indices()
+        .iter()
+        .map(|&index| remap[index as usize])
+        .collect();
+    mesh.with_geometry(vertices, indices)
+}
+
+/// Drops triangles with no area, including those that use a vertex twice.
+pub fn remove_degenerate_triangles(mesh: &Mesh) -> Mesh {
+    let vertices = mesh.vertices();
+    let indices = mesh
+        .indices()
+        .chunks_exact(3)
+        .filter(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|k| vertices[triangle[k] as usize].position);
+            face_normal(a, b, c) != [0.0; 3]
+        })
+        .flatten()
+        .copied()
+        .collect();
+    mesh.with_geometry(vertices.to_vec(), indices)
+}
+
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.0;
+    }
+
+    let cache_score = match cache_position {
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scale = 1.0 / (CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        }
+        None => 0.0,
+    };
+    let valence_boost =
+        VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER);
+    cache_score + valence_boost
+}
+
+/// Reorders triangles so that consecutive triangles reuse recently
+/// transformed vertices, using Forsyth's greedy scoring over an LRU cache of
+/// `CACHE_SIZE` vertices. Each triangle keeps its own index order, and so its
+/// winding.
+pub fn vertex_cache(mesh: &Mesh) -> Mesh {
+    let triangles: Vec<&[u32]> = mesh.indices().chunks_exact(3).collect();
+    let mut vertex_triangles = vec![Vec::new(); mesh.vertices().len()];
+    for (t, triangle) in triangles.iter().enumerate() {
+        for &index in triangle.iter() {
+            vertex_triangles[index as usize].push(t);
+        }
+    }
+
+    let mut vertex_scores: Vec<f32> = vertex_triangles
+        .iter()
+        .map(|adjacent| vertex_score(None, adjacent.len()))
+        .collect();
+    let mut triangle_scores: Vec<f32> = triangles
+        .iter()
+        .map(|triangle| triangle.iter().map(|&i| vertex_scores[i as usize]).sum())
+        .collect();
+
+    let mut emitted = vec![false; triangles.len()];
+    let mut indices = Vec::with_capacity(mesh.indices().len());
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    // Where to look for an unemitted triangle once nothing in the cache is
+    // still in use, such as when moving on to a disconnected piece.
+    let mut next_unemitted = 0;
+    let mut best = triangle_scores
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(t, _)| t);
+
+    while let Some(t) = best {
+        let triangle = triangles[t];
+        emitted[t] = true;
+        indices.extend_from_slice(triangle);
+
+        let mut new_cache = Vec::with_capacity(CACHE_SIZE + 3);
+        for &index in triangle {
+            let adjacent = &mut vertex_triangles[index as usize];
+            if let Some(k) = adjacent.iter().position(|&other| other == t) {
+                adjacent.swap_remove(k);
+            }
+            if !new_cache.contains(&index) {
+                new_cache.push(index);
+            }
+        }
+        for &index in &cache {
+            if !new_cache.contains(&index) {
+                new_cache.push(index);
+            }
+        }
+
+        // Rescore everything that moved in the cache, including the vertices
+        // that just fell out of it.
+        for (position, &index) in new_cache.iter().enumerate() {
+            let index = index as usize;
+            let position = Some(position).filter(|&p| p < CACHE_SIZE);
+            let score = vertex_score(position, vertex_triangles[index].len());
+            let delta = score - vertex_scores[index];
+            vertex_scores[index] = score;
+            for &other in &vertex_triangles[index] {
+                triangle_scores[other] += delta;
+            }
+        }
+        new_cache.truncate(CACHE_SIZE);
+        cache = new_cache;
+
+        best = None;
+        let mut best_score = f32::NEG_INFINITY;
+        for &index in &cache {
+            for &other in &vertex_triangles[index as usize] {
+                if triangle_scores[other] > best_score {
+                    best = Some(other);
+                    best_score = triangle_scores[other];
+                }
+            }
+        }
+        if best.is_none() {
+            while next_unemitted < triangles.len() && emitted[next_unemitted] {
+                next_unemitted += 1;
+            }
+            best = Some(next_unemitted).filter(|&t| t < triangles.len());
+        }
+    }
+
+    mesh.with_geometry(mesh.vertices().to_vec(), indices)
+}
+
+/// Renumbers vertices in the order the indices first use them, so the vertex
+/// buffer is read front to back, and drops vertices no triangle uses.
+pub fn vertex_fetch(mesh: &Mesh) -> Mesh {
+    let mut remap = vec![None; mesh.vertices().len()];
+    let mut vertices = Vec::new();
+    let indices = mesh
+        .indices()
+        .iter()
+        .map(|&index| {
+            *remap[index as usize].get_or_insert_with(|| {
+                vertices.push(mesh.vertices()[index as usize]);
+                (vertices.len() - 1) as u32
+            })
+        })
+        .collect();
+    mesh.with_geometry(vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube;
+
+    // The corner positions of every triangle, ignoring the order of triangles
+    // but not of the corners within them.
+    fn triangles(mesh: &Mesh) -> Vec<[[u32; 3]; 3]> {
+        let mut triangles: Vec<_> = mesh
+            .indices()
+            .chunks_exact(3)
+            .map(|t| [0, 1, 2].map(|k| mesh.vertices()[t[k] as usize].position.map(f32::to_bits)))
+            .collect();
+        triangles.sort_unstable();
+        triangles
+    }
+
+    #[test]
+    fn welding_merges_nearby_vertices() {
+        let mesh = cube::unit();
+        assert_eq!(weld(&mesh, 0.0).vertices().len(), 8);
+        assert_eq!(triangles(&weld(&mesh, 0.0)), triangles(&mesh));
+
+        // Normals are uploaded, so the corners of different faces stay apart.
+        let flat = crate::normals::flat(&cube::generate(1));
+        assert_eq!(weld(&flat, 1e-5).vertices().len(), 24);
+
+        let mut vertices = mesh.vertices().to_vec();
+        vertices[1].position[0] += 1e-4;
+        let nudged = mesh.with_geometry(vertices, mesh.indices().to_vec());
+        assert_eq!(weld(&nudged, 0.0).vertices().len(), 9);
+        assert_eq!(weld(&nudged, 1e-3).vertices().len(), 8);
+    }
+
+    #[test]
+    fn degenerate_triangles_are_removed() {
+        let mut vertices = cube::unit().vertices()[..3].to_vec();
+        vertices.push(Vertex::new(2.0, 0.0, 0.0));
+        vertices.push(Vertex::new(4.0, 0.0, 0.0));
+        vertices.push(Vertex::new(6.0, 0.0, 0.0));
+        let mesh = Mesh::new(vertices, vec![0, 1, 2, 0, 0, 1, 3, 4, 5]).unwrap();
+
+        let cleaned = remove_degenerate_triangles(&mesh);
+        assert_eq!(cleaned.indices(), &[0, 1, 2]);
+    }
+
+    #[test]
+    fn vertex_cache_keeps_triangles() {
+        let mesh = cube::generate(16);
+        let optimized = vertex_cache(&mesh);
+        assert_eq!(triangles(&optimized), triangles(&mesh));
+        assert!(acmr(optimized.indices(), CACHE_SIZE) <= acmr(mesh.indices(), CACHE_SIZE));
+
+        // Shuffling the triangles ruins locality, which reordering recovers.
+        let triangles: Vec<&[u32]> = mesh.indices().chunks_exact(3).collect();
+        let shuffled: Vec<u32> = (0..triangles.len())
+            .flat_map(|t| triangles[t * 37 % triangles.len()])
+            .copied()
+            .collect();
+        let shuffled = mesh.with_geometry(mesh.vertices().to_vec(), shuffled);
+        assert!(acmr(shuffled.indices(), CACHE_SIZE) > 1.5);
+        assert!(acmr(vertex_cache(&shuffled).indices(), CACHE_SIZE) < 0.8);
+    }
+
+    #[test]
+    fn vertex_fetch_follows_first_use() {
+        let mut vertices = cube::unit().vertices()[..4].to_vec();
+        vertices.push(Vertex::new(9.0, 9.0, 9.0));
+        let mesh = Mesh::new(vertices.clone(), vec![3, 1, 2, 2, 1, 0]).unwrap();
+
+        let compacted = vertex_fetch(&mesh);
+        assert_eq!(compacted.indices(), &[0, 1, 2, 2, 1, 3]);
+        assert_eq!(
+            compacted.vertices(),
+            &[vertices[3], vertices[1], vertices[2], vertices[0]]
+        );
+    }
+
+    #[test]
+    fn optimize_reports_statistics() {
+        let (mesh, report) = optimize(&cube::unit(), 1e-5);
+        assert_eq!(triangles(&mesh), triangles(&cube::unit()));
+        assert_eq!(report.before, Stats::of(&cube::unit()));
+        assert_eq!(report.before.acmr, 3.0);
+        assert_eq!(report.after.vertex_count, 8);
+        assert_eq!(report.after.triangle_count, 12);
+        assert_eq!(report.after.acmr, 8.0 / 12.0);
+        assert_eq!(
+            report.to_string(),
+            "36 -> 8 vertices, 12 -> 12 triangles, ACMR 3.000 -> 0.667"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+/// Which triangles are discarded. Front faces are wound clockwise on screen,
+/// as with Direct3D's default rasterizer state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CullMode {
+    None,
+    Front,
+    Back,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FillMode {
+    Solid,
+    /// Only the edges of each triangle are drawn.
+    Wireframe,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Replaces the target.
+    Opaque,
+    /// `src * src.a + dst * (1 - src.a)`.
+    Alpha,
+    /// `src * src.a + dst`.
+    Additive,
+}
+
+impl BlendMode {
+    /// Combines a shaded colour with the colour already in the target.
+    pub fn blend(self, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+        let alpha = src[3];
+        let mut out = [0.0; 4];
+        for (i, value) in out.iter_mut().enumerate() {
+            *value = match self {
+                BlendMode::Opaque => src[i],
+                BlendMode::Alpha => src[i] * alpha + dst[i] * (1.0 - alpha),
+                BlendMode::Additive => src[i] * alpha + dst[i],
+            };
+        }
+        out
+    }
+}
+
+/// How index buffers are assembled into primitives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Topology {
+    TriangleList,
+    /// Each pair of indices is a line. Lines are never culled and ignore the
+    /// fill mode.
+    LineList,
+}
+
+/// Fixed-function state applied to draws, created once with
+/// `GraphicsDevice::create_pipeline_state` and then bound by handle.
+///
+/// The default matches what backends use before any state is bound: depth
+/// tested with `LESS_EQUAL` and written, no culling, solid, opaque triangles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PipelineState {
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub cull_mode: CullMode,
+    pub fill_mode: FillMode,
+    pub blend_mode: BlendMode,
+    pub topology: Topology,
+}
+
+impl Default for PipelineState {
+    fn default() -> PipelineState {
+        PipelineState {
+            depth_test: true,
+            depth_write: true,
+            cull_mode: CullMode::None,
+            fill_mode: FillMode::Solid,
+            blend_mode: BlendMode::Opaque,
+            topology: Topology::TriangleList,
+        }
+    }
+}
+
+impl PipelineState {
+    /// Alpha blended and depth tested without writing depth, for transparent
+    /// geometry drawn after everything opaque.
+    pub fn transparent() -> PipelineState {
+        PipelineState {
+            depth_write: false,
+            blend_mode: BlendMode::Alpha,
+            ..PipelineState::default()
+        }
+    }
+
+    pub fn wireframe() -> PipelineState {
+        PipelineState {
+            fill_mode: FillMode::Wireframe,
+            ..PipelineState::default()
+        }
+    }
+
+    pub fn lines() -> PipelineState {
+        PipelineState {
+            topology: Topology::LineList,
+            ..PipelineState::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blend_modes() {
+        let src = [1.0, 0.0, 0.0, 0.25];
+        let dst = [0.0, 0.0, 1.0, 1.0];
+        assert_eq!(BlendMode::Opaque.blend(src, dst), src);
+        assert_eq!(BlendMode::Alpha.blend(src, dst), [0.25, 0.0, 0.75, 0.8125]);
+        assert_eq!(
+            BlendMode::Additive.blend(src, dst),
+            [0.25, 0.0, 1.0, 1.0625]
+        );
+    }
+}
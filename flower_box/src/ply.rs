@@ -0,0 +1,687 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use crate::mesh::Mesh;
+use crate::vertex::{Vertex, VertexSemantic};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlyFormat {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlyError {
+    Io(String),
+    /// A malformed or unsupported header line.
+    InvalidHeader {
+        line: usize,
+        reason: String,
+    },
+    /// The vertex element lacks `x`, `y` or `z`, or there is no face element
+    /// with a `vertex_indices` list.
+    MissingProperty(String),
+    /// An ASCII value that doesn't parse as its property's type.
+    InvalidValue {
+        line: usize,
+        token: String,
+    },
+    UnexpectedEof,
+    IndexOutOfRange {
+        face: usize,
+        index: i64,
+        vertex_count: usize,
+    },
+}
+
+impl fmt::Display for PlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlyError::Io(reason) => write!(f, "failed to read PLY file: {}", reason),
+            PlyError::InvalidHeader { line, reason } => {
+                write!(f, "invalid PLY header at line {}: {}", line, reason)
+            }
+            PlyError::MissingProperty(name) => write!(f, "PLY file has no {} property", name),
+            PlyError::InvalidValue { line, token } => {
+                write!(f, "line {}: '{}' is not a valid value", line, token)
+            }
+            PlyError::UnexpectedEof => write!(f, "PLY file ended unexpectedly"),
+            PlyError::IndexOutOfRange {
+                face,
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "face {} refers to vertex {} but there are only {}",
+                face, index, vertex_count
+            ),
+        }
+    }
+}
+
+impl Error for PlyError {}
+
+/// Serializes `mesh` as a PLY file with float positions, plus normals
+/// (`nx`, `ny`, `nz`) and texture coordinates (`s`, `t`) when the mesh's layout
+/// has them.
+///
+/// Like OBJ export, z is negated, faces are wound counter-clockwise and t is
+/// counted from the bottom, matching right-handed tools such as Blender.
+pub fn write(mesh: &Mesh, format: PlyFormat) -> Vec<u8> {
+    let has_normals = mesh.has_attribute(VertexSemantic::Normal);
+    let has_uvs = mesh.has_attribute(VertexSemantic::TexCoord);
+
+    let format_name = match format {
+        PlyFormat::Ascii => "ascii",
+        PlyFormat::BinaryLittleEndian => "binary_little_endian",
+        PlyFormat::BinaryBigEndian => "binary_big_endian",
+    };
+    let mut header = format!(
+        "ply\nformat {} 1.0\ncomment flower_box\nelement vertex {}\n",
+        format_name,
+        mesh.vertices().len()
+    );
+    let mut properties = vec!["x", "y", "z"];
+    if has_normals {
+        properties.extend_from_slice(&["nx", "ny", "nz"]);
+    }
+    if has_uvs {
+        properties.extend_from_slice(&["s", "t"]);
+    }
+    for property in &properties {
+        header += &format!("property float {}\n", property);
+    }
+    header += &format!(
+        "element face {}\nproperty list uchar uint vertex_indices\nend_header\n",
+        mesh.triangle_count()
+    );
+
+    let mut out = header.into_bytes();
+    for vertex in mesh.vertices() {
+        let [x, y, z] = vertex.position;
+        let mut values = vec![x, y, -z];
+        if has_normals {
+            let [x, y, z] = vertex.normal;
+            values.extend_from_slice(&[x, y, -z]);
+        }
+        if has_uvs {
+            let [s, t] = vertex.uv;
+            values.extend_from_slice(&[s, 1.0 - t]);
+        }
+        match format {
+            PlyFormat::Ascii => {
+                let values: Vec<String> = values.iter().map(f32::to_string).collect();
+                out.extend_from_slice(values.join(" ").as_bytes());
+                out.push(b'\n');
+            }
+            PlyFormat::BinaryLittleEndian => {
+                for value in values {
+                    out.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            PlyFormat::BinaryBigEndian => {
+                for value in values {
+                    out.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+        }
+    }
+
+    for triangle in mesh.indices().chunks_exact(3) {
+        let face = [triangle[0], triangle[2], triangle[1]];
+        match format {
+            PlyFormat::Ascii => {
+                let line = format!("3 {} {} {}\n", face[0], face[1], face[2]);
+                out.extend_from_slice(line.as_bytes());
+            }
+            PlyFormat::BinaryLittleEndian => {
+                out.push(3);
+                for index in &face {
+                    out.extend_from_slice(&index.to_le_bytes());
+                }
+            }
+            PlyFormat::BinaryBigEndian => {
+                out.push(3);
+                for index in &face {
+                    out.extend_from_slice(&index.to_be_bytes());
+                }
+            }
+        }
+    }
+    out
+}
+
+pub fn save(path: impl AsRef<Path>, mesh: &Mesh, format: PlyFormat) -> Result<(), PlyError> {
+    std::fs::write(path, write(mesh, format)).map_err(|error| PlyError::Io(error.to_string()))
+}
+
+/// Parses a PLY file in any of the three formats into a triangle mesh,
+/// applying the reverse of `write`'s conversions.
+///
+/// Positions come from the `x`, `y` and `z` properties of the `vertex`
+/// element, normals from `nx`, `ny` and `nz`, and texture coordinates from
+/// `s` and `t` or `u` and `v`. Polygons in the `face` element's
+/// `vertex_indices` lists are fan triangulated; other elements and properties
+/// are skipped.
+pub fn parse(bytes: &[u8]) -> Result<Mesh, PlyError> {
+    let (header, body) = split_header(bytes)?;
+    let header_lines = header.len();
+    let (format, elements) = parse_header(&header)?;
+    // Every row takes up some minimum number of bytes of the body, which
+    // bounds how many rows the header can claim. The last ASCII value need not
+    // be followed by whitespace.
+    let mut remaining = body.len() + 1;
+    for element in &elements {
+        let min_row_size: usize = element
+            .properties
+            .iter()
+            .map(|property| match (format, property.kind) {
+                // A value and the whitespace after it.
+                (PlyFormat::Ascii, _) => 2,
+                (_, PropertyKind::Scalar(scalar)) => scalar.size(),
+                (_, PropertyKind::List { count, .. }) => count.size(),
+            })
+            .sum();
+        remaining = element
+            .count
+            .checked_mul(min_row_size)
+            .and_then(|size| remaining.checked_sub(size))
+            .ok_or(PlyError::UnexpectedEof)?;
+    }
+
+    let mut body = match format {
+        PlyFormat::Ascii => {
+            let text = std::str::from_utf8(body).map_err(|_| PlyError::InvalidValue {
+                line: header_lines + 1,
+                token: "<binary data>".into(),
+            })?;
+            Body::Ascii(Box::new(text.lines().enumerate().flat_map(
+                move |(index, line)| {
+                    line.split_whitespace()
+                        .map(move |token| (header_lines + index + 1, token))
+                },
+            )))
+        }
+        _ => Body::Binary {
+            bytes: body,
+            offset: 0,
+            big_endian: format == PlyFormat::BinaryBigEndian,
+        },
+    };
+
+    let mut vertices: Option<(Vec<Vec<f64>>, &Element)> = None;
+    let mut faces: Option<Vec<Vec<f64>>> = None;
+    for element in &elements {
+        // Rows without properties take no input, so there is nothing to read.
+        if element.properties.is_empty() {
+            continue;
+        }
+
+        let mut scalars = Vec::new();
+        let mut lists = Vec::new();
+        for _ in 0..element.count {
+            let mut row = Vec::with_capacity(element.properties.len());
+            for property in &element.properties {
+                match property.kind {
+                    PropertyKind::Scalar(scalar) => row.push(body.read(scalar)?),
+                    PropertyKind::List { count, item } => {
+                        // Not preallocated, since the length is untrusted.
+                        let length = body.read(count)? as usize;
+                        let mut list = Vec::new();
+                        for _ in 0..length {
+                            list.push(body.read(item)?);
+                        }
+                        if is_face_list(&element.name, &property.name) {
+                            lists.push(list);
+                        }
+                        row.push(0.0);
+                    }
+                }
+            }
+            // Only vertex rows are used.
+            if element.name == "vertex" {
+                scalars.push(row);
+            }
+        }
+
+        if element.name == "vertex" {
+            vertices = Some((scalars, element));
+        } else if element.name == "face" && lists.len() == element.count {
+            faces = Some(lists);
+        }
+    }
+
+    let (rows, element) = vertices.ok_or_else(|| PlyError::MissingProperty("vertex x".into()))?;
+    let column = |name: &str| element.properties.iter().position(|p| p.name == name);
+    let columns =
+        |names: &[&str]| -> Option<Vec<usize>> { names.iter().map(|name| column(name)).collect() };
+    let positions = ["x", "y", "z"]
+        .iter()
+        .map(|&name| {
+            column(name).ok_or_else(|| PlyError::MissingProperty(format!("vertex {}", name)))
+        })
+        .collect::<Result<Vec<usize>, PlyError>>()?;
+    let normals = columns(&["nx", "ny", "nz"]);
+    let uvs = columns(&["s", "t"]).or_else(|| columns(&["u", "v"]));
+
+    let vertices: Vec<Vertex> = rows
+        .iter()
+        .map(|row| {
+            let value = |i: usize| row[i] as f32;
+            let mut vertex = Vertex::new(
+                value(positions[0]),
+                value(positions[1]),
+                -value(positions[2]),
+            );
+            if let Some(normals) = &normals {
+                vertex.normal = [value(normals[0]), value(normals[1]), -value(normals[2])];
+            }
+            if let Some(uvs) = &uvs {
+                vertex.uv = [value(uvs[0]), 1.0 - value(uvs[1])];
+            }
+            vertex
+        })
+        .collect();
+
+    let faces = faces.ok_or_else(|| PlyError::MissingProperty("face vertex_indices".into()))?;
+    let mut indices = Vec::new();
+    for (face, corners) in faces.iter().enumerate() {
+        for &index in corners {
+            if index < 0.0 || index as usize >= vertices.len() {
+                return Err(PlyError::IndexOutOfRange {
+                    face,
+                    index: index as i64,
+                    vertex_count: vertices.len(),
+                });
+            }
+        }
+        // Polygons with fewer than three corners have no area and are dropped.
+        for i in 1..corners.len().saturating_sub(1) {
+            indices.extend_from_slice(&[
+                corners[0] as u32,
+                corners[i + 1] as u32,
+                corners[i] as u32,
+            ]);
+        }
+    }
+
+    let mut mesh = Mesh::new_unchecked(vertices, indices);
+    if normals.is_some() {
+        mesh = mesh.with_attribute(VertexSemantic::Normal);
+    }
+    if uvs.is_some() {
+        mesh = mesh.with_attribute(VertexSemantic::TexCoord);
+    }
+    Ok(mesh)
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Mesh, PlyError> {
+    let bytes = std::fs::read(path).map_err(|error| PlyError::Io(error.to_string()))?;
+    parse(&bytes)
+}
+
+fn is_face_list(element: &str, property: &str) -> bool {
+    element == "face" && (property == "vertex_indices" || property == "vertex_index")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<ScalarType> {
+        Some(match name {
+            "char" | "int8" => ScalarType::Int8,
+            "uchar" | "uint8" => ScalarType::UInt8,
+            "short" | "int16" => ScalarType::Int16,
+            "ushort" | "uint16" => ScalarType::UInt16,
+            "int" | "int32" => ScalarType::Int32,
+            "uint" | "uint32" => ScalarType::UInt32,
+            "float" | "float32" => ScalarType::Float32,
+            "double" | "float64" => ScalarType::Float64,
+            _ => return None,
+        })
+    }
+
+    /// The values an integer type can hold, or `None` for floats.
+    fn range(self) -> Option<RangeInclusive<i64>> {
+        Some(match self {
+            ScalarType::Int8 => i8::MIN as i64..=i8::MAX as i64,
+            ScalarType::UInt8 => 0..=u8::MAX as i64,
+            ScalarType::Int16 => i16::MIN as i64..=i16::MAX as i64,
+            ScalarType::UInt16 => 0..=u16::MAX as i64,
+            ScalarType::Int32 => i32::MIN as i64..=i32::MAX as i64,
+            ScalarType::UInt32 => 0..=u32::MAX as i64,
+            ScalarType::Float32 | ScalarType::Float64 => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum PropertyKind {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Splits off the header lines, up to and including `end_header`.
+fn split_header(bytes: &[u8]) -> Result<(Vec<String>, &[u8]), PlyError> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while let Some(length) = bytes[offset..].iter().position(|&byte| byte == b'\n') {
+        let line = String::from_utf8_lossy(&bytes[offset..offset + length])
+            .trim_end_matches('\r')
+            .to_string();
+        offset += length + 1;
+        let done = line.trim() == "end_header";
+        lines.push(line);
+        if done {
+            return Ok((lines, &bytes[offset..]));
+        }
+    }
+    Err(PlyError::UnexpectedEof)
+}
+
+fn parse_header(lines: &[String]) -> Result<(PlyFormat, Vec<Element>), PlyError> {
+    let invalid = |line: usize, reason: &str| PlyError::InvalidHeader {
+        line,
+        reason: reason.to_string(),
+    };
+    if lines.first().map(|line| line.trim()) != Some("ply") {
+        return Err(invalid(1, "missing 'ply' magic"));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for (index, text) in lines.iter().enumerate().skip(1) {
+        let line = index + 1;
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["format", name, "1.0"] => {
+                format = Some(match *name {
+                    "ascii" => PlyFormat::Ascii,
+                    "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                    "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                    _ => return Err(invalid(line, "unknown format")),
+                });
+            }
+            ["element", name, count] => {
+                let count = count
+                    .parse()
+                    .map_err(|_| invalid(line, "element count is not a number"))?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            ["property", rest @ ..] => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| invalid(line, "property before any element"))?;
+                let scalar = |name: &str| {
+                    ScalarType::parse(name).ok_or_else(|| invalid(line, "unknown property type"))
+                };
+                let (kind, name) = match rest {
+                    ["list", count, item, name] => (
+                        PropertyKind::List {
+                            count: scalar(count)?,
+                            item: scalar(item)?,
+                        },
+                        name,
+                    ),
+                    [scalar_type, name] => (PropertyKind::Scalar(scalar(scalar_type)?), name),
+                    _ => return Err(invalid(line, "malformed property")),
+                };
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind,
+                });
+            }
+            ["comment", ..] | ["obj_info", ..] | ["end_header"] | [] => {}
+            _ => return Err(invalid(line, "unknown keyword")),
+        }
+    }
+
+    let format = format.ok_or_else(|| invalid(lines.len(), "missing format"))?;
+    Ok((format, elements))
+}
+
+enum Body<'a> {
+    // Whitespace separated tokens with their line numbers.
+    Ascii(Box<dyn Iterator<Item = (usize, &'a str)> + 'a>),
+    Binary {
+        bytes: &'a [u8],
+        offset: usize,
+        big_endian: bool,
+    },
+}
+
+impl Body<'_> {
+    fn read(&mut self, scalar: ScalarType) -> Result<f64, PlyError> {
+        match self {
+            Body::Ascii(tokens) => {
+                let (line, token) = tokens.next().ok_or(PlyError::UnexpectedEof)?;
+                let invalid = || PlyError::InvalidValue {
+                    line,
+                    token: token.to_string(),
+                };
+                match scalar.range() {
+                    None => token.parse().map_err(|_| invalid()),
+                    Some(range) => token
+                        .parse::<i64>()
+                        .ok()
+                        .filter(|value| range.contains(value))
+                        .map(|value| value as f64)
+                        .ok_or_else(invalid),
+                }
+            }
+            Body::Binary {
+                bytes,
+                offset,
+                big_endian,
+            } => {
+                let size = scalar.size();
+                let data = bytes
+                    .get(*offset..*offset + size)
+                    .ok_or(PlyError::UnexpectedEof)?;
+                *offset += size;
+
+                let mut word = [0; 8];
+                word[..size].copy_from_slice(data);
+                if *big_endian {
+                    word[..size].reverse();
+                }
+                let [a, b, c, d, ..] = word;
+                Ok(match scalar {
+                    ScalarType::Int8 => a as i8 as f64,
+                    ScalarType::UInt8 => a as f64,
+                    ScalarType::Int16 => i16::from_le_bytes([a, b]) as f64,
+                    ScalarType::UInt16 => u16::from_le_bytes([a, b]) as f64,
+                    ScalarType::Int32 => i32::from_le_bytes([a, b, c, d]) as f64,
+                    ScalarType::UInt32 => u32::from_le_bytes([a, b, c, d]) as f64,
+                    ScalarType::Float32 => f32::from_le_bytes([a, b, c, d]) as f64,
+                    ScalarType::Float64 => f64::from_le_bytes(word),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::{self, UvLayout};
+    use crate::normals;
+    use crate::obj::{self, tests::assert_same_triangles};
+
+    const FORMATS: [PlyFormat; 3] = [
+        PlyFormat::Ascii,
+        PlyFormat::BinaryLittleEndian,
+        PlyFormat::BinaryBigEndian,
+    ];
+
+    #[test]
+    fn written_meshes_round_trip() {
+        let plain = cube::generate(3);
+        let textured = normals::smooth(
+            &cube::generate_textured(2, UvLayout::PerFace),
+            std::f32::consts::FRAC_PI_4,
+        );
+        for mesh in &[plain, textured] {
+            for &format in &FORMATS {
+                let imported = parse(&write(mesh, format)).unwrap();
+                assert_eq!(imported.vertices().len(), mesh.vertices().len());
+                for &semantic in &[VertexSemantic::Normal, VertexSemantic::TexCoord] {
+                    assert_eq!(
+                        imported.has_attribute(semantic),
+                        mesh.has_attribute(semantic)
+                    );
+                }
+                assert_same_triangles(&imported, mesh);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_obj_export() {
+        // Both exporters use the same conventions, so a PLY file and an OBJ
+        // file of the same mesh import identically.
+        let mesh = cube::generate_textured(1, UvLayout::Cross);
+        let from_ply = parse(&write(&mesh, PlyFormat::Ascii)).unwrap();
+        let from_obj = obj::parse_mesh(&obj::write(&mesh)).unwrap();
+        assert_same_triangles(&from_ply, &from_obj);
+    }
+
+    #[test]
+    fn reads_quads_and_other_types() {
+        let source = "\
+ply
+format ascii 1.0
+comment a unit quad with an extra element
+element vertex 4
+property double x
+property double y
+property double z
+property uchar red
+element material 1
+property list uchar int ids
+element face 1
+property list uchar int vertex_index
+end_header
+0 0 0 255
+1 0 0 255
+1 1 0 255
+0 1 0 255
+2 7 8
+4 0 1 2 3
+";
+        let mesh = parse(source.as_bytes()).unwrap();
+        assert_eq!(mesh.indices(), &[0, 2, 1, 0, 3, 2]);
+        assert_eq!(mesh.vertices()[2].position, [1.0, 1.0, 0.0]);
+        assert!(!mesh.has_attribute(VertexSemantic::Normal));
+    }
+
+    #[test]
+    fn malformed_files_are_reported() {
+        let mesh = cube::generate(1);
+        let ascii = String::from_utf8(write(&mesh, PlyFormat::Ascii)).unwrap();
+
+        assert_eq!(
+            parse(ascii.replace("float y", "half y").as_bytes()),
+            Err(PlyError::InvalidHeader {
+                line: 6,
+                reason: "unknown property type".into()
+            })
+        );
+        assert_eq!(
+            parse(
+                ascii
+                    .replace("end_header\n", "end_header\noops ")
+                    .as_bytes()
+            ),
+            Err(PlyError::InvalidValue {
+                line: 11,
+                token: "oops".into()
+            })
+        );
+        assert_eq!(
+            parse(ascii.replace("3 0 ", "3 99 ").as_bytes()),
+            Err(PlyError::IndexOutOfRange {
+                face: 0,
+                index: 99,
+                vertex_count: 8
+            })
+        );
+
+        let binary = write(&mesh, PlyFormat::BinaryLittleEndian);
+        assert_eq!(
+            parse(&binary[..binary.len() - 1]),
+            Err(PlyError::UnexpectedEof)
+        );
+        assert_eq!(parse(&binary[..20]), Err(PlyError::UnexpectedEof));
+
+        // Huge list lengths run out of input rather than being allocated.
+        let face = ascii.find("\n3 ").unwrap() + 1;
+        let huge = format!(
+            "{}2000000000000000000{}",
+            &ascii[..face],
+            &ascii[face + 1..]
+        );
+        assert!(matches!(
+            parse(huge.as_bytes()),
+            Err(PlyError::InvalidValue { token, .. }) if token == "2000000000000000000"
+        ));
+        let wide = format!("{}256{}", &ascii[..face], &ascii[face + 1..]);
+        assert!(matches!(
+            parse(wide.as_bytes()),
+            Err(PlyError::InvalidValue { token, .. }) if token == "256"
+        ));
+        let header = "ply\nformat ascii 1.0\nelement junk 100000000000000\n";
+        let empty = ascii.replacen("ply\nformat ascii 1.0\n", header, 1);
+        assert_eq!(
+            parse(empty.as_bytes()).unwrap(),
+            parse(ascii.as_bytes()).unwrap()
+        );
+        let rows = ascii.replace("element vertex 8", "element vertex 100000000000000");
+        assert_eq!(parse(rows.as_bytes()), Err(PlyError::UnexpectedEof));
+        let mut long = b"ply\nformat binary_little_endian 1.0\nelement vertex 0\n\
+            property float x\nproperty float y\nproperty float z\nelement face 1\n\
+            property list uint int vertex_indices\nend_header\n"
+            .to_vec();
+        long.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(parse(&long), Err(PlyError::UnexpectedEof));
+    }
+}
@@ -0,0 +1,54 @@
+use std::marker::PhantomData;
+
+/// Errors of binary formats read with `Reader`.
+pub(crate) trait UnexpectedEof {
+    /// The input ended part way through a value.
+    fn unexpected_eof() -> Self;
+}
+
+/// Reads little-endian values from the front of a byte slice, failing with
+/// `E::unexpected_eof()` once it runs out.
+pub(crate) struct Reader<'a, E> {
+    bytes: &'a [u8],
+    offset: usize,
+    error: PhantomData<fn() -> E>,
+}
+
+impl<'a, E: UnexpectedEof> Reader<'a, E> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Reader<'a, E> {
+        Reader {
+            bytes,
+            offset: 0,
+            error: PhantomData,
+        }
+    }
+
+    /// The number of bytes read so far.
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], E> {
+        let end = self.offset.checked_add(len).ok_or_else(E::unexpected_eof)?;
+        let slice = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or_else(E::unexpected_eof)?;
+        self.offset = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, E> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, E> {
+        let mut word = [0; 4];
+        word.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(word))
+    }
+
+    pub(crate) fn f32(&mut self) -> Result<f32, E> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+}
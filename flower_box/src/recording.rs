@@ -0,0 +1,394 @@
+use crate::constant;
+use crate::instance::{self, Instance};
+use crate::pipeline::PipelineState;
+use crate::resource::{self, Pool};
+use crate::vertex::VertexLayout;
+use crate::{
+    ConstantBufferHandle, Error, GraphicsDevice, IndexBufferHandle, InstanceBufferHandle,
+    PipelineStateHandle, Result, VertexBufferHandle,
+};
+
+/// A single `GraphicsDevice` call together with its arguments.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    CreateVertexBuffer {
+        handle: VertexBufferHandle,
+        data: Vec<u8>,
+        layout: VertexLayout,
+    },
+    CreateIndexBuffer {
+        handle: IndexBufferHandle,
+        indices: Vec<u32>,
+    },
+    UpdateVertexBuffer {
+        handle: VertexBufferHandle,
+        data: Vec<u8>,
+    },
+    DestroyVertexBuffer {
+        handle: VertexBufferHandle,
+    },
+    DestroyIndexBuffer {
+        handle: IndexBufferHandle,
+    },
+    CreateConstantBuffer {
+        handle: ConstantBufferHandle,
+        data: Vec<u8>,
+    },
+    UpdateConstantBuffer {
+        handle: ConstantBufferHandle,
+        data: Vec<u8>,
+    },
+    DestroyConstantBuffer {
+        handle: ConstantBufferHandle,
+    },
+    BindConstantBuffer {
+        slot: u32,
+        handle: ConstantBufferHandle,
+    },
+    CreateInstanceBuffer {
+        handle: InstanceBufferHandle,
+        instances: Vec<Instance>,
+    },
+    UpdateInstanceBuffer {
+        handle: InstanceBufferHandle,
+        instances: Vec<Instance>,
+    },
+    DestroyInstanceBuffer {
+        handle: InstanceBufferHandle,
+    },
+    CreatePipelineState {
+        handle: PipelineStateHandle,
+        state: PipelineState,
+    },
+    DestroyPipelineState {
+        handle: PipelineStateHandle,
+    },
+    BindPipelineState {
+        handle: PipelineStateHandle,
+    },
+    BeginFrame {
+        clear_color: [f32; 4],
+        clear_depth: f32,
+    },
+    EndFrame,
+    Draw {
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+    },
+    DrawInstanced {
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+        instance_buffer: InstanceBufferHandle,
+        num_instances: u32,
+    },
+}
+
+/// A `GraphicsDevice` that renders nothing and instead keeps a trace of every
+/// call it accepts.
+///
+/// Handles and index ranges are validated the same way a real backend would,
+/// and calls that fail are not recorded.
+#[derive(Default)]
+pub struct RecordingGraphicsDevice {
+    commands: Vec<Command>,
+    vertex_buffers: Pool<resource::VertexBuffer, (VertexLayout, u32)>,
+    index_buffers: Pool<resource::IndexBuffer, u32>,
+    constant_buffers: Pool<resource::ConstantBuffer, usize>,
+    instance_buffers: Pool<resource::InstanceBuffer, u32>,
+    pipeline_states: Pool<resource::PipelineState, PipelineState>,
+    in_frame: bool,
+}
+
+impl RecordingGraphicsDevice {
+    pub fn new() -> RecordingGraphicsDevice {
+        RecordingGraphicsDevice::default()
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    pub fn take_commands(&mut self) -> Vec<Command> {
+        std::mem::take(&mut self.commands)
+    }
+
+    fn validate_draw(
+        &self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+    ) -> Result<()> {
+        if !self.in_frame {
+            return Err(Error::NoFrameInProgress);
+        }
+        self.vertex_buffers
+            .get(vertex_buffer)
+            .ok_or(Error::InvalidHandle)?;
+        let &available = self
+            .index_buffers
+            .get(index_buffer)
+            .ok_or(Error::InvalidHandle)?;
+
+        if num_indices > available {
+            return Err(Error::InvalidIndexRange {
+                requested: num_indices,
+                available,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl GraphicsDevice for RecordingGraphicsDevice {
+    fn create_vertex_buffer(
+        &mut self,
+        data: &[u8],
+        layout: &VertexLayout,
+    ) -> Result<VertexBufferHandle> {
+        let vertex_count = layout.vertex_count(data)?;
+        let handle = self.vertex_buffers.insert((layout.clone(), vertex_count));
+        self.commands.push(Command::CreateVertexBuffer {
+            handle,
+            data: data.to_vec(),
+            layout: layout.clone(),
+        });
+        Ok(handle)
+    }
+
+    fn create_index_buffer(&mut self, indices: &[u32]) -> Result<IndexBufferHandle> {
+        let handle = self.index_buffers.insert(indices.len() as u32);
+        self.commands.push(Command::CreateIndexBuffer {
+            handle,
+            indices: indices.to_vec(),
+        });
+        Ok(handle)
+    }
+
+    fn update_vertex_buffer(&mut self, handle: VertexBufferHandle, data: &[u8]) -> Result<()> {
+        let (layout, capacity) = self
+            .vertex_buffers
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        layout.validate_update(*capacity, data)?;
+        self.commands.push(Command::UpdateVertexBuffer {
+            handle,
+            data: data.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn destroy_vertex_buffer(&mut self, handle: VertexBufferHandle) -> Result<()> {
+        self.vertex_buffers
+            .remove(handle)
+            .ok_or(Error::InvalidHandle)?;
+        self.commands.push(Command::DestroyVertexBuffer { handle });
+        Ok(())
+    }
+
+    fn destroy_index_buffer(&mut self, handle: IndexBufferHandle) -> Result<()> {
+        self.index_buffers
+            .remove(handle)
+            .ok_or(Error::InvalidHandle)?;
+        self.commands.push(Command::DestroyIndexBuffer { handle });
+        Ok(())
+    }
+
+    fn create_constant_buffer(&mut self, data: &[u8]) -> Result<ConstantBufferHandle> {
+        constant::validate(data)?;
+        let handle = self.constant_buffers.insert(data.len());
+        self.commands.push(Command::CreateConstantBuffer {
+            handle,
+            data: data.to_vec(),
+        });
+        Ok(handle)
+    }
+
+    fn update_constant_buffer(&mut self, handle: ConstantBufferHandle, data: &[u8]) -> Result<()> {
+        let &size = self
+            .constant_buffers
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        constant::validate_update(size, data)?;
+        self.commands.push(Command::UpdateConstantBuffer {
+            handle,
+            data: data.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn destroy_constant_buffer(&mut self, handle: ConstantBufferHandle) -> Result<()> {
+        self.constant_buffers
+            .remove(handle)
+            .ok_or(Error::InvalidHandle)?;
+        self.commands
+            .push(Command::DestroyConstantBuffer { handle });
+        Ok(())
+    }
+
+    fn bind_constant_buffer(&mut self, slot: u32, handle: ConstantBufferHandle) -> Result<()> {
+        constant::validate_slot(slot)?;
+        self.constant_buffers
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        self.commands
+            .push(Command::BindConstantBuffer { slot, handle });
+        Ok(())
+    }
+
+    fn create_instance_buffer(&mut self, instances: &[Instance]) -> Result<InstanceBufferHandle> {
+        instance::validate(instances)?;
+        let handle = self.instance_buffers.insert(instances.len() as u32);
+        self.commands.push(Command::CreateInstanceBuffer {
+            handle,
+            instances: instances.to_vec(),
+        });
+        Ok(handle)
+    }
+
+    fn update_instance_buffer(
+        &mut self,
+        handle: InstanceBufferHandle,
+        instances: &[Instance],
+    ) -> Result<()> {
+        let &capacity = self
+            .instance_buffers
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        instance::validate_range(capacity, instances.len() as u32)?;
+        self.commands.push(Command::UpdateInstanceBuffer {
+            handle,
+            instances: instances.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn destroy_instance_buffer(&mut self, handle: InstanceBufferHandle) -> Result<()> {
+        self.instance_buffers
+            .remove(handle)
+            .ok_or(Error::InvalidHandle)?;
+        self.commands
+            .push(Command::DestroyInstanceBuffer { handle });
+        Ok(())
+    }
+
+    fn create_pipeline_state(&mut self, state: &PipelineState) -> Result<PipelineStateHandle> {
+        let handle = self.pipeline_states.insert(*state);
+        self.commands.push(Command::CreatePipelineState {
+            handle,
+            state: *state,
+        });
+        Ok(handle)
+    }
+
+    fn destroy_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()> {
+        self.pipeline_states
+            .remove(handle)
+            .ok_or(Error::InvalidHandle)?;
+        self.commands.push(Command::DestroyPipelineState { handle });
+        Ok(())
+    }
+
+    fn bind_pipeline_state(&mut self, handle: PipelineStateHandle) -> Result<()> {
+        self.pipeline_states
+            .get(handle)
+            .ok_or(Error::InvalidHandle)?;
+        self.commands.push(Command::BindPipelineState { handle });
+        Ok(())
+    }
+
+    fn begin_frame(&mut self, clear_color: [f32; 4], clear_depth: f32) -> Result<()> {
+        if self.in_frame {
+            return Err(Error::FrameInProgress);
+        }
+        self.in_frame = true;
+        self.commands.push(Command::BeginFrame {
+            clear_color,
+            clear_depth,
+        });
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Result<()> {
+        if !self.in_frame {
+            return Err(Error::NoFrameInProgress);
+        }
+        self.in_frame = false;
+        self.commands.push(Command::EndFrame);
+        Ok(())
+    }
+
+    fn draw(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+    ) -> Result<()> {
+        self.validate_draw(vertex_buffer, index_buffer, num_indices)?;
+        self.commands.push(Command::Draw {
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+        });
+        Ok(())
+    }
+
+    fn draw_instanced(
+        &mut self,
+        vertex_buffer: VertexBufferHandle,
+        index_buffer: IndexBufferHandle,
+        num_indices: u32,
+        instance_buffer: InstanceBufferHandle,
+        num_instances: u32,
+    ) -> Result<()> {
+        self.validate_draw(vertex_buffer, index_buffer, num_indices)?;
+        let &capacity = self
+            .instance_buffers
+            .get(instance_buffer)
+            .ok_or(Error::InvalidHandle)?;
+        instance::validate_range(capacity, num_instances)?;
+
+        self.commands.push(Command::DrawInstanced {
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+            instance_buffer,
+            num_instances,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vertex::{Vertex, VertexSemantic};
+
+    #[test]
+    fn failed_calls_are_not_recorded() {
+        let mut device = RecordingGraphicsDevice::new();
+        let layout = Vertex::layout(&[VertexSemantic::Position]);
+        assert!(device.create_vertex_buffer(&[0; 5], &layout).is_err());
+
+        let vertex_buffer = device.create_vertex_buffer(&[], &layout).unwrap();
+        let index_buffer = device.create_index_buffer(&[0, 0, 0]).unwrap();
+
+        assert!(device.draw(vertex_buffer, index_buffer, 3).is_err());
+        device.begin_frame([0.0; 4], 1.0).unwrap();
+        assert!(device.draw(vertex_buffer, index_buffer, 6).is_err());
+        device.destroy_vertex_buffer(vertex_buffer).unwrap();
+        assert_eq!(
+            device.destroy_vertex_buffer(vertex_buffer),
+            Err(Error::InvalidHandle)
+        );
+
+        assert_eq!(device.commands().len(), 4);
+        assert_eq!(
+            device.commands()[3],
+            Command::DestroyVertexBuffer {
+                handle: vertex_buffer
+            }
+        );
+    }
+}
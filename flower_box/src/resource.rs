@@ -0,0 +1,191 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// Identifies a resource of kind `K` owned by a `GraphicsDevice`.
+///
+/// Handles carry a generation so that a handle to a destroyed resource is not
+/// mistaken for a newer resource reusing the same slot.
+pub struct Handle<K> {
+    index: u32,
+    generation: u32,
+    kind: PhantomData<fn() -> K>,
+}
+
+pub enum VertexBuffer {}
+pub enum IndexBuffer {}
+pub enum ConstantBuffer {}
+pub enum InstanceBuffer {}
+pub enum PipelineState {}
+
+pub type VertexBufferHandle = Handle<VertexBuffer>;
+pub type IndexBufferHandle = Handle<IndexBuffer>;
+pub type ConstantBufferHandle = Handle<ConstantBuffer>;
+pub type InstanceBufferHandle = Handle<InstanceBuffer>;
+pub type PipelineStateHandle = Handle<PipelineState>;
+
+impl<K> Handle<K> {
+    pub(crate) fn from_raw(index: u32, generation: u32) -> Handle<K> {
+        Handle {
+            index,
+            generation,
+            kind: PhantomData,
+        }
+    }
+
+    pub fn index(self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+impl<K> Clone for Handle<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K> Copy for Handle<K> {}
+
+impl<K> PartialEq for Handle<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<K> Eq for Handle<K> {}
+
+impl<K> Hash for Handle<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<K> fmt::Debug for Handle<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({}v{})", self.index, self.generation)
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Backend-side storage for resources addressed by `Handle<K>`.
+pub struct Pool<K, T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    len: usize,
+    kind: PhantomData<fn() -> K>,
+}
+
+impl<K, T> Default for Pool<K, T> {
+    fn default() -> Self {
+        Pool::new()
+    }
+}
+
+impl<K, T> Pool<K, T> {
+    pub fn new() -> Self {
+        Pool {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            kind: PhantomData,
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle<K> {
+        self.len += 1;
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.slots[index as usize].value = Some(value);
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: Some(value),
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+
+        Handle {
+            index,
+            generation: self.slots[index as usize].generation,
+            kind: PhantomData,
+        }
+    }
+
+    pub fn get(&self, handle: Handle<K>) -> Option<&T> {
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<K>) -> Option<&mut T> {
+        self.slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.value.as_mut())
+    }
+
+    pub fn remove(&mut self, handle: Handle<K>) -> Option<T> {
+        let slot = self
+            .slots
+            .get_mut(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)?;
+        let value = slot.value.take()?;
+
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_get() {
+        let mut pool: Pool<VertexBuffer, &str> = Pool::new();
+        let a = pool.insert("a");
+        let b = pool.insert("b");
+
+        assert_ne!(a, b);
+        assert_eq!(pool.get(a), Some(&"a"));
+        assert_eq!(pool.get(b), Some(&"b"));
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn removed_handle_is_stale_after_slot_reuse() {
+        let mut pool: Pool<VertexBuffer, &str> = Pool::new();
+        let a = pool.insert("a");
+        assert_eq!(pool.remove(a), Some("a"));
+
+        let b = pool.insert("b");
+        assert_eq!(a.index(), b.index());
+        assert_eq!(pool.get(a), None);
+        assert_eq!(pool.remove(a), None);
+        assert_eq!(pool.get(b), Some(&"b"));
+        assert_eq!(pool.len(), 1);
+    }
+}
@@ -0,0 +1,607 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::constant::{ConstantBuffer, DrawConstants};
+use crate::instance::Instance;
+use crate::math::Mat4;
+use crate::resource::{Handle, Pool};
+use crate::{GpuMesh, GraphicsDevice, InstanceBufferHandle};
+
+pub enum SceneNode {}
+
+pub type NodeHandle = Handle<SceneNode>;
+
+/// A transform in the scene hierarchy, optionally drawing a mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    local: Mat4,
+    world: Mat4,
+    mesh: Option<GpuMesh>,
+    color: [f32; 4],
+    visible: bool,
+    parent: Option<NodeHandle>,
+    children: Vec<NodeHandle>,
+}
+
+impl Default for Node {
+    fn default() -> Node {
+        Node {
+            local: Mat4::IDENTITY,
+            world: Mat4::IDENTITY,
+            mesh: None,
+            color: DrawConstants::DEFAULT_COLOR,
+            visible: true,
+            parent: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl Node {
+    pub fn new() -> Node {
+        Node::default()
+    }
+
+    pub fn with_local(mut self, local: Mat4) -> Node {
+        self.local = local;
+        self
+    }
+
+    pub fn with_mesh(mut self, mesh: GpuMesh) -> Node {
+        self.mesh = Some(mesh);
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Node {
+        self.color = color;
+        self
+    }
+
+    /// Relative to the parent node, or to the world for a root.
+    pub fn local(&self) -> &Mat4 {
+        &self.local
+    }
+
+    pub fn world(&self) -> &Mat4 {
+        &self.world
+    }
+
+    pub fn mesh(&self) -> Option<&GpuMesh> {
+        self.mesh.as_ref()
+    }
+
+    pub fn color(&self) -> [f32; 4] {
+        self.color
+    }
+
+    /// Hidden nodes also hide everything below them; see `Scene::set_visible`.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn parent(&self) -> Option<NodeHandle> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeHandle] {
+        &self.children
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SceneError {
+    InvalidNode,
+    /// The new parent is the node itself or one of its descendants.
+    Cycle,
+    /// The new parent's world transform cannot be inverted, so the node's world
+    /// transform cannot be preserved under it.
+    SingularParent,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::InvalidNode => write!(f, "node handle is not valid in this scene"),
+            SceneError::Cycle => write!(f, "a node cannot be parented to its own subtree"),
+            SceneError::SingularParent => write!(f, "parent world transform is not invertible"),
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+/// A hierarchy of nodes whose world transforms are kept up to date as local
+/// transforms and parents change.
+#[derive(Default)]
+pub struct Scene {
+    nodes: Pool<SceneNode, Node>,
+    roots: Vec<NodeHandle>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn roots(&self) -> &[NodeHandle] {
+        &self.roots
+    }
+
+    pub fn node(&self, handle: NodeHandle) -> Option<&Node> {
+        self.nodes.get(handle)
+    }
+
+    pub fn add(
+        &mut self,
+        parent: Option<NodeHandle>,
+        node: Node,
+    ) -> Result<NodeHandle, SceneError> {
+        let parent_world = match parent {
+            Some(parent) => *self.get(parent)?.world(),
+            None => Mat4::IDENTITY,
+        };
+
+        let handle = self.nodes.insert(Node {
+            world: parent_world * node.local,
+            parent,
+            children: Vec::new(),
+            ..node
+        });
+        match parent {
+            Some(parent) => self.get_mut(parent)?.children.push(handle),
+            None => self.roots.push(handle),
+        }
+        Ok(handle)
+    }
+
+    /// Removes a node together with all of its descendants.
+    pub fn remove(&mut self, handle: NodeHandle) -> Result<(), SceneError> {
+        let parent = self.get(handle)?.parent;
+        self.siblings_mut(parent).retain(|&child| child != handle);
+
+        let mut stack = vec![handle];
+        while let Some(handle) = stack.pop() {
+            if let Some(node) = self.nodes.remove(handle) {
+                stack.extend(node.children);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_local(&mut self, handle: NodeHandle, local: Mat4) -> Result<(), SceneError> {
+        self.get_mut(handle)?.local = local;
+        self.update_world(handle);
+        Ok(())
+    }
+
+    pub fn set_mesh(
+        &mut self,
+        handle: NodeHandle,
+        mesh: Option<GpuMesh>,
+    ) -> Result<(), SceneError> {
+        self.get_mut(handle)?.mesh = mesh;
+        Ok(())
+    }
+
+    pub fn set_color(&mut self, handle: NodeHandle, color: [f32; 4]) -> Result<(), SceneError> {
+        self.get_mut(handle)?.color = color;
+        Ok(())
+    }
+
+    /// Hiding a node also hides everything below it.
+    pub fn set_visible(&mut self, handle: NodeHandle, visible: bool) -> Result<(), SceneError> {
+        self.get_mut(handle)?.visible = visible;
+        Ok(())
+    }
+
+    /// Moves a node, with its subtree, under `parent` (or to the root when
+    /// `None`). Its local transform is adjusted so its world transform does not
+    /// change.
+    pub fn set_parent(
+        &mut self,
+        handle: NodeHandle,
+        parent: Option<NodeHandle>,
+    ) -> Result<(), SceneError> {
+        let world = *self.get(handle)?.world();
+        let parent_world = match parent {
+            Some(parent) => {
+                if self.is_ancestor(handle, parent)? {
+                    return Err(SceneError::Cycle);
+                }
+                *self.get(parent)?.world()
+            }
+            None => Mat4::IDENTITY,
+        };
+        let local = parent_world.inverse().ok_or(SceneError::SingularParent)? * world;
+
+        let old_parent = self.get(handle)?.parent;
+        self.siblings_mut(old_parent)
+            .retain(|&child| child != handle);
+        self.siblings_mut(parent).push(handle);
+
+        let node = self.get_mut(handle)?;
+        node.parent = parent;
+        node.local = local;
+        self.update_world(handle);
+        Ok(())
+    }
+
+    /// Calls `visit` on every visible node, parents before children.
+    pub fn visit_visible(&self, mut visit: impl FnMut(NodeHandle, &Node)) {
+        let mut stack: Vec<NodeHandle> = self.roots.iter().rev().copied().collect();
+        while let Some(handle) = stack.pop() {
+            let node = self.nodes.get(handle).expect("scene links are kept valid");
+            if !node.visible {
+                continue;
+            }
+            visit(handle, node);
+            stack.extend(node.children.iter().rev());
+        }
+    }
+
+    // Whether `ancestor` is `node` or lies above it.
+    fn is_ancestor(&self, ancestor: NodeHandle, node: NodeHandle) -> Result<bool, SceneError> {
+        let mut current = Some(node);
+        while let Some(handle) = current {
+            if handle == ancestor {
+                return Ok(true);
+            }
+            current = self.get(handle)?.parent;
+        }
+        Ok(false)
+    }
+
+    fn siblings_mut(&mut self, parent: Option<NodeHandle>) -> &mut Vec<NodeHandle> {
+        match parent {
+            Some(parent) => {
+                &mut self
+                    .nodes
+                    .get_mut(parent)
+                    .expect("scene links are kept valid")
+                    .children
+            }
+            None => &mut self.roots,
+        }
+    }
+
+    // Recomputes world transforms for `handle` and its subtree.
+    fn update_world(&mut self, handle: NodeHandle) {
+        let mut stack = vec![handle];
+        while let Some(handle) = stack.pop() {
+            let node = self.nodes.get(handle).expect("scene links are kept valid");
+            let parent_world = node
+                .parent
+                .and_then(|parent| self.nodes.get(parent))
+                .map_or(Mat4::IDENTITY, |parent| parent.world);
+
+            let node = self.nodes.get_mut(handle).unwrap();
+            node.world = parent_world * node.local;
+            stack.extend(node.children.iter().copied());
+        }
+    }
+
+    fn get(&self, handle: NodeHandle) -> Result<&Node, SceneError> {
+        self.nodes.get(handle).ok_or(SceneError::InvalidNode)
+    }
+
+    fn get_mut(&mut self, handle: NodeHandle) -> Result<&mut Node, SceneError> {
+        self.nodes.get_mut(handle).ok_or(SceneError::InvalidNode)
+    }
+}
+
+/// Draws scenes, batching visible nodes that share a mesh into one instanced
+/// draw each.
+///
+/// Nodes whose mesh is used only once are drawn with a plain `draw`, with
+/// their transform and colour in the `DrawConstants`. Batches put the view
+/// projection in the constants and each node's world transform and colour in
+/// an instance buffer that grows to fit the largest batch.
+pub struct SceneRenderer {
+    constants: ConstantBuffer<DrawConstants>,
+    instance_buffer: Option<(InstanceBufferHandle, usize)>,
+}
+
+impl SceneRenderer {
+    pub fn new(graphics_device: &mut dyn GraphicsDevice) -> crate::Result<SceneRenderer> {
+        Ok(SceneRenderer {
+            constants: ConstantBuffer::new(graphics_device, &DrawConstants::default())?,
+            instance_buffer: None,
+        })
+    }
+
+    /// Must be called between `begin_frame` and `end_frame`.
+    pub fn draw(
+        &mut self,
+        graphics_device: &mut dyn GraphicsDevice,
+        scene: &Scene,
+        view_projection: &Mat4,
+    ) -> crate::Result<()> {
+        let mut batches: Vec<(GpuMesh, Vec<Instance>)> = Vec::new();
+        let mut batch_indices: HashMap<GpuMesh, usize> = HashMap::new();
+        scene.visit_visible(|_, node| {
+            if let Some(&mesh) = node.mesh() {
+                let index = *batch_indices.entry(mesh).or_insert_with(|| {
+                    batches.push((mesh, Vec::new()));
+                    batches.len() - 1
+                });
+                batches[index]
+                    .1
+                    .push(Instance::new(*node.world(), node.color()));
+            }
+        });
+
+        self.constants.bind(graphics_device, DrawConstants::SLOT)?;
+        for (mesh, instances) in &batches {
+            if let [instance] = instances.as_slice() {
+                let constants = DrawConstants {
+                    transform: *view_projection * instance.transform,
+                    color: instance.tint,
+                };
+                self.constants.update(graphics_device, &constants)?;
+                crate::draw(graphics_device, mesh)?;
+            } else {
+                let constants = DrawConstants {
+                    transform: *view_projection,
+                    color: [1.0, 1.0, 1.0, 1.0],
+                };
+                self.constants.update(graphics_device, &constants)?;
+                let instance_buffer = self.upload_instances(graphics_device, instances)?;
+                graphics_device.draw_instanced(
+                    mesh.vertex_buffer,
+                    mesh.index_buffer,
+                    mesh.index_count,
+                    instance_buffer,
+                    instances.len() as u32,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn release(self, graphics_device: &mut dyn GraphicsDevice) -> crate::Result<()> {
+        if let Some((instance_buffer, _)) = self.instance_buffer {
+            graphics_device.destroy_instance_buffer(instance_buffer)?;
+        }
+        self.constants.destroy(graphics_device)
+    }
+
+    fn upload_instances(
+        &mut self,
+        graphics_device: &mut dyn GraphicsDevice,
+        instances: &[Instance],
+    ) -> crate::Result<InstanceBufferHandle> {
+        match self.instance_buffer {
+            Some((handle, capacity)) if capacity >= instances.len() => {
+                graphics_device.update_instance_buffer(handle, instances)?;
+                Ok(handle)
+            }
+            current => {
+                if let Some((handle, _)) = current {
+                    self.instance_buffer = None;
+                    graphics_device.destroy_instance_buffer(handle)?;
+                }
+                let handle = graphics_device.create_instance_buffer(instances)?;
+                self.instance_buffer = Some((handle, instances.len()));
+                Ok(handle)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::{Quat, Vec3};
+    use crate::recording::{Command, RecordingGraphicsDevice};
+    use crate::{cube, upload_mesh};
+
+    fn assert_mat_near(a: &Mat4, b: &Mat4) {
+        for (x, y) in a.to_cols_array().iter().zip(b.to_cols_array().iter()) {
+            assert!((x - y).abs() < 1e-5, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn translation(x: f32, y: f32, z: f32) -> Mat4 {
+        Mat4::translation(Vec3::new(x, y, z))
+    }
+
+    #[test]
+    fn world_transforms_follow_parents() {
+        let mut scene = Scene::new();
+        let root = scene
+            .add(None, Node::new().with_local(translation(1.0, 0.0, 0.0)))
+            .unwrap();
+        let child = scene
+            .add(
+                Some(root),
+                Node::new().with_local(translation(0.0, 2.0, 0.0)),
+            )
+            .unwrap();
+        let grandchild = scene
+            .add(
+                Some(child),
+                Node::new().with_local(translation(0.0, 0.0, 3.0)),
+            )
+            .unwrap();
+        assert_mat_near(
+            scene.node(grandchild).unwrap().world(),
+            &translation(1.0, 2.0, 3.0),
+        );
+
+        scene
+            .set_local(root, Mat4::scale(Vec3::new(2.0, 2.0, 2.0)))
+            .unwrap();
+        assert_mat_near(
+            scene.node(grandchild).unwrap().world(),
+            &(Mat4::scale(Vec3::new(2.0, 2.0, 2.0)) * translation(0.0, 2.0, 3.0)),
+        );
+    }
+
+    #[test]
+    fn reparenting_keeps_world_transform() {
+        let mut scene = Scene::new();
+        let a = scene
+            .add(
+                None,
+                Node::new().with_local(Mat4::from_scale_rotation_translation(
+                    Vec3::new(1.0, 2.0, 1.0),
+                    Quat::from_axis_angle(Vec3::Y, 0.7),
+                    Vec3::new(3.0, 0.0, -1.0),
+                )),
+            )
+            .unwrap();
+        let b = scene
+            .add(None, Node::new().with_local(translation(-2.0, 1.0, 0.0)))
+            .unwrap();
+        let child = scene
+            .add(Some(a), Node::new().with_local(translation(0.5, 0.5, 0.5)))
+            .unwrap();
+        let grandchild = scene
+            .add(
+                Some(child),
+                Node::new().with_local(translation(0.0, 1.0, 0.0)),
+            )
+            .unwrap();
+
+        let world = *scene.node(child).unwrap().world();
+        let grandchild_world = *scene.node(grandchild).unwrap().world();
+
+        for &parent in &[Some(b), None, Some(a)] {
+            scene.set_parent(child, parent).unwrap();
+            assert_eq!(scene.node(child).unwrap().parent(), parent);
+            assert_mat_near(scene.node(child).unwrap().world(), &world);
+            assert_mat_near(scene.node(grandchild).unwrap().world(), &grandchild_world);
+        }
+        assert_eq!(scene.roots(), &[a, b]);
+        assert_eq!(scene.node(a).unwrap().children(), &[child]);
+        assert!(scene.node(b).unwrap().children().is_empty());
+    }
+
+    #[test]
+    fn rejects_cycles_and_stale_handles() {
+        let mut scene = Scene::new();
+        let root = scene.add(None, Node::new()).unwrap();
+        let child = scene.add(Some(root), Node::new()).unwrap();
+        let grandchild = scene.add(Some(child), Node::new()).unwrap();
+
+        assert_eq!(
+            scene.set_parent(root, Some(grandchild)),
+            Err(SceneError::Cycle)
+        );
+        assert_eq!(scene.set_parent(child, Some(child)), Err(SceneError::Cycle));
+
+        scene.remove(child).unwrap();
+        assert_eq!(scene.len(), 1);
+        assert!(scene.node(root).unwrap().children().is_empty());
+        assert_eq!(
+            scene.set_local(grandchild, Mat4::IDENTITY),
+            Err(SceneError::InvalidNode)
+        );
+        assert_eq!(
+            scene.add(Some(child), Node::new()),
+            Err(SceneError::InvalidNode)
+        );
+
+        let singular = scene
+            .add(None, Node::new().with_local(Mat4::scale(Vec3::ZERO)))
+            .unwrap();
+        assert_eq!(
+            scene.set_parent(root, Some(singular)),
+            Err(SceneError::SingularParent)
+        );
+    }
+
+    #[test]
+    fn draw_batches_visible_nodes_by_mesh() {
+        let mut device = RecordingGraphicsDevice::new();
+        let cube = upload_mesh(&mut device, &cube::generate(1)).unwrap();
+        let other = upload_mesh(&mut device, &cube::generate(2)).unwrap();
+        let mut renderer = SceneRenderer::new(&mut device).unwrap();
+
+        let mut scene = Scene::new();
+        let root = scene.add(None, Node::new().with_mesh(cube)).unwrap();
+        let group = scene.add(Some(root), Node::new()).unwrap();
+        let hidden = scene.add(Some(group), Node::new().with_mesh(cube)).unwrap();
+        scene
+            .add(Some(hidden), Node::new().with_mesh(other))
+            .unwrap();
+        let red = [1.0, 0.0, 0.0, 1.0];
+        scene
+            .add(
+                Some(group),
+                Node::new()
+                    .with_mesh(cube)
+                    .with_color(red)
+                    .with_local(translation(1.0, 0.0, 0.0)),
+            )
+            .unwrap();
+        scene
+            .add(Some(group), Node::new().with_mesh(other))
+            .unwrap();
+        scene.set_visible(hidden, false).unwrap();
+        device.begin_frame([0.0, 0.0, 0.0, 1.0], 1.0).unwrap();
+        device.take_commands();
+
+        let view_projection = translation(0.0, 0.0, 5.0);
+        renderer
+            .draw(&mut device, &scene, &view_projection)
+            .unwrap();
+
+        let instances = vec![
+            Instance::new(Mat4::IDENTITY, DrawConstants::DEFAULT_COLOR),
+            Instance::new(translation(1.0, 0.0, 0.0), red),
+        ];
+        let (instance_buffer, _) = renderer.instance_buffer.unwrap();
+        let commands = device.commands();
+        assert!(commands.contains(&Command::CreateInstanceBuffer {
+            handle: instance_buffer,
+            instances,
+        }));
+        assert!(commands.contains(&Command::DrawInstanced {
+            vertex_buffer: cube.vertex_buffer,
+            index_buffer: cube.index_buffer,
+            num_indices: cube.index_count,
+            instance_buffer,
+            num_instances: 2,
+        }));
+        assert!(commands.contains(&Command::Draw {
+            vertex_buffer: other.vertex_buffer,
+            index_buffer: other.index_buffer,
+            num_indices: other.index_count,
+        }));
+        let draws = commands
+            .iter()
+            .filter(|command| {
+                matches!(
+                    command,
+                    Command::Draw { .. } | Command::DrawInstanced { .. }
+                )
+            })
+            .count();
+        assert_eq!(draws, 2);
+
+        // A second frame reuses the instance buffer.
+        device.end_frame().unwrap();
+        device.begin_frame([0.0, 0.0, 0.0, 1.0], 1.0).unwrap();
+        device.take_commands();
+        renderer
+            .draw(&mut device, &scene, &view_projection)
+            .unwrap();
+        assert!(!device
+            .commands()
+            .iter()
+            .any(|command| matches!(command, Command::CreateInstanceBuffer { .. })));
+
+        device.end_frame().unwrap();
+        renderer.release(&mut device).unwrap();
+    }
+}
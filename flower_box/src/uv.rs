@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use crate::mesh::Mesh;
+use crate::normals::face_normal;
+use crate::vertex::{Vertex, VertexSemantic};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+struct Bounds {
+    min: [f32; 3],
+    max: [f32; 3],
+}
+
+impl Bounds {
+    fn of(mesh: &Mesh) -> Bounds {
+        let mut bounds = Bounds {
+            min: [f32::INFINITY; 3],
+            max: [f32::NEG_INFINITY; 3],
+        };
+        for vertex in mesh.vertices() {
+            for k in 0..3 {
+                bounds.min[k] = bounds.min[k].min(vertex.position[k]);
+                bounds.max[k] = bounds.max[k].max(vertex.position[k]);
+            }
+        }
+        bounds
+    }
+
+    fn normalized(&self, position: [f32; 3], axis: usize) -> f32 {
+        let extent = self.max[axis] - self.min[axis];
+        if extent > 0.0 {
+            (position[axis] - self.min[axis]) / extent
+        } else {
+            0.0
+        }
+    }
+
+    fn centre(&self) -> [f32; 3] {
+        [
+            (self.min[0] + self.max[0]) * 0.5,
+            (self.min[1] + self.max[1]) * 0.5,
+            (self.min[2] + self.max[2]) * 0.5,
+        ]
+    }
+}
+
+/// The directions that point right and up in texture space on a plane facing
+/// along `axis`, as seen from the `positive` (or negative) side in the
+/// left-handed Direct3D view convention.
+pub(crate) fn plane_frame(axis: Axis, positive: bool) -> ([f32; 3], [f32; 3]) {
+    let sign = if positive { 1.0 } else { -1.0 };
+    match axis {
+        Axis::X => ([0.0, 0.0, sign], [0.0, 1.0, 0.0]),
+        Axis::Y => ([1.0, 0.0, 0.0], [0.0, 0.0, sign]),
+        Axis::Z => ([-sign, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    }
+}
+
+// Projects `position` onto the plane facing along `axis`, scaled so the mesh
+// bounds cover 0..1 and oriented so the texture reads correctly from the
+// `positive` side.
+fn project(bounds: &Bounds, position: [f32; 3], axis: Axis, positive: bool) -> [f32; 2] {
+    let (right, up) = plane_frame(axis, positive);
+    let along = |direction: [f32; 3]| {
+        let k = direction.iter().position(|&c| c != 0.0).unwrap();
+        let t = bounds.normalized(position, k);
+        if direction[k] > 0.0 {
+            t
+        } else {
+            1.0 - t
+        }
+    };
+    [along(right), 1.0 - along(up)]
+}
+
+/// Rebuilds `mesh` with the texture coordinate of each triangle corner given by
+/// `corner_uv(triangle, corner)`, splitting vertices whose corners disagree.
+fn assign_corner_uvs(mesh: &Mesh, corner_uv: impl Fn(usize, usize) -> [f32; 2]) -> Mesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::with_capacity(mesh.indices().len());
+    let mut split: HashMap<(u32, [u32; 2]), u32> = HashMap::new();
+
+    for (t, triangle) in mesh.indices().chunks_exact(3).enumerate() {
+        for (corner, &index) in triangle.iter().enumerate() {
+            let uv = corner_uv(t, corner);
+            let new_index = *split
+                .entry((index, uv.map(f32::to_bits)))
+                .or_insert_with(|| {
+                    vertices.push(Vertex {
+                        uv,
+                        ..mesh.vertices()[index as usize]
+                    });
+                    (vertices.len() - 1) as u32
+                });
+            indices.push(new_index);
+        }
+    }
+
+    mesh.with_geometry(vertices, indices)
+        .with_attribute(VertexSemantic::TexCoord)
+}
+
+/// Projects every vertex onto the plane perpendicular to `axis`, viewed from
+/// the positive side. Planar mapping is continuous, so no vertices are split.
+pub fn planar(mesh: &Mesh, axis: Axis) -> Mesh {
+    let bounds = Bounds::of(mesh);
+    let vertices = mesh
+        .vertices()
+        .iter()
+        .map(|vertex| Vertex {
+            uv: project(&bounds, vertex.position, axis, true),
+            ..*vertex
+        })
+        .collect();
+
+    mesh.with_geometry(vertices, mesh.indices().to_vec())
+        .with_attribute(VertexSemantic::TexCoord)
+}
+
+/// Projects each triangle along the axis its normal is closest to, like a
+/// texture applied to all six sides of a box around the mesh.
+pub fn box_projection(mesh: &Mesh) -> Mesh {
+    let bounds = Bounds::of(mesh);
+    let positions = |t: usize| {
+        let triangle = &mesh.indices()[t * 3..t * 3 + 3];
+        triangle
+            .iter()
+            .map(move |&i| mesh.vertices()[i as usize].position)
+    };
+
+    let planes: Vec<(Axis, bool)> = (0..mesh.triangle_count())
+        .map(|t| {
+            let p: Vec<[f32; 3]> = positions(t).collect();
+            let normal = face_normal(p[0], p[1], p[2]);
+            let axis = [Axis::X, Axis::Y, Axis::Z]
+                .iter()
+                .copied()
+                .max_by(|a, b| normal[a.index()].abs().total_cmp(&normal[b.index()].abs()))
+                .unwrap();
+            (axis, normal[axis.index()] >= 0.0)
+        })
+        .collect();
+
+    assign_corner_uvs(mesh, |t, corner| {
+        let (axis, positive) = planes[t];
+        let position = positions(t).nth(corner).unwrap();
+        project(&bounds, position, axis, positive)
+    })
+}
+
+/// Maps longitude and latitude around the centre of the mesh bounds to u and
+/// v. Triangles that straddle the `u = 0` seam get their own copies of the
+/// vertices on the far side, with u continuing past 1 so a wrapping sampler
+/// interpolates across the seam. Vertices at the poles take the longitude of
+/// the triangle using them.
+pub fn spherical(mesh: &Mesh) -> Mesh {
+    let centre = Bounds::of(mesh).centre();
+    let direction = |index: u32| {
+        let p = mesh.vertices()[index as usize].position;
+        let d = [p[0] - centre[0], p[1] - centre[1], p[2] - centre[2]];
+        let length = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+        if length > 0.0 {
+            [d[0] / length, d[1] / length, d[2] / length]
+        } else {
+            [0.0, 1.0, 0.0]
+        }
+    };
+    let is_pole = |d: [f32; 3]| d[0].abs() < 1e-6 && d[2].abs() < 1e-6;
+
+    let triangle_uvs: Vec<[[f32; 2]; 3]> = mesh
+        .indices()
+        .chunks_exact(3)
+        .map(|triangle| {
+            let mut uvs = [[0.0; 2]; 3];
+            for (uv, &index) in uvs.iter_mut().zip(triangle) {
+                let d = direction(index);
+                *uv = [
+                    0.5 + d[0].atan2(-d[2]) / (2.0 * PI),
+                    d[1].clamp(-1.0, 1.0).acos() / PI,
+                ];
+            }
+
+            let poles: Vec<bool> = triangle.iter().map(|&i| is_pole(direction(i))).collect();
+            let mut longitudes: Vec<f32> =
+                (0..3).filter(|&k| !poles[k]).map(|k| uvs[k][0]).collect();
+
+            let min = longitudes.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = longitudes.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            if max - min > 0.5 {
+                for (k, uv) in uvs.iter_mut().enumerate() {
+                    if !poles[k] && uv[0] < 0.5 {
+                        uv[0] += 1.0;
+                    }
+                }
+                longitudes = (0..3).filter(|&k| !poles[k]).map(|k| uvs[k][0]).collect();
+            }
+
+            if !longitudes.is_empty() {
+                let average = longitudes.iter().sum::<f32>() / longitudes.len() as f32;
+                for (k, uv) in uvs.iter_mut().enumerate() {
+                    if poles[k] {
+                        uv[0] = average;
+                    }
+                }
+            }
+            uvs
+        })
+        .collect();
+
+    assign_corner_uvs(mesh, |t, corner| triangle_uvs[t][corner])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::{self, UvLayout};
+
+    fn corner_uvs(mesh: &Mesh) -> Vec<[[f32; 2]; 3]> {
+        mesh.indices()
+            .chunks(3)
+            .map(|t| {
+                let uv = |k: usize| mesh.vertices()[t[k] as usize].uv;
+                [uv(0), uv(1), uv(2)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn planar_covers_unit_square() {
+        let mesh = planar(&cube::generate(2), Axis::Z);
+        assert_eq!(mesh.vertices().len(), cube::generate(2).vertices().len());
+        assert!(mesh.has_attribute(VertexSemantic::TexCoord));
+
+        // Seen from +Z in a left-handed view, +X points left.
+        for vertex in mesh.vertices() {
+            let expected = [0.5 - vertex.position[0], 0.5 - vertex.position[1]];
+            assert_eq!(vertex.uv, expected);
+        }
+    }
+
+    #[test]
+    fn box_projection_of_cube_matches_per_face_unwrap() {
+        let projected = box_projection(&cube::generate(3));
+        let unwrapped = cube::generate_textured(3, UvLayout::PerFace);
+
+        for (a, b) in corner_uvs(&projected).iter().zip(corner_uvs(&unwrapped)) {
+            for (uv_a, uv_b) in a.iter().zip(b.iter()) {
+                assert!((uv_a[0] - uv_b[0]).abs() < 1e-6 && (uv_a[1] - uv_b[1]).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn spherical_splits_seam() {
+        let source = cube::generate(4);
+        let mesh = spherical(&source);
+        assert!(mesh.vertices().len() > source.vertices().len());
+
+        for uvs in corner_uvs(&mesh) {
+            let us: Vec<f32> = uvs.iter().map(|uv| uv[0]).collect();
+            let spread = us.iter().copied().fold(f32::NEG_INFINITY, f32::max)
+                - us.iter().copied().fold(f32::INFINITY, f32::min);
+            assert!(spread <= 0.5, "triangle wraps around the seam: {:?}", uvs);
+
+            for uv in uvs.iter() {
+                assert!((0.0..=1.5).contains(&uv[0]));
+                assert!((0.0..=1.0).contains(&uv[1]));
+            }
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{Error, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VertexSemantic {
+    Position,
+    Normal,
+    TexCoord,
+    Color,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VertexFormat {
+    Float32x2,
+    Float32x3,
+    Float32x4,
+}
+
+impl VertexFormat {
+    pub fn components(self) -> u32 {
+        match self {
+            VertexFormat::Float32x2 => 2,
+            VertexFormat::Float32x3 => 3,
+            VertexFormat::Float32x4 => 4,
+        }
+    }
+
+    pub fn size(self) -> u32 {
+        self.components() * std::mem::size_of::<f32>() as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VertexAttribute {
+    pub semantic: VertexSemantic,
+    pub format: VertexFormat,
+    pub offset: u32,
+}
+
+/// Describes how the attributes of one vertex are interleaved in a vertex
+/// buffer. Backends build their input layouts and strides from this.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VertexLayout {
+    attributes: Vec<VertexAttribute>,
+    stride: u32,
+}
+
+impl VertexLayout {
+    pub fn new() -> VertexLayout {
+        VertexLayout::default()
+    }
+
+    /// Appends an attribute directly after the previous one.
+    pub fn with(mut self, semantic: VertexSemantic, format: VertexFormat) -> VertexLayout {
+        self.attributes.push(VertexAttribute {
+            semantic,
+            format,
+            offset: self.stride,
+        });
+        self.stride += format.size();
+        self
+    }
+
+    pub fn attributes(&self) -> &[VertexAttribute] {
+        &self.attributes
+    }
+
+    pub fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    pub fn attribute(&self, semantic: VertexSemantic) -> Option<&VertexAttribute> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.semantic == semantic)
+    }
+
+    pub fn contains(&self, semantic: VertexSemantic) -> bool {
+        self.attribute(semantic).is_some()
+    }
+
+    /// Checks that `data` holds whole vertices of this layout and returns how
+    /// many there are.
+    pub fn vertex_count(&self, data: &[u8]) -> Result<u32> {
+        if !self.contains(VertexSemantic::Position) {
+            return Err(Error::InvalidVertexData(
+                "layout has no position attribute".into(),
+            ));
+        }
+        if !data.len().is_multiple_of(self.stride as usize) {
+            return Err(Error::InvalidVertexData(format!(
+                "{} bytes is not a whole number of {} byte vertices",
+                data.len(),
+                self.stride
+            )));
+        }
+        Ok((data.len() / self.stride as usize) as u32)
+    }
+
+    /// Checks that `data` holds whole vertices of this layout that fit in a
+    /// buffer of `capacity` vertices.
+    pub fn validate_update(&self, capacity: u32, data: &[u8]) -> Result<()> {
+        let count = self.vertex_count(data)?;
+        if count > capacity {
+            return Err(Error::InvalidVertexData(format!(
+                "update of {} vertices exceeds the {} vertex buffer",
+                count, capacity
+            )));
+        }
+        Ok(())
+    }
+
+    /// Reads one attribute of the vertex at `index` in `data`, filling missing
+    /// components with `(0, 0, 0, 1)` the way input assemblers do.
+    pub fn read(&self, data: &[u8], index: usize, semantic: VertexSemantic) -> Option<[f32; 4]> {
+        let attribute = self.attribute(semantic)?;
+        let start = index * self.stride as usize + attribute.offset as usize;
+        let bytes = data.get(start..start + attribute.format.size() as usize)?;
+
+        let mut value = [0.0, 0.0, 0.0, 1.0];
+        for (component, chunk) in value.iter_mut().zip(bytes.chunks_exact(4)) {
+            let mut word = [0; 4];
+            word.copy_from_slice(chunk);
+            *component = f32::from_le_bytes(word);
+        }
+        Some(value)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl Vertex {
+    pub const fn new(x: f32, y: f32, z: f32) -> Vertex {
+        Vertex {
+            position: [x, y, z],
+            normal: [0.0, 0.0, 0.0],
+            uv: [0.0, 0.0],
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+
+    pub fn format(semantic: VertexSemantic) -> VertexFormat {
+        match semantic {
+            VertexSemantic::Position | VertexSemantic::Normal => VertexFormat::Float32x3,
+            VertexSemantic::TexCoord => VertexFormat::Float32x2,
+            VertexSemantic::Color => VertexFormat::Float32x4,
+        }
+    }
+
+    /// A layout holding the given attributes of `Vertex`, in order.
+    pub fn layout(semantics: &[VertexSemantic]) -> VertexLayout {
+        semantics
+            .iter()
+            .fold(VertexLayout::new(), |layout, &semantic| {
+                layout.with(semantic, Vertex::format(semantic))
+            })
+    }
+
+    pub fn attribute(&self, semantic: VertexSemantic) -> &[f32] {
+        match semantic {
+            VertexSemantic::Position => &self.position,
+            VertexSemantic::Normal => &self.normal,
+            VertexSemantic::TexCoord => &self.uv,
+            VertexSemantic::Color => &self.color,
+        }
+    }
+
+    /// Interleaves `vertices` into the byte layout described by `layout`.
+    pub fn write(vertices: &[Vertex], layout: &VertexLayout) -> Vec<u8> {
+        let mut data = vec![0; vertices.len() * layout.stride() as usize];
+        for (vertex, bytes) in vertices
+            .iter()
+            .zip(data.chunks_exact_mut(layout.stride().max(1) as usize))
+        {
+            for attribute in layout.attributes() {
+                let values = vertex.attribute(attribute.semantic);
+                let offset = attribute.offset as usize;
+                for (i, value) in values
+                    .iter()
+                    .take(attribute.format.components() as usize)
+                    .enumerate()
+                {
+                    bytes[offset + i * 4..offset + i * 4 + 4].copy_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_offsets_and_stride() {
+        let layout = Vertex::layout(&[
+            VertexSemantic::Position,
+            VertexSemantic::Normal,
+            VertexSemantic::TexCoord,
+            VertexSemantic::Color,
+        ]);
+
+        let offsets: Vec<u32> = layout.attributes().iter().map(|a| a.offset).collect();
+        assert_eq!(offsets, vec![0, 12, 24, 32]);
+        assert_eq!(layout.stride(), 48);
+        assert_eq!(
+            layout.stride() as usize,
+            std::mem::size_of::<Vertex>(),
+            "a full layout matches the in-memory Vertex"
+        );
+    }
+
+    #[test]
+    fn write_then_read() {
+        let mut vertex = Vertex::new(1.0, 2.0, 3.0);
+        vertex.uv = [0.25, 0.75];
+        let layout = Vertex::layout(&[VertexSemantic::TexCoord, VertexSemantic::Position]);
+
+        let data = Vertex::write(&[Vertex::new(0.0, 0.0, 0.0), vertex], &layout);
+        assert_eq!(data.len(), 40);
+        assert_eq!(
+            layout.read(&data, 1, VertexSemantic::Position),
+            Some([1.0, 2.0, 3.0, 1.0])
+        );
+        assert_eq!(
+            layout.read(&data, 1, VertexSemantic::TexCoord),
+            Some([0.25, 0.75, 0.0, 1.0])
+        );
+        assert_eq!(layout.read(&data, 1, VertexSemantic::Normal), None);
+        assert_eq!(layout.read(&data, 2, VertexSemantic::Position), None);
+    }
+}
@@ -0,0 +1,177 @@
+use std::f32::consts::FRAC_PI_4;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use flower_box::camera::Camera;
+use flower_box::capture::{self, Capture};
+use flower_box::constant::{ConstantBuffer, DrawConstants};
+use flower_box::cpu::{CpuGraphicsDevice, Framebuffer};
+use flower_box::cube;
+use flower_box::math::Vec3;
+use flower_box::{draw, render_frame, upload_mesh};
+
+const USAGE: &str = "usage: headless <width> <height> <output.png> [capture]";
+// 8192 x 8192, which already needs half a gigabyte of colour and depth.
+const MAX_PIXEL_COUNT: u64 = 1 << 26;
+
+#[derive(Debug, PartialEq)]
+struct Args {
+    width: u32,
+    height: u32,
+    output: PathBuf,
+    capture: Option<PathBuf>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    if args.len() != 3 && args.len() != 4 {
+        return Err(USAGE.to_string());
+    }
+
+    let parse_dimension = |name: &str, value: &str| match value.parse::<u32>() {
+        Ok(dimension) if dimension > 0 => Ok(dimension),
+        _ => Err(format!(
+            "{} must be a positive integer, got '{}'",
+            name, value
+        )),
+    };
+
+    let width = parse_dimension("width", &args[0])?;
+    let height = parse_dimension("height", &args[1])?;
+    if width as u64 * height as u64 > MAX_PIXEL_COUNT {
+        return Err(format!(
+            "{}x{} is too large, at most {} pixels are supported\n{}",
+            width, height, MAX_PIXEL_COUNT, USAGE
+        ));
+    }
+
+    Ok(Args {
+        width,
+        height,
+        output: PathBuf::from(&args[2]),
+        capture: args.get(3).map(PathBuf::from),
+    })
+}
+
+fn write_png(path: &Path, framebuffer: &Framebuffer) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        framebuffer.width(),
+        framebuffer.height(),
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(framebuffer.color())
+        .map_err(|e| e.to_string())
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let mut graphics_device = CpuGraphicsDevice::new(args.width, args.height);
+
+    match &args.capture {
+        Some(path) => {
+            let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let capture =
+                Capture::decode(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+            capture::replay(&capture, &mut graphics_device).map_err(|e| e.to_string())?;
+        }
+        None => {
+            let mesh = cube::unit();
+            let gpu_mesh = upload_mesh(&mut graphics_device, &mesh).map_err(|e| e.to_string())?;
+
+            // Same view as the desktop viewer.
+            let mut camera = Camera::perspective(
+                FRAC_PI_4,
+                args.width as f32 / args.height as f32,
+                0.1,
+                100.0,
+            );
+            camera.look_at(Vec3::new(1.5, 1.5, -3.0), Vec3::ZERO, Vec3::Y);
+            let constants = DrawConstants {
+                transform: camera.view_projection(),
+                ..DrawConstants::default()
+            };
+            ConstantBuffer::new(&mut graphics_device, &constants)
+                .and_then(|buffer| buffer.bind(&mut graphics_device, DrawConstants::SLOT))
+                .map_err(|e| e.to_string())?;
+
+            render_frame(&mut graphics_device, [0.0, 0.0, 0.0, 1.0], |device| {
+                draw(device, &gpu_mesh)
+            })
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    write_png(&args.output, graphics_device.framebuffer())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = parse_args(&args).and_then(run);
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn parses_dimensions_and_paths() {
+        assert_eq!(
+            parse(&["640", "480", "out.png"]),
+            Ok(Args {
+                width: 640,
+                height: 480,
+                output: PathBuf::from("out.png"),
+                capture: None,
+            })
+        );
+        assert_eq!(
+            parse(&["8192", "8192", "out.png", "frame.cap"])
+                .unwrap()
+                .capture,
+            Some(PathBuf::from("frame.cap"))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(parse(&["640", "480"]), Err(USAGE.to_string()));
+        assert_eq!(
+            parse(&["640", "480", "out.png", "frame.cap", "extra"]),
+            Err(USAGE.to_string())
+        );
+        assert_eq!(
+            parse(&["0", "480", "out.png"]),
+            Err("width must be a positive integer, got '0'".to_string())
+        );
+        assert_eq!(
+            parse(&["640", "tall", "out.png"]),
+            Err("height must be a positive integer, got 'tall'".to_string())
+        );
+        assert!(parse(&["-1", "480", "out.png"]).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_targets() {
+        let error = parse(&["70000", "70000", "out.png"]).unwrap_err();
+        assert!(error.starts_with("70000x70000 is too large"));
+        assert!(parse(&["8193", "8192", "out.png"]).is_err());
+        assert!(parse(&["4294967295", "1", "out.png"]).is_err());
+    }
+}